@@ -1,9 +1,27 @@
 // build.rs for embedding Windows icons and version info using winres
 fn main() {
+    // Resources are only embedded into Windows executables
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() != Ok("windows") {
+        return;
+    }
+
     let mut res = winres::WindowsResource::new();
     res.set_icon("assets/icons/syncthing_green.ico"); // Use green icon as main app icon
     res.set_icon_with_id("assets/icons/syncthing_green.ico", "syncthing_green");
     res.set_icon_with_id("assets/icons/syncthing_red.ico", "syncthing_red");
+    for state in [
+        "starting",
+        "syncing",
+        "paused",
+        "no_peers",
+        "error",
+        "unreachable",
+    ] {
+        res.set_icon_with_id(
+            &format!("assets/icons/syncthing_{state}.ico"),
+            &format!("syncthing_{state}"),
+        );
+    }
     res.set_manifest_file("assets/app.manifest"); // Optional: if you want a manifest
     res.compile().expect("Failed to compile Windows resources");
 }
@@ -10,6 +10,11 @@ pub struct AppDirs {
 
 const CONFIG_FILE_NAME: &str = "configuration.json";
 const LOG_FILE_NAME: &str = "syncthingers.log";
+const SYNCTHING_LOG_FILE_NAME: &str = "syncthing.log";
+#[cfg(not(target_os = "windows"))]
+const LOCK_FILE_NAME: &str = "syncthingers.lock";
+#[cfg(not(target_os = "windows"))]
+const SOCKET_FILE_NAME: &str = "syncthingers.sock";
 
 impl AppDirs {
     /// Create a new AppDirs instance, using the provided override or the default app data dir.
@@ -51,6 +56,38 @@ impl AppDirs {
     pub fn log_file_path(&self) -> PathBuf {
         self.base_dir.join(LOG_FILE_NAME)
     }
+
+    /// Get the path to the file capturing Syncthing's own output.
+    pub fn syncthing_log_file_path(&self) -> PathBuf {
+        self.base_dir.join(SYNCTHING_LOG_FILE_NAME)
+    }
+
+    /// Get the path to the file capturing the output of a named Syncthing instance.
+    pub fn instance_log_file_path(&self, instance_name: &str) -> PathBuf {
+        let slug: String = instance_name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '-'
+                }
+            })
+            .collect();
+        self.base_dir.join(format!("syncthing-{slug}.log"))
+    }
+
+    /// Get the path to the singleton lock file.
+    #[cfg(not(target_os = "windows"))]
+    pub fn lock_file_path(&self) -> PathBuf {
+        self.base_dir.join(LOCK_FILE_NAME)
+    }
+
+    /// Get the path to the socket used to reach the running instance.
+    #[cfg(not(target_os = "windows"))]
+    pub fn socket_path(&self) -> PathBuf {
+        self.base_dir.join(SOCKET_FILE_NAME)
+    }
 }
 
 #[cfg(test)]
@@ -1,296 +1,337 @@
 use crate::app_dirs::AppDirs;
-use crate::config::{Config, ProcessClosureBehavior};
-use crate::error_handling::{self, AppError};
-use crate::process;
-use crate::process::SyncthingProcess;
-use crate::utils::is_test_environment;
+use crate::config::{Config, DEFAULT_INSTANCE_NAME};
+use crate::dialogs::{Dialogs, HeadlessDialogs};
+use crate::error_handling::AppError;
+use crate::events::EventBus;
+use crate::instance::Instance;
+use crate::logging;
+use crate::process::Shutdown;
 
-use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
 
 /// Syncthingers application state.
 pub struct AppState {
     pub config: Config,
-    pub syncthing_process: Option<SyncthingProcess>,
     pub app_dirs: AppDirs,
+    /// The configured Syncthing instances, in configuration order.
+    pub instances: Vec<Instance>,
+    /// Syncthing events streamed by `events::spawn()` and process events of the instances
+    pub events: EventBus,
+    /// Dialogs shown to the user; answers automatically unless replaced by `set_dialogs()`
+    pub dialogs: Arc<dyn Dialogs>,
 }
 
 impl AppState {
     pub fn new(config: Config, app_dirs: AppDirs) -> Self {
-        // Skip process detection for test environments
-        let syncthing_process = if is_test_environment(&config.syncthing_path) {
-            log::debug!(
-                "Skipping process detection for test path: {}",
-                config.syncthing_path
-            );
-            None
-        } else {
-            // Try to detect and attach to an external Syncthing process
-            match SyncthingProcess::detect_process(&config.syncthing_path, true) {
-                Ok(Some(proc)) => {
-                    log::info!("Attached to external Syncthing process.");
-                    Some(proc)
-                }
-                Ok(None) => None,
-                Err(e) => {
-                    log::warn!("Failed to detect external Syncthing process: {}", e);
-                    None
-                }
-            }
-        };
+        let events = EventBus::default();
+        let dialogs: Arc<dyn Dialogs> = Arc::new(HeadlessDialogs { answer: false });
+        let instances = config
+            .instance_configs()
+            .into_iter()
+            .map(|instance| {
+                let log_file = Self::instance_log_file(&app_dirs, &instance.name);
+                let mut instance = Instance::new(instance, &config, log_file);
+                instance.events = events.clone();
+                instance.dialogs = dialogs.clone();
+                instance
+            })
+            .collect();
         Self {
             config,
-            syncthing_process,
             app_dirs,
+            instances,
+            events,
+            dialogs,
         }
     }
 
-    /// Attempts to detect and attach to an external Syncthing process, updating state.
-    pub fn detect_and_attach_external(&mut self) -> Result<bool, AppError> {
-        match SyncthingProcess::detect_process(&self.config.syncthing_path, true) {
-            Ok(Some(proc)) => {
-                self.syncthing_process = Some(proc);
-                log::info!("Attached to external Syncthing process.");
-                Ok(true)
-            }
-            Ok(None) => Ok(false),
-            Err(e) => {
-                log::warn!("Failed to detect external Syncthing process: {}", e);
-                Err(AppError::Process(format!(
-                    "Failed to detect external Syncthing: {}",
-                    e
-                )))
-            }
+    /// Replaces the dialogs used by the application and all instances.
+    pub fn set_dialogs(&mut self, dialogs: Arc<dyn Dialogs>) {
+        for instance in &mut self.instances {
+            instance.dialogs = dialogs.clone();
         }
+        self.dialogs = dialogs;
     }
 
-    /// Checks if Syncthing is currently running.
-    /// This method only checks tracked processes and does not attempt to detect or attach to external instances.
-    pub fn syncthing_running(&mut self) -> bool {
-        // Check our tracked process state
-        if let Some(proc) = &mut self.syncthing_process {
-            // We already know about a process - check if it's still running
-            if proc.is_running() {
-                return true;
-            } else {
-                // Process is no longer running, clear our reference to it
-                self.syncthing_process = None;
-                return false;
-            }
+    /// Marks all instances as detached: a Syncthing started from now on keeps running
+    /// after this process exits, as for command-line control commands.
+    pub fn set_detached(&mut self) {
+        for instance in &mut self.instances {
+            instance.detached = true;
         }
-
-        // No process being tracked
-        false
     }
 
-    /// Starts the Syncthing process if it's not already running.
-    pub fn start_syncthing(&mut self) -> Result<(), AppError> {
-        if self.syncthing_running() {
-            return Ok(());
+    /// File capturing the output of the given instance's Syncthing.
+    fn instance_log_file(app_dirs: &AppDirs, name: &str) -> std::path::PathBuf {
+        if name == DEFAULT_INSTANCE_NAME {
+            app_dirs.syncthing_log_file_path()
+        } else {
+            app_dirs.instance_log_file_path(name)
         }
+    }
 
-        if !Path::new(&self.config.syncthing_path).exists() {
-            log::error!(
-                "Syncthing executable not found at path: {}",
-                self.config.syncthing_path
+    /// Replaces the active configuration with a reloaded one.
+    ///
+    /// Settings that are read on demand (such as `web_ui_url`) and the log level take
+    /// effect immediately. Instances are matched by name: new instances are added,
+    /// removed ones are closed according to their closure behavior, and running managed
+    /// processes whose launch settings changed are marked as requiring a restart.
+    /// Returns true if any instance requires a restart.
+    pub fn apply_config(&mut self, new_config: Config) -> bool {
+        if new_config.log_level != self.config.log_level {
+            log::info!(
+                "Log level changed from '{}' to '{}'",
+                self.config.log_level,
+                new_config.log_level
             );
+            logging::set_log_level(logging::log_level_from_str(&new_config.log_level));
+        }
 
-            if error_handling::show_native_yes_no_question_dialog(
-                "Configured path for Syncthing does not exist. Would you like to autodetect a new path?",
-                "Syncthing path not found",
-            ) {
-                match Config::find_syncthing_in_path() {
-                    Some(path) => {
-                        log::info!("Autodetected Syncthing path: {}", path);
-                        self.config.syncthing_path = path;
-                    }
-                    None => {
-                        log::error!("Could not autodetect Syncthing path");
-                    }
+        let mut previous = std::mem::take(&mut self.instances);
+        for instance_config in new_config.instance_configs() {
+            let instance = match previous
+                .iter()
+                .position(|instance| instance.name() == instance_config.name)
+            {
+                Some(index) => {
+                    let mut instance = previous.remove(index);
+                    instance.apply_config(instance_config, &new_config);
+                    instance
                 }
-            }
+                None => {
+                    log::info!("Instance {} added", instance_config.name);
+                    let log_file = Self::instance_log_file(&self.app_dirs, &instance_config.name);
+                    let mut instance = Instance::new(instance_config, &new_config, log_file);
+                    instance.events = self.events.clone();
+                    instance.dialogs = self.dialogs.clone();
+                    instance
+                }
+            };
+            self.instances.push(instance);
+        }
+        for mut removed in previous {
+            log::info!("Instance {} removed", removed.name());
+            let shutdowns = removed.begin_exit_closure();
+            // Reloads hold the state lock, so the processes are waited for elsewhere
+            thread::spawn(move || {
+                for shutdown in shutdowns {
+                    shutdown.wait();
+                }
+            });
         }
 
-        let exe_path = &self.config.syncthing_path;
-        let args = &self.config.startup_args;
-        let mut process = SyncthingProcess::new(exe_path);
-        process
-            .start(args)
-            .map_err(|e| AppError::Process(format!("Failed to start Syncthing: {}", e)))?;
-        self.syncthing_process = Some(process);
+        self.config = new_config;
+        log::info!("Configuration reloaded");
 
-        log::info!("Syncthing process started successfully.");
-        Ok(())
+        self.restart_required()
     }
 
-    /// Stops the Syncthing process if it's running.
-    pub fn stop_syncthing(&mut self) -> Result<(), AppError> {
-        match &self.syncthing_process {
-            Some(process) => {
-                if process.started_by_app {
-                    // For app-started processes, use the normal stop method
-                    self.stop_managed_syncthing_processes()?;
-                } else {
-                    // For external processes, use the external process stopping function
-                    self.stop_external_syncthing_processes()?;
-                }
-            }
-            None => {
-                log::info!("No Syncthing process is currently tracked.");
-            }
+    /// Returns the instance with the given name.
+    pub fn instance_mut(&mut self, name: &str) -> Result<&mut Instance, AppError> {
+        let names = self.instance_names();
+        self.instances
+            .iter_mut()
+            .find(|i| i.name() == name)
+            .ok_or_else(|| {
+                AppError::Command(format!(
+                    "Unknown instance '{}'. Configured instances: {}",
+                    name,
+                    names.join(", ")
+                ))
+            })
+    }
+
+    /// Names of the instances selected by `name`, or of all instances if `name` is `None`.
+    pub fn selected_instance_names(&mut self, name: Option<&str>) -> Result<Vec<String>, AppError> {
+        match name {
+            Some(name) => Ok(vec![self.instance_mut(name)?.name().to_string()]),
+            None => Ok(self.instance_names()),
         }
+    }
 
-        self.syncthing_process = None;
-        Ok(())
+    /// Names of all configured instances.
+    pub fn instance_names(&self) -> Vec<String> {
+        self.instances
+            .iter()
+            .map(|i| i.name().to_string())
+            .collect()
+    }
+
+    /// Whether more than one instance is configured, so output must name the instance.
+    pub fn has_multiple_instances(&self) -> bool {
+        self.instances.len() > 1
+    }
+
+    /// Whether any instance requires a restart to apply configuration changes.
+    pub fn restart_required(&self) -> bool {
+        self.instances.iter().any(|i| i.restart_required)
     }
 
-    /// Handles process closure on application exit based on configuration.
+    /// Detects exits of managed processes and performs due automatic restarts.
     ///
-    /// This method implements the configured process closure behavior:
-    /// - CloseAll: Stops both managed and external Syncthing processes
-    /// - CloseManaged: Only stops processes started by this app
-    /// - DontClose: Leaves all processes running
-    pub fn handle_exit_closure(&mut self) -> Result<(), AppError> {
-        match self.config.process_closure_behavior {
-            ProcessClosureBehavior::CloseAll => {
-                log::info!("Exit closure behavior: Closing all Syncthing processes");
-                _ = self.stop_managed_syncthing_processes();
-                _ = self.stop_external_syncthing_processes();
-            }
-            ProcessClosureBehavior::CloseManaged => {
-                log::info!("Exit closure behavior: Closing only managed processes");
-                _ = self.stop_managed_syncthing_processes();
-            }
-            ProcessClosureBehavior::DontClose => {
-                log::info!("Exit closure behavior: Leaving all processes running");
-            }
+    /// Called periodically by the monitoring thread.
+    pub fn supervise(&mut self) {
+        for instance in &mut self.instances {
+            instance.supervise();
         }
-
-        self.syncthing_process = None;
-        Ok(())
     }
 
-    /// Stops only processes that were started by this application.
-    fn stop_managed_syncthing_processes(&mut self) -> Result<(), AppError> {
-        match &mut self.syncthing_process {
-            Some(process) => {
-                if process.started_by_app {
-                    process.stop().map_err(|e| {
-                        AppError::Process(format!("Failed to stop Syncthing: {}", e))
-                    })?;
-                    log::info!("App-started Syncthing process stopped successfully.");
-                }
-            }
-            None => {
-                log::info!("No Syncthing process is currently tracked.");
+    /// Stops Syncthing of the named instance, returning `false` if it was not running.
+    ///
+    /// The state lock is released while waiting for the processes to exit, which can
+    /// take up to the shutdown grace period.
+    pub fn stop_instance(app_state: &Mutex<Self>, name: &str) -> Result<bool, AppError> {
+        let shutdown = {
+            let mut state = Self::lock(app_state)?;
+            let instance = state.instance_mut(name)?;
+            if !instance.syncthing_running() {
+                return Ok(false);
             }
+            instance.begin_stop_syncthing()?
+        };
+        shutdown.wait();
+        Ok(true)
+    }
+
+    /// Restarts Syncthing of the named instance, releasing the state lock while the
+    /// old processes exit.
+    pub fn restart_instance(app_state: &Mutex<Self>, name: &str) -> Result<(), AppError> {
+        log::info!("Restarting Syncthing ({})", name);
+        let shutdown = Self::lock(app_state)?
+            .instance_mut(name)?
+            .begin_stop_syncthing()?;
+        shutdown.wait();
+        Self::lock(app_state)?.instance_mut(name)?.start_syncthing()
+    }
+
+    fn lock(app_state: &Mutex<Self>) -> Result<MutexGuard<'_, Self>, AppError> {
+        app_state
+            .lock()
+            .map_err(|_| AppError::Command("Failed to lock application state".to_string()))
+    }
+
+    /// Handles process closure on application exit based on each instance's configuration.
+    pub fn handle_exit_closure(&mut self) -> Result<(), AppError> {
+        for instance in &mut self.instances {
+            instance.handle_exit_closure()?;
         }
         Ok(())
     }
 
-    /// Attempts to stop external Syncthing processes.
-    fn stop_external_syncthing_processes(&self) -> Result<(), AppError> {
-        // Use the process module function to stop external processes
-        process::stop_external_syncthing_processes(&self.config.syncthing_path).map_err(|e| {
-            AppError::Process(format!(
-                "Failed to stop external Syncthing processes: {}",
-                e
-            ))
-        })?;
-        log::info!("Stopped external Syncthing processes");
+    /// Like `handle_exit_closure`, but releases the state lock while waiting for the
+    /// processes to exit.
+    pub fn exit_closure(app_state: &Mutex<Self>) -> Result<(), AppError> {
+        let shutdowns: Vec<Shutdown> = Self::lock(app_state)?
+            .instances
+            .iter_mut()
+            .flat_map(Instance::begin_exit_closure)
+            .collect();
+        for shutdown in shutdowns {
+            shutdown.wait();
+        }
         Ok(())
     }
 
-    /// Checks and auto-starts Syncthing if needed based on configuration.
+    /// Auto-starts the instances configured to do so.
+    ///
+    /// All instances are attempted; the first error is returned.
     pub fn check_and_autostart_syncthing(&mut self) -> Result<(), AppError> {
-        if self.config.auto_launch_internal {
-            // If not running, start internal syncthing
-            if !self.syncthing_running() {
-                log::info!(
-                    "Auto-launching internal Syncthing as no external process is running and auto_launch_internal is enabled."
-                );
-                self.start_syncthing()?;
-            } else {
-                log::info!("Syncthing is already running, auto-launch not needed.");
+        let mut result = Ok(());
+        for instance in &mut self.instances {
+            if let Err(e) = instance.check_and_autostart_syncthing() {
+                log::warn!("Auto-launch of {} failed: {}", instance.name(), e);
+                if result.is_ok() {
+                    result = Err(e);
+                }
             }
-        } else {
-            log::debug!("Auto-launch internal Syncthing is disabled in config.");
         }
-        Ok(())
+        result
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::ProcessClosureBehavior;
+    use crate::config::InstanceConfig;
 
-    fn create_test_config(closure_behavior: ProcessClosureBehavior) -> Config {
+    fn create_test_config() -> Config {
         Config {
-            process_closure_behavior: closure_behavior,
+            syncthing_path: "nonexistent_test_syncthing.exe".to_string(),
             ..Config::default()
         }
     }
 
-    #[test]
-    fn test_handle_exit_closure_dont_close() {
-        let config = create_test_config(ProcessClosureBehavior::DontClose);
-        let mut app_state = AppState::new(config, AppDirs::new(None).unwrap());
-
-        // Should succeed without doing anything
-        let result = app_state.handle_exit_closure();
-        assert!(result.is_ok());
-    }
-
-    #[test]
-    fn test_handle_exit_closure_close_managed_no_process() {
-        let config = create_test_config(ProcessClosureBehavior::CloseManaged);
-        let mut app_state = AppState::new(config, AppDirs::new(None).unwrap());
-
-        // Should succeed when no process is running
-        let result = app_state.handle_exit_closure();
-        assert!(result.is_ok());
+    fn test_instance(name: &str) -> InstanceConfig {
+        InstanceConfig {
+            name: name.to_string(),
+            syncthing_path: format!("nonexistent_test_{name}.exe"),
+            ..InstanceConfig::default()
+        }
     }
 
     #[test]
-    fn test_handle_exit_closure_close_managed_with_external() {
-        let config = create_test_config(ProcessClosureBehavior::CloseManaged);
-        let mut app_state = AppState::new(config, AppDirs::new(None).unwrap());
-        // Simulate an external process
-        app_state.syncthing_process = Some(SyncthingProcess::mock_for_testing(false));
-
-        // Should succeed and leave external process running
-        let result = app_state.handle_exit_closure();
-        assert!(result.is_ok());
+    fn test_apply_config_without_running_process_needs_no_restart() {
+        let config = create_test_config();
+        let mut app_state = AppState::new(config.clone(), AppDirs::new(None).unwrap());
+
+        let new_config = Config {
+            startup_args: vec!["--no-browser".to_string()],
+            web_ui_url: "http://localhost:9999".to_string(),
+            ..config
+        };
+        assert!(!app_state.apply_config(new_config));
+        let instance = &app_state.instances[0];
+        assert_eq!(instance.config.web_ui_url, "http://localhost:9999");
+        assert_eq!(instance.config.startup_args, vec!["--no-browser"]);
     }
 
     #[test]
-    fn test_handle_exit_closure_close_managed_with_managed() {
-        let config = create_test_config(ProcessClosureBehavior::CloseManaged);
-        let mut app_state = AppState::new(config, AppDirs::new(None).unwrap());
-
-        // Simulate a managed process
-        app_state.syncthing_process = Some(SyncthingProcess::mock_for_testing(true));
-
-        // Should succeed and stop the managed process
-        let result = app_state.handle_exit_closure();
-        assert!(result.is_ok());
-        assert!(app_state.syncthing_process.is_none());
+    fn test_apply_config_matches_instances_by_name() {
+        let config = Config {
+            instances: vec![test_instance("work"), test_instance("personal")],
+            ..create_test_config()
+        };
+        let mut app_state = AppState::new(config.clone(), AppDirs::new(None).unwrap());
+        assert_eq!(app_state.instance_names(), vec!["work", "personal"]);
+        app_state.instances[1].restart_required = true;
+
+        let new_config = Config {
+            instances: vec![
+                InstanceConfig {
+                    home: Some("/tmp/personal".to_string()),
+                    ..test_instance("personal")
+                },
+                test_instance("music"),
+            ],
+            ..config
+        };
+        // The existing "personal" instance keeps its state and gets the new settings
+        assert!(app_state.apply_config(new_config));
+        assert_eq!(app_state.instance_names(), vec!["personal", "music"]);
+        assert!(app_state.instances[0].restart_required);
+        assert_eq!(
+            app_state.instances[0].config.home.as_deref(),
+            Some("/tmp/personal")
+        );
+        assert!(!app_state.instances[1].restart_required);
     }
 
     #[test]
-    fn test_handle_exit_closure_close_all() {
-        let config = create_test_config(ProcessClosureBehavior::CloseAll);
+    fn test_select_unknown_instance_lists_names() {
+        let config = Config {
+            instances: vec![test_instance("work")],
+            ..create_test_config()
+        };
         let mut app_state = AppState::new(config, AppDirs::new(None).unwrap());
 
-        // Simulate a process
-        app_state.syncthing_process = Some(SyncthingProcess::mock_for_testing(false));
-
-        // For CloseAll behavior, it calls stop_all_syncthing_processes which:
-        // 1. Calls stop_syncthing() to clean up tracked process (sets syncthing_process = None)
-        // 2. Calls stop_external_syncthing_processes() (skipped in test env)
-        let result = app_state.handle_exit_closure();
-        assert!(result.is_ok());
-        // The process should be None after cleanup since stop_syncthing() was called
-        assert!(app_state.syncthing_process.is_none());
+        assert_eq!(
+            app_state.selected_instance_names(None).unwrap(),
+            vec!["work"]
+        );
+        match app_state.selected_instance_names(Some("home")) {
+            Err(AppError::Command(message)) => assert!(message.contains("work")),
+            _ => panic!("Expected unknown instance error"),
+        }
     }
 }
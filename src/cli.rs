@@ -0,0 +1,278 @@
+use clap::builder::PossibleValuesParser;
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::conflicts::Resolution;
+use crate::error_handling::AppError;
+use crate::ipc::IpcRequest;
+use crate::logging::LOG_LEVELS;
+
+/// Command-line interface of Syncthingers.
+#[derive(Debug, Parser)]
+#[command(
+    name = "syncthingers",
+    version,
+    about = "System tray manager for Syncthing"
+)]
+pub struct Cli {
+    /// Initial log level
+    #[arg(long, global = true, value_name = "LEVEL", value_parser = PossibleValuesParser::new(LOG_LEVELS))]
+    pub log_level: Option<String>,
+
+    /// Syncthing instance to control (default: all configured instances)
+    #[arg(long, global = true, value_name = "NAME")]
+    pub instance: Option<String>,
+
+    /// Store configuration and logs in the current directory instead of the user's app data folder
+    #[arg(long, global = true)]
+    pub portable: bool,
+
+    /// Only create the default configuration file (if it doesn't exist) and exit
+    #[arg(long)]
+    pub create_config: bool,
+
+    /// Run without a system tray icon, exiting on SIGTERM or SIGINT
+    #[arg(long)]
+    pub headless: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
+pub enum Command {
+    /// Run the system tray application (default)
+    Tray,
+    /// Start Syncthing
+    Start,
+    /// Stop Syncthing
+    Stop,
+    /// Restart Syncthing
+    Restart,
+    /// Show whether Syncthing is running
+    Status,
+    /// Pause syncing with all devices, keeping Syncthing running
+    Pause,
+    /// Resume syncing with all devices
+    Resume,
+    /// Open the Syncthing web UI in the browser
+    OpenUi,
+    /// List the sync conflict copies in the synced folders
+    Conflicts,
+    /// Resolve a sync conflict listed by `conflicts`
+    Resolve {
+        /// Path of the conflict copy
+        path: PathBuf,
+        /// Which version to keep: the file at the original path (local), the
+        /// conflict copy (remote) or both
+        #[arg(value_enum)]
+        resolution: Resolution,
+    },
+    /// Wait until folders are up to date locally and on the remote devices
+    ///
+    /// Exits with 0 once everything is in sync, 3 when the timeout expires and 1 on
+    /// errors such as an unreachable Syncthing or unknown folder.
+    WaitSynced {
+        /// Folder ID or label to wait for; repeatable (default: all unpaused folders)
+        #[arg(long = "folder", value_name = "FOLDER")]
+        folders: Vec<String>,
+        /// Device ID or name that must be in sync; repeatable (default: all unpaused
+        /// devices sharing the folders)
+        #[arg(long = "device", value_name = "DEVICE")]
+        devices: Vec<String>,
+        /// Give up after this many seconds (default: wait indefinitely)
+        #[arg(long, value_name = "SECONDS")]
+        timeout: Option<u64>,
+    },
+    /// Inspect or edit the configuration file
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Subcommand)]
+pub enum ConfigCommand {
+    /// Print the path of the configuration file
+    Path,
+    /// Print the effective configuration
+    Show,
+    /// Open the configuration file in the default editor
+    Edit,
+    /// Validate the configuration file and report problems
+    Validate,
+}
+
+impl Cli {
+    /// Parses arguments forwarded from another launch (without the program name).
+    pub fn try_parse_args(args: Vec<String>) -> Result<Self, String> {
+        Self::try_parse_from(std::iter::once(env!("CARGO_PKG_NAME").to_string()).chain(args))
+            .map_err(|e| e.to_string())
+    }
+
+    /// The subcommand to run, defaulting to the tray.
+    pub fn subcommand(&self) -> Command {
+        self.command.clone().unwrap_or(Command::Tray)
+    }
+
+    /// The request carrying out a control command, or `None` for other commands.
+    ///
+    /// Control commands are sent to a running tray instance, or executed directly
+    /// when no instance is running.
+    pub fn ipc_request(&self) -> Option<IpcRequest> {
+        let instance = self.instance.clone();
+        match self.subcommand() {
+            Command::Start => Some(IpcRequest::Start { instance }),
+            Command::Stop => Some(IpcRequest::Stop { instance }),
+            Command::Restart => Some(IpcRequest::Restart { instance }),
+            Command::Status => Some(IpcRequest::Status { instance }),
+            Command::Pause => Some(IpcRequest::Pause { instance }),
+            Command::Resume => Some(IpcRequest::Resume { instance }),
+            Command::OpenUi => Some(IpcRequest::OpenWebUi { instance }),
+            Command::Conflicts => Some(IpcRequest::Conflicts { instance }),
+            // The running instance may have another working directory
+            Command::Resolve { path, resolution } => Some(IpcRequest::ResolveConflict {
+                path: std::path::absolute(&path).unwrap_or(path),
+                resolution,
+            }),
+            Command::Tray | Command::WaitSynced { .. } | Command::Config { .. } => None,
+        }
+    }
+}
+
+/// Runs a `config` subcommand against the configuration file at `path`.
+pub fn run_config_command(action: ConfigCommand, path: &Path) -> Result<String, AppError> {
+    match action {
+        ConfigCommand::Path => Ok(path.display().to_string()),
+        ConfigCommand::Show => {
+            let (config, _) = Config::load_validated(path)?;
+            serde_json::to_string_pretty(&config)
+                .map_err(|e| AppError::Command(format!("Failed to format configuration: {}", e)))
+        }
+        ConfigCommand::Edit => {
+            // Creates the file with defaults if it does not exist yet
+            Config::load_or_create(path)
+                .map_err(|e| AppError::Command(format!("Failed to create config file: {}", e)))?;
+            Config::open_in_editor(path)
+                .map_err(|e| AppError::Command(format!("Failed to open config file: {}", e)))?;
+            Ok(format!("Opened {}", path.display()))
+        }
+        ConfigCommand::Validate => {
+            let (_, warnings) = Config::load_validated(path)?;
+            let mut lines: Vec<String> = warnings.iter().map(|w| format!("Warning: {w}")).collect();
+            lines.push(format!("{} is valid.", path.display()));
+            Ok(lines.join("\n"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_legacy_flags_still_parse() {
+        let cli = Cli::try_parse_from(["syncthingers", "--portable", "--log-level=debug"]).unwrap();
+        assert!(cli.portable);
+        assert!(!cli.headless);
+        assert_eq!(cli.log_level.as_deref(), Some("debug"));
+        assert_eq!(cli.subcommand(), Command::Tray);
+
+        let cli = Cli::try_parse_from(["syncthingers", "--headless"]).unwrap();
+        assert!(cli.headless);
+        assert_eq!(cli.subcommand(), Command::Tray);
+    }
+
+    #[test]
+    fn test_subcommands_parse() {
+        let cli = Cli::try_parse_from(["syncthingers", "config", "validate"]).unwrap();
+        assert_eq!(
+            cli.subcommand(),
+            Command::Config {
+                action: ConfigCommand::Validate
+            }
+        );
+
+        let cli = Cli::try_parse_from(["syncthingers", "open-ui", "--log-level", "warn"]).unwrap();
+        assert_eq!(cli.subcommand(), Command::OpenUi);
+        assert_eq!(
+            cli.ipc_request(),
+            Some(IpcRequest::OpenWebUi { instance: None })
+        );
+
+        let cli = Cli::try_parse_from(["syncthingers", "restart", "--instance", "work"]).unwrap();
+        assert_eq!(
+            cli.ipc_request(),
+            Some(IpcRequest::Restart {
+                instance: Some("work".to_string())
+            })
+        );
+
+        let cli = Cli::try_parse_from(["syncthingers", "pause"]).unwrap();
+        assert_eq!(
+            cli.ipc_request(),
+            Some(IpcRequest::Pause { instance: None })
+        );
+
+        let cli = Cli::try_parse_from(["syncthingers", "resolve", "a.txt", "keep-both"]).unwrap();
+        assert_eq!(
+            cli.ipc_request(),
+            Some(IpcRequest::ResolveConflict {
+                path: std::env::current_dir().unwrap().join("a.txt"),
+                resolution: Resolution::Both,
+            })
+        );
+        assert!(Cli::try_parse_from(["syncthingers", "resolve", "a.txt", "keep-mine"]).is_err());
+
+        let cli = Cli::try_parse_from([
+            "syncthingers",
+            "wait-synced",
+            "--folder",
+            "docs",
+            "--folder",
+            "photos",
+            "--timeout",
+            "600",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.subcommand(),
+            Command::WaitSynced {
+                folders: vec!["docs".to_string(), "photos".to_string()],
+                devices: Vec::new(),
+                timeout: Some(600),
+            }
+        );
+        assert_eq!(cli.ipc_request(), None);
+    }
+
+    #[test]
+    fn test_unknown_flags_and_levels_are_rejected() {
+        assert!(Cli::try_parse_from(["syncthingers", "--bogus"]).is_err());
+        assert!(Cli::try_parse_from(["syncthingers", "--log-level=loud"]).is_err());
+    }
+
+    #[test]
+    fn test_config_path_and_validate() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("configuration.json");
+
+        assert_eq!(
+            run_config_command(ConfigCommand::Path, &path).unwrap(),
+            path.display().to_string()
+        );
+
+        std::fs::write(&path, "{ not json").unwrap();
+        assert!(matches!(
+            run_config_command(ConfigCommand::Validate, &path),
+            Err(AppError::Config(_))
+        ));
+    }
+}
@@ -18,15 +18,272 @@ pub enum ProcessClosureBehavior {
     DontClose,
 }
 
+/// Defines whether a managed Syncthing process is restarted after it exits.
+#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// Never restart automatically
+    #[serde(rename = "never")]
+    #[default]
+    Never,
+    /// Restart only when the process exits with an error or is killed
+    #[serde(rename = "on_failure")]
+    OnFailure,
+    /// Restart whenever the process exits, including clean exits
+    #[serde(rename = "always")]
+    Always,
+}
+
+/// Defines how strictly running processes are matched against the configured Syncthing.
+#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MatchStrictness {
+    /// Any process with the same executable name
+    #[serde(rename = "name")]
+    Name,
+    /// Processes running the configured executable file
+    #[serde(rename = "path")]
+    #[default]
+    Path,
+    /// Same executable, same `--home`/`--config` directory and same user
+    #[serde(rename = "strict")]
+    Strict,
+}
+
+/// Units used to display transfer speeds.
+#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SpeedUnits {
+    /// Bytes per second with binary prefixes (KiB/s, MiB/s), as in the Syncthing web UI
+    #[serde(rename = "bytes")]
+    #[default]
+    Bytes,
+    /// Bits per second with decimal prefixes (kbit/s, Mbit/s)
+    #[serde(rename = "bits")]
+    Bits,
+}
+
+/// Transfer speed monitoring settings.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct TransferSpeedConfig {
+    pub enabled: bool,
+    /// Seconds between polls of Syncthing's connection statistics
+    pub poll_interval_secs: u64,
+    pub units: SpeedUnits,
+}
+
+impl Default for TransferSpeedConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            poll_interval_secs: 5,
+            units: SpeedUnits::default(),
+        }
+    }
+}
+
+/// Desktop notification settings; each category can be enabled separately.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    /// A managed Syncthing process exited unexpectedly
+    pub syncthing_crashed: bool,
+    /// Automatic restarts stopped after a crash loop
+    pub restart_gave_up: bool,
+    pub device_disconnected: bool,
+    /// Files of a folder could not be synced
+    pub folder_errors: bool,
+    /// Syncthing created a `.sync-conflict` copy of a file
+    pub conflicts: bool,
+    /// A folder finished syncing
+    pub sync_complete: bool,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            syncthing_crashed: true,
+            restart_gave_up: true,
+            device_disconnected: false,
+            folder_errors: true,
+            conflicts: true,
+            sync_complete: false,
+        }
+    }
+}
+
+/// Automatic restart settings for the managed Syncthing process.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct SupervisionConfig {
+    pub policy: RestartPolicy,
+    /// Restarts allowed within the reset window before giving up
+    pub max_retries: u32,
+    /// Delay before the first restart; doubled for each further attempt
+    pub initial_backoff_secs: u64,
+    /// Upper bound for the restart delay
+    pub max_backoff_secs: u64,
+    /// Uptime after which the restart counter is reset
+    pub reset_window_secs: u64,
+}
+
+impl Default for SupervisionConfig {
+    fn default() -> Self {
+        Self {
+            policy: RestartPolicy::default(),
+            max_retries: 5,
+            initial_backoff_secs: 2,
+            max_backoff_secs: 60,
+            reset_window_secs: 300,
+        }
+    }
+}
+
+/// GUI address used when neither the configuration nor Syncthing's `config.xml` sets one.
+pub const DEFAULT_WEB_UI_URL: &str = "http://localhost:8384";
+
+/// A remote Syncthing whose health and sync status are monitored over its REST API.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RemoteConfig {
+    /// Name shown in the tray menu and logs
+    pub name: String,
+    /// Base URL of the remote GUI/REST API, e.g. `https://nas.local:8384`
+    pub url: String,
+    #[serde(default)]
+    pub api_key: String,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            url: DEFAULT_WEB_UI_URL.to_string(),
+            api_key: String::new(),
+        }
+    }
+}
+
+/// Name of the single instance described by the top-level settings when no
+/// `instances` are configured.
+pub const DEFAULT_INSTANCE_NAME: &str = "Syncthing";
+
+/// A named Syncthing instance with its own executable, home directory and GUI.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct InstanceConfig {
+    /// Name shown in the tray menu and used to select the instance on the command line
+    pub name: String,
+    pub syncthing_path: String,
+    /// Syncthing home directory, passed as `--home` unless `startup_args` already set one
+    #[serde(default)]
+    pub home: Option<String>,
+    /// Empty to use the GUI address from Syncthing's `config.xml`
+    #[serde(default)]
+    pub web_ui_url: String,
+    /// Empty to use the API key from Syncthing's `config.xml`
+    #[serde(default)]
+    pub api_key: String,
+    #[serde(default)]
+    pub startup_args: Vec<String>,
+    /// Start this instance when the app starts, unless it is already running
+    #[serde(default)]
+    pub auto_launch: bool,
+    #[serde(default)]
+    pub process_closure_behavior: ProcessClosureBehavior,
+}
+
+impl Default for InstanceConfig {
+    fn default() -> Self {
+        let config = Config::default();
+        Self {
+            name: DEFAULT_INSTANCE_NAME.to_string(),
+            syncthing_path: config.syncthing_path,
+            home: None,
+            web_ui_url: config.web_ui_url,
+            api_key: String::new(),
+            startup_args: Vec::new(),
+            auto_launch: false,
+            process_closure_behavior: ProcessClosureBehavior::default(),
+        }
+    }
+}
+
+impl InstanceConfig {
+    /// Arguments Syncthing is started with, including `--home` if configured.
+    pub fn launch_args(&self) -> Vec<String> {
+        let mut args = self.startup_args.clone();
+        if let Some(home) = &self.home
+            && crate::process_match::home_argument(&args).is_none()
+        {
+            args.insert(0, format!("--home={home}"));
+        }
+        args
+    }
+
+    /// The home directory this instance runs with, if set in `home` or `startup_args`.
+    pub fn home_dir(&self) -> Option<String> {
+        crate::process_match::home_argument(&self.launch_args())
+    }
+}
+
+/// Fields that are added automatically to existing configuration files when missing.
+const AUTO_ADDED_FIELDS: &[&str] = &[
+    "process_closure_behavior",
+    "auto_launch_internal",
+    "api_key",
+    "supervision",
+    "shutdown_grace_period_secs",
+    "process_match_strictness",
+    "transfer_speed",
+    "instances",
+    "remotes",
+    "remote_poll_interval_secs",
+    "notifications",
+];
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Config {
     pub log_level: String,
     pub syncthing_path: String,
+    /// Empty to use the GUI address from Syncthing's `config.xml`
+    #[serde(default)]
     pub web_ui_url: String,
+    /// API key used to authenticate against Syncthing's REST API.
+    /// Empty to use the API key from Syncthing's `config.xml`.
+    #[serde(default)]
+    pub api_key: String,
     pub startup_args: Vec<String>,
     #[serde(default)]
     pub process_closure_behavior: ProcessClosureBehavior,
     pub auto_launch_internal: bool, // auto-launch internal syncthing if external not running
+    #[serde(default)]
+    pub supervision: SupervisionConfig,
+    /// Seconds to wait for Syncthing to exit after a graceful shutdown request before force-killing it
+    #[serde(default = "default_shutdown_grace_period_secs")]
+    pub shutdown_grace_period_secs: u64,
+    /// How running processes are recognized as the configured Syncthing instance
+    #[serde(default)]
+    pub process_match_strictness: MatchStrictness,
+    /// Upload and download rates shown in the tray
+    #[serde(default)]
+    pub transfer_speed: TransferSpeedConfig,
+    /// Named Syncthing instances; when empty, the top-level settings describe a single instance
+    #[serde(default)]
+    pub instances: Vec<InstanceConfig>,
+    /// Remote Syncthing instances that are monitored but not managed
+    #[serde(default)]
+    pub remotes: Vec<RemoteConfig>,
+    /// Seconds between health and sync status polls of the remotes
+    #[serde(default = "default_remote_poll_interval_secs")]
+    pub remote_poll_interval_secs: u64,
+    /// Desktop notifications for sync events and failures
+    #[serde(default)]
+    pub notifications: NotificationsConfig,
+}
+
+fn default_shutdown_grace_period_secs() -> u64 {
+    10
+}
+
+fn default_remote_poll_interval_secs() -> u64 {
+    30
 }
 
 impl Default for Config {
@@ -36,15 +293,45 @@ impl Default for Config {
         Self {
             log_level: "info".to_string(),
             syncthing_path,
-            web_ui_url: "http://localhost:8384".to_string(),
+            web_ui_url: String::new(),
+            api_key: String::new(),
             startup_args: vec![],
             process_closure_behavior: ProcessClosureBehavior::default(),
             auto_launch_internal: false, // Default: do not auto-launch
+            supervision: SupervisionConfig::default(),
+            shutdown_grace_period_secs: default_shutdown_grace_period_secs(),
+            process_match_strictness: MatchStrictness::default(),
+            transfer_speed: TransferSpeedConfig::default(),
+            instances: Vec::new(),
+            remotes: Vec::new(),
+            remote_poll_interval_secs: default_remote_poll_interval_secs(),
+            notifications: NotificationsConfig::default(),
         }
     }
 }
 
 impl Config {
+    /// The configured Syncthing instances.
+    ///
+    /// Without an `instances` list, a single instance is built from the top-level
+    /// `syncthing_path`, `web_ui_url`, `api_key`, `startup_args`, `auto_launch_internal`
+    /// and `process_closure_behavior` settings.
+    pub fn instance_configs(&self) -> Vec<InstanceConfig> {
+        if !self.instances.is_empty() {
+            return self.instances.clone();
+        }
+        vec![InstanceConfig {
+            name: DEFAULT_INSTANCE_NAME.to_string(),
+            syncthing_path: self.syncthing_path.clone(),
+            home: None,
+            web_ui_url: self.web_ui_url.clone(),
+            api_key: self.api_key.clone(),
+            startup_args: self.startup_args.clone(),
+            auto_launch: self.auto_launch_internal,
+            process_closure_behavior: self.process_closure_behavior.clone(),
+        }]
+    }
+
     #[cfg(windows)]
     pub fn find_syncthing_in_path() -> Option<String> {
         let mut detected_path: Option<String> = None;
@@ -150,6 +437,20 @@ impl Config {
     /// Merges an existing config with default values, preserving existing settings
     /// while adding any missing fields from the default configuration.
     fn merge_with_defaults(existing: serde_json::Value, defaults: &Self) -> io::Result<Self> {
+        let merged = Self::merged_value(&existing, defaults)?;
+
+        // Convert the merged Value back to Config
+        let config = serde_json::from_value(merged)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        Ok(config)
+    }
+
+    /// Returns the default configuration as JSON with the existing values laid over it.
+    pub(crate) fn merged_value(
+        existing: &serde_json::Value,
+        defaults: &Self,
+    ) -> io::Result<serde_json::Value> {
         // Convert defaults to Value for easier merging
         let default_value = serde_json::to_value(defaults)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
@@ -166,27 +467,19 @@ impl Config {
             }
         }
 
-        // Convert the merged Value back to Config
-        let config = serde_json::from_value(merged)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
-        Ok(config)
+        Ok(merged)
     }
 
     /// Checks if the JSON object is missing any fields that are present in the Config struct.
     /// Returns true if any fields are missing.
     fn check_missing_fields(json_value: &serde_json::Value) -> bool {
-        // Check for specific fields we know might be missing
-        // This is specifically checking for the process_closure_behavior and auto_launch_internal fields
+        // Fields that were added after the first release and may be missing from older files
         if let Some(obj) = json_value.as_object() {
-            // If we're missing any expected field, return true
-            if !obj.contains_key("process_closure_behavior") {
-                log::info!("Missing field 'process_closure_behavior' in config");
-                return true;
-            }
-            if !obj.contains_key("auto_launch_internal") {
-                log::info!("Missing field 'auto_launch_internal' in config");
-                return true;
+            for field in AUTO_ADDED_FIELDS {
+                if !obj.contains_key(*field) {
+                    log::info!("Missing field '{}' in config", field);
+                    return true;
+                }
             }
         }
         false
@@ -280,6 +573,8 @@ mod config_tests {
 
         assert!(updated_json.get("process_closure_behavior").is_some());
         assert!(updated_json.get("auto_launch_internal").is_some());
+        assert!(updated_json.get("api_key").is_some());
+        assert!(updated_json.get("supervision").is_some());
 
         Ok(())
     }
@@ -326,6 +621,18 @@ mod tests {
         assert_eq!(behavior, ProcessClosureBehavior::DontClose);
     }
 
+    #[test]
+    fn test_supervision_partial_section_uses_defaults() {
+        let supervision: SupervisionConfig =
+            serde_json::from_str(r#"{"policy": "on_failure", "max_retries": 2}"#).unwrap();
+        assert_eq!(supervision.policy, RestartPolicy::OnFailure);
+        assert_eq!(supervision.max_retries, 2);
+        assert_eq!(
+            supervision.reset_window_secs,
+            SupervisionConfig::default().reset_window_secs
+        );
+    }
+
     #[test]
     fn test_config_with_process_closure_behavior() {
         let config = Config::default();
@@ -0,0 +1,789 @@
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::config::{Config, InstanceConfig, RemoteConfig};
+use crate::error_handling::AppError;
+use crate::logging;
+use crate::syncthing_config;
+
+/// How serious a configuration problem is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The configuration cannot be used.
+    Error,
+    /// The configuration can be used, but something is likely wrong.
+    Warning,
+}
+
+/// The kind of problem found in the configuration file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigProblemKind {
+    /// The file is not valid JSON.
+    Syntax(String),
+    /// The file could not be read or written.
+    Io(String),
+    /// A value has the wrong type or an unsupported variant.
+    InvalidValue(String),
+    /// `log_level` is not one of the supported levels.
+    UnknownLogLevel(String),
+    /// A URL setting is not a valid http(s) URL.
+    InvalidUrl { value: String, reason: String },
+    /// `syncthing_path` does not point to an existing file.
+    SyncthingPathNotFound(String),
+    /// `syncthing_path` exists but is not an executable file.
+    SyncthingPathNotExecutable(String),
+    /// The key is not a known configuration option.
+    UnknownKey,
+    /// The configured web UI URL differs from the GUI address in Syncthing's `config.xml`.
+    GuiAddressMismatch { configured: String, actual: String },
+}
+
+/// A single problem found while validating the configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigProblem {
+    /// JSON path of the offending value, e.g. `log_level` or `supervision.max_retries`.
+    /// Empty for problems that concern the whole file.
+    pub path: String,
+    pub kind: ConfigProblemKind,
+    /// 1-based line of the offending key, if known.
+    pub line: Option<usize>,
+    /// 1-based column of the offending key, if known.
+    pub column: Option<usize>,
+}
+
+impl ConfigProblem {
+    fn new(path: &str, kind: ConfigProblemKind) -> Self {
+        Self {
+            path: path.to_string(),
+            kind,
+            line: None,
+            column: None,
+        }
+    }
+
+    /// Fills in the position of the problem from the positions of keys in the source text.
+    fn locate(mut self, positions: &HashMap<String, (usize, usize)>) -> Self {
+        if let Some(&(line, column)) = positions.get(&self.path) {
+            self.line = Some(line);
+            self.column = Some(column);
+        }
+        self
+    }
+
+    /// Returns how serious the problem is.
+    pub fn severity(&self) -> Severity {
+        match self.kind {
+            ConfigProblemKind::SyncthingPathNotFound(_)
+            | ConfigProblemKind::SyncthingPathNotExecutable(_)
+            | ConfigProblemKind::UnknownKey
+            | ConfigProblemKind::GuiAddressMismatch { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "{}", self.path)?;
+        }
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, " (line {line}, column {column})")?,
+            (Some(line), None) => write!(f, " (line {line})")?,
+            _ => {}
+        }
+        if !self.path.is_empty() || self.line.is_some() {
+            write!(f, ": ")?;
+        }
+        match &self.kind {
+            ConfigProblemKind::Syntax(msg) => write!(f, "invalid JSON: {msg}"),
+            ConfigProblemKind::Io(msg) => write!(f, "{msg}"),
+            ConfigProblemKind::InvalidValue(msg) => write!(f, "invalid value: {msg}"),
+            ConfigProblemKind::UnknownLogLevel(level) => write!(
+                f,
+                "unknown log level '{level}', expected one of: {}",
+                logging::LOG_LEVELS.join(", ")
+            ),
+            ConfigProblemKind::InvalidUrl { value, reason } => {
+                write!(f, "'{value}' is not a valid http(s) URL: {reason}")
+            }
+            ConfigProblemKind::SyncthingPathNotFound(path) => {
+                write!(f, "Syncthing executable '{path}' does not exist")
+            }
+            ConfigProblemKind::SyncthingPathNotExecutable(path) => {
+                write!(f, "'{path}' is not an executable file")
+            }
+            ConfigProblemKind::UnknownKey => write!(f, "unknown configuration option"),
+            ConfigProblemKind::GuiAddressMismatch { configured, actual } => write!(
+                f,
+                "'{configured}' does not match the GUI address in Syncthing's config.xml ({actual})"
+            ),
+        }
+    }
+}
+
+/// A list of configuration problems, displayed one per line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigProblems(pub Vec<ConfigProblem>);
+
+impl ConfigProblems {
+    /// Returns true if any problem prevents the configuration from being used.
+    pub fn has_errors(&self) -> bool {
+        self.0.iter().any(|p| p.severity() == Severity::Error)
+    }
+}
+
+impl fmt::Display for ConfigProblems {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, problem) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{problem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<ConfigProblem> for ConfigProblems {
+    fn from(problem: ConfigProblem) -> Self {
+        Self(vec![problem])
+    }
+}
+
+impl Config {
+    /// Validates the configuration values.
+    ///
+    /// When the raw JSON `source` is given, unknown keys are reported as well and
+    /// problems are annotated with the line and column of the offending key.
+    pub fn validate(&self, source: Option<&str>) -> Vec<ConfigProblem> {
+        let mut problems = Vec::new();
+
+        if !logging::LOG_LEVELS.contains(&self.log_level.to_lowercase().as_str()) {
+            problems.push(ConfigProblem::new(
+                "log_level",
+                ConfigProblemKind::UnknownLogLevel(self.log_level.clone()),
+            ));
+        }
+
+        // An empty URL is read from Syncthing's config.xml
+        if !self.web_ui_url.is_empty()
+            && let Err(reason) = validate_http_url(&self.web_ui_url)
+        {
+            problems.push(ConfigProblem::new(
+                "web_ui_url",
+                ConfigProblemKind::InvalidUrl {
+                    value: self.web_ui_url.clone(),
+                    reason,
+                },
+            ));
+        }
+
+        // The top-level executable is unused once instances are configured
+        if self.instances.is_empty()
+            && let Some(kind) = check_executable(&self.syncthing_path)
+        {
+            problems.push(ConfigProblem::new("syncthing_path", kind));
+        }
+
+        let mut names = HashSet::new();
+        for (i, instance) in self.instances.iter().enumerate() {
+            let prefix = format!("instances[{i}]");
+            if instance.name.trim().is_empty() {
+                problems.push(ConfigProblem::new(
+                    &format!("{prefix}.name"),
+                    ConfigProblemKind::InvalidValue("instance name must not be empty".to_string()),
+                ));
+            } else if !names.insert(instance.name.as_str()) {
+                problems.push(ConfigProblem::new(
+                    &format!("{prefix}.name"),
+                    ConfigProblemKind::InvalidValue(format!(
+                        "duplicate instance name '{}'",
+                        instance.name
+                    )),
+                ));
+            }
+            if !instance.web_ui_url.is_empty()
+                && let Err(reason) = validate_http_url(&instance.web_ui_url)
+            {
+                problems.push(ConfigProblem::new(
+                    &format!("{prefix}.web_ui_url"),
+                    ConfigProblemKind::InvalidUrl {
+                        value: instance.web_ui_url.clone(),
+                        reason,
+                    },
+                ));
+            }
+            if let Some(kind) = check_executable(&instance.syncthing_path) {
+                problems.push(ConfigProblem::new(
+                    &format!("{prefix}.syncthing_path"),
+                    kind,
+                ));
+            }
+        }
+
+        // Compare URLs with what Syncthing itself listens on
+        for (i, instance) in self.instance_configs().iter().enumerate() {
+            if let Some(actual) = syncthing_config::address_mismatch(instance) {
+                let path = if self.instances.is_empty() {
+                    "web_ui_url".to_string()
+                } else {
+                    format!("instances[{i}].web_ui_url")
+                };
+                problems.push(ConfigProblem::new(
+                    &path,
+                    ConfigProblemKind::GuiAddressMismatch {
+                        configured: instance.web_ui_url.clone(),
+                        actual,
+                    },
+                ));
+            }
+        }
+
+        let mut remote_names = HashSet::new();
+        for (i, remote) in self.remotes.iter().enumerate() {
+            let prefix = format!("remotes[{i}]");
+            if remote.name.trim().is_empty() {
+                problems.push(ConfigProblem::new(
+                    &format!("{prefix}.name"),
+                    ConfigProblemKind::InvalidValue("remote name must not be empty".to_string()),
+                ));
+            } else if !remote_names.insert(remote.name.as_str()) {
+                problems.push(ConfigProblem::new(
+                    &format!("{prefix}.name"),
+                    ConfigProblemKind::InvalidValue(format!(
+                        "duplicate remote name '{}'",
+                        remote.name
+                    )),
+                ));
+            }
+            if let Err(reason) = validate_http_url(&remote.url) {
+                problems.push(ConfigProblem::new(
+                    &format!("{prefix}.url"),
+                    ConfigProblemKind::InvalidUrl {
+                        value: remote.url.clone(),
+                        reason,
+                    },
+                ));
+            }
+        }
+
+        if self.remote_poll_interval_secs == 0 {
+            problems.push(ConfigProblem::new(
+                "remote_poll_interval_secs",
+                ConfigProblemKind::InvalidValue("must be at least 1 second".to_string()),
+            ));
+        }
+
+        if self.transfer_speed.poll_interval_secs == 0 {
+            problems.push(ConfigProblem::new(
+                "transfer_speed.poll_interval_secs",
+                ConfigProblemKind::InvalidValue("must be at least 1 second".to_string()),
+            ));
+        }
+
+        let Some(source) = source else {
+            return problems;
+        };
+
+        let positions = key_positions(source);
+        if let Ok(value) = serde_json::from_str::<Value>(source)
+            && let Ok(defaults) = serde_json::to_value(Config::default())
+        {
+            collect_unknown_keys(&value, &defaults, "", &mut problems);
+
+            // Entries of lists are checked against the defaults of their element type
+            let list_defaults = [
+                ("instances", serde_json::to_value(InstanceConfig::default())),
+                ("remotes", serde_json::to_value(RemoteConfig::default())),
+            ];
+            for (key, defaults) in list_defaults {
+                if let Some(entries) = value.get(key).and_then(Value::as_array)
+                    && let Ok(defaults) = defaults
+                {
+                    for (i, entry) in entries.iter().enumerate() {
+                        collect_unknown_keys(
+                            entry,
+                            &defaults,
+                            &format!("{key}[{i}]"),
+                            &mut problems,
+                        );
+                    }
+                }
+            }
+        }
+
+        problems.into_iter().map(|p| p.locate(&positions)).collect()
+    }
+
+    /// Loads the configuration file and validates it.
+    ///
+    /// Returns the configuration together with any warnings, or an `AppError::Config`
+    /// listing every problem if the file cannot be used.
+    pub fn load_validated<P: AsRef<Path>>(path: P) -> Result<(Self, Vec<ConfigProblem>), AppError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            let config = Self::load_or_create(path).map_err(io_problem)?;
+            let problems = config.validate(None);
+            return Ok((config, problems));
+        }
+
+        let source = fs::read_to_string(path).map_err(io_problem)?;
+        let value: Value = serde_json::from_str(&source)
+            .map_err(|e| AppError::Config(ConfigProblem::from(e).into()))?;
+        Self::check_types(&value, &source)?;
+
+        let config = Self::load_or_create(path).map_err(io_problem)?;
+        let problems = ConfigProblems(config.validate(Some(&source)));
+        if problems.has_errors() {
+            return Err(AppError::Config(problems));
+        }
+        Ok((config, problems.0))
+    }
+
+    /// Checks that the JSON value deserializes into a `Config`, reporting the path of
+    /// the first value with the wrong type.
+    fn check_types(value: &Value, source: &str) -> Result<(), AppError> {
+        let merged = Self::merged_value(value, &Self::default())
+            .map_err(|e| io_problem(std::io::Error::other(e)))?;
+        serde_path_to_error::deserialize::<_, Config>(merged)
+            .map(|_| ())
+            .map_err(|e| {
+                let problem = ConfigProblem::new(
+                    &e.path().to_string(),
+                    ConfigProblemKind::InvalidValue(e.inner().to_string()),
+                )
+                .locate(&key_positions(source));
+                AppError::Config(problem.into())
+            })
+    }
+}
+
+impl From<serde_json::Error> for ConfigProblem {
+    fn from(e: serde_json::Error) -> Self {
+        let (line, column) = if e.line() > 0 {
+            (Some(e.line()), Some(e.column()))
+        } else {
+            (None, None)
+        };
+        // serde_json appends the position to its message; it is reported separately
+        let message = e.to_string();
+        let message = message
+            .split(" at line ")
+            .next()
+            .unwrap_or(&message)
+            .to_string();
+        let kind = if e.is_syntax() || e.is_eof() {
+            ConfigProblemKind::Syntax(message)
+        } else {
+            ConfigProblemKind::InvalidValue(message)
+        };
+        Self {
+            path: String::new(),
+            kind,
+            line,
+            column,
+        }
+    }
+}
+
+fn io_problem(e: std::io::Error) -> AppError {
+    AppError::Config(ConfigProblem::new("", ConfigProblemKind::Io(e.to_string())).into())
+}
+
+/// Checks that a URL uses http or https and has a host.
+fn validate_http_url(value: &str) -> Result<(), String> {
+    let url = url::Url::parse(value).map_err(|e| e.to_string())?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(format!("unsupported scheme '{}'", url.scheme()));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err("missing host".to_string());
+    }
+    Ok(())
+}
+
+/// Checks that the path points to an existing, executable file.
+fn check_executable(path: &str) -> Option<ConfigProblemKind> {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return Some(ConfigProblemKind::SyncthingPathNotFound(path.to_string())),
+    };
+    if !metadata.is_file() {
+        return Some(ConfigProblemKind::SyncthingPathNotExecutable(
+            path.to_string(),
+        ));
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Some(ConfigProblemKind::SyncthingPathNotExecutable(
+                path.to_string(),
+            ));
+        }
+    }
+
+    None
+}
+
+/// Reports keys of `value` that do not exist in `known`, descending into nested
+/// objects that are present in both.
+fn collect_unknown_keys(value: &Value, known: &Value, prefix: &str, out: &mut Vec<ConfigProblem>) {
+    let (Some(obj), Some(known_obj)) = (value.as_object(), known.as_object()) else {
+        return;
+    };
+    for (key, child) in obj {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match known_obj.get(key) {
+            Some(known_child) => collect_unknown_keys(child, known_child, &path, out),
+            None => out.push(ConfigProblem::new(&path, ConfigProblemKind::UnknownKey)),
+        }
+    }
+}
+
+/// Scans JSON text and returns the 1-based line and column of every object key,
+/// keyed by its JSON path (e.g. `supervision.policy` or `instances[0].name`).
+///
+/// The scanner is deliberately lenient: it is only used to annotate problems found
+/// by serde, so malformed input simply yields fewer positions.
+fn key_positions(source: &str) -> HashMap<String, (usize, usize)> {
+    enum Frame {
+        Object {
+            key: Option<String>,
+            expect_key: bool,
+        },
+        Array {
+            index: usize,
+        },
+    }
+
+    fn path_of(frames: &[Frame]) -> String {
+        let mut path = String::new();
+        for frame in frames {
+            match frame {
+                Frame::Object { key: Some(key), .. } => {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(key);
+                }
+                Frame::Object { key: None, .. } => {}
+                Frame::Array { index } => path.push_str(&format!("[{index}]")),
+            }
+        }
+        path
+    }
+
+    let mut positions = HashMap::new();
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut chars = source.chars().peekable();
+    let (mut line, mut column) = (1, 0);
+
+    while let Some(c) = chars.next() {
+        column += 1;
+        match c {
+            '\n' => {
+                line += 1;
+                column = 0;
+            }
+            '{' => stack.push(Frame::Object {
+                key: None,
+                expect_key: true,
+            }),
+            '[' => stack.push(Frame::Array { index: 0 }),
+            '}' | ']' => {
+                stack.pop();
+            }
+            ',' => match stack.last_mut() {
+                Some(Frame::Object { expect_key, .. }) => *expect_key = true,
+                Some(Frame::Array { index }) => *index += 1,
+                None => {}
+            },
+            ':' => {
+                if let Some(Frame::Object { expect_key, .. }) = stack.last_mut() {
+                    *expect_key = false;
+                }
+            }
+            '"' => {
+                let start = (line, column);
+                let mut text = String::new();
+                let mut escaped = false;
+                for c in chars.by_ref() {
+                    column += 1;
+                    if escaped {
+                        escaped = false;
+                        text.push(c);
+                    } else if c == '\\' {
+                        escaped = true;
+                    } else if c == '"' {
+                        break;
+                    } else {
+                        text.push(c);
+                    }
+                }
+
+                let depth = stack.len();
+                if let Some(Frame::Object {
+                    key,
+                    expect_key: true,
+                }) = stack.last_mut()
+                {
+                    *key = Some(text.clone());
+                    let prefix = path_of(&stack[..depth - 1]);
+                    let path = if prefix.is_empty() {
+                        text
+                    } else {
+                        format!("{prefix}.{text}")
+                    };
+                    positions.insert(path, start);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    positions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn valid_source() -> String {
+        r#"{
+  "log_level": "info",
+  "syncthing_path": "test/syncthing.exe",
+  "web_ui_url": "http://localhost:8384",
+  "api_key": "",
+  "startup_args": [],
+  "process_closure_behavior": "close_managed",
+  "auto_launch_internal": false
+}"#
+        .to_string()
+    }
+
+    fn kinds(problems: &[ConfigProblem]) -> Vec<&ConfigProblemKind> {
+        problems.iter().map(|p| &p.kind).collect()
+    }
+
+    #[test]
+    fn test_key_positions() {
+        let source = "{\n  \"a\": 1,\n  \"b\": {\"c\": [ {\"d\": true} ]}\n}";
+        let positions = key_positions(source);
+        assert_eq!(positions["a"], (2, 3));
+        assert_eq!(positions["b"], (3, 3));
+        assert_eq!(positions["b.c"], (3, 9));
+        assert_eq!(positions["b.c[0].d"], (3, 17));
+    }
+
+    #[test]
+    fn test_unknown_log_level_is_error_with_position() {
+        let source = valid_source().replace("\"info\"", "\"verbose\"");
+        let config: Config = serde_json::from_str(&source).unwrap();
+
+        let problems = config.validate(Some(&source));
+        let problem = problems
+            .iter()
+            .find(|p| p.path == "log_level")
+            .expect("log level problem");
+        assert_eq!(
+            problem.kind,
+            ConfigProblemKind::UnknownLogLevel("verbose".to_string())
+        );
+        assert_eq!(problem.severity(), Severity::Error);
+        assert_eq!((problem.line, problem.column), (Some(2), Some(3)));
+    }
+
+    #[test]
+    fn test_invalid_web_ui_url() {
+        let source = valid_source().replace("http://localhost:8384", "localhost:8384");
+        let config: Config = serde_json::from_str(&source).unwrap();
+
+        let problems = config.validate(Some(&source));
+        assert!(
+            problems.iter().any(|p| p.path == "web_ui_url"
+                && matches!(p.kind, ConfigProblemKind::InvalidUrl { .. }))
+        );
+    }
+
+    #[test]
+    fn test_missing_syncthing_path_is_warning() {
+        let config: Config = serde_json::from_str(&valid_source()).unwrap();
+
+        let problems = config.validate(None);
+        assert_eq!(
+            kinds(&problems),
+            vec![&ConfigProblemKind::SyncthingPathNotFound(
+                "test/syncthing.exe".to_string()
+            )]
+        );
+        assert_eq!(problems[0].severity(), Severity::Warning);
+    }
+
+    #[test]
+    fn test_zero_transfer_speed_interval_is_error() {
+        let source = valid_source().replace(
+            "  \"api_key\": \"\",",
+            "  \"api_key\": \"\",\n  \"transfer_speed\": {\"enabled\": true, \"poll_interval_secs\": 0},",
+        );
+        let config: Config = serde_json::from_str(&source).unwrap();
+
+        let problems = config.validate(Some(&source));
+        let problem = problems
+            .iter()
+            .find(|p| p.path == "transfer_speed.poll_interval_secs")
+            .expect("poll interval problem");
+        assert_eq!(problem.severity(), Severity::Error);
+        assert_eq!(problem.line, Some(6));
+    }
+
+    #[test]
+    fn test_instances_are_validated() {
+        let source = valid_source().replace(
+            "  \"api_key\": \"\",",
+            r#"  "api_key": "",
+  "instances": [
+    {"name": "work", "syncthing_path": "test/syncthing.exe", "web_ui_url": "http://localhost:8384"},
+    {"name": "work", "syncthing_path": "test/syncthing.exe", "web_ui_url": "localhost",
+     "hom": "/srv/work"}
+  ],"#,
+        );
+        let config: Config = serde_json::from_str(&source).unwrap();
+
+        let problems = config.validate(Some(&source));
+        let paths: Vec<&str> = problems.iter().map(|p| p.path.as_str()).collect();
+        assert!(!paths.contains(&"syncthing_path"));
+        assert!(paths.contains(&"instances[1].name"));
+        assert!(paths.contains(&"instances[1].web_ui_url"));
+        let unknown = problems
+            .iter()
+            .find(|p| p.kind == ConfigProblemKind::UnknownKey)
+            .expect("unknown key problem");
+        assert_eq!(unknown.path, "instances[1].hom");
+        assert_eq!(unknown.line, Some(9));
+    }
+
+    #[test]
+    fn test_remotes_are_validated() {
+        let source = valid_source().replace(
+            "  \"api_key\": \"\",",
+            r#"  "api_key": "",
+  "remote_poll_interval_secs": 0,
+  "remotes": [
+    {"name": "nas", "url": "nas.local:8384", "apikey": "x"}
+  ],"#,
+        );
+        let config: Config = serde_json::from_str(&source).unwrap();
+
+        let problems = config.validate(Some(&source));
+        let paths: Vec<&str> = problems.iter().map(|p| p.path.as_str()).collect();
+        assert!(paths.contains(&"remote_poll_interval_secs"));
+        assert!(paths.contains(&"remotes[0].url"));
+        assert!(paths.contains(&"remotes[0].apikey"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_executable_syncthing_path() {
+        let file = NamedTempFile::new().unwrap();
+        let config = Config {
+            syncthing_path: file.path().to_string_lossy().to_string(),
+            ..serde_json::from_str(&valid_source()).unwrap()
+        };
+
+        let problems = config.validate(None);
+        assert!(matches!(
+            problems[0].kind,
+            ConfigProblemKind::SyncthingPathNotExecutable(_)
+        ));
+    }
+
+    #[test]
+    fn test_unknown_key_reported_with_position() {
+        let source = valid_source().replace(
+            "  \"api_key\": \"\",",
+            "  \"api_key\": \"\",\n  \"web_ui_ulr\": \"x\",",
+        );
+        let config: Config = serde_json::from_str(&source).unwrap();
+
+        let problems = config.validate(Some(&source));
+        let problem = problems
+            .iter()
+            .find(|p| p.kind == ConfigProblemKind::UnknownKey)
+            .expect("unknown key problem");
+        assert_eq!(problem.path, "web_ui_ulr");
+        assert_eq!(problem.line, Some(6));
+    }
+
+    #[test]
+    fn test_load_validated_syntax_error_has_position() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(b"{\n  \"log_level\": \"info\",\n  oops\n}")
+            .unwrap();
+
+        match Config::load_validated(file.path()) {
+            Err(AppError::Config(problems)) => {
+                assert_eq!(problems.0.len(), 1);
+                assert!(matches!(problems.0[0].kind, ConfigProblemKind::Syntax(_)));
+                assert_eq!(problems.0[0].line, Some(3));
+            }
+            other => panic!("Expected config error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_load_validated_type_error_has_path() {
+        let mut file = NamedTempFile::new().unwrap();
+        let source = valid_source().replace("\"close_managed\"", "\"close_some\"");
+        file.write_all(source.as_bytes()).unwrap();
+
+        match Config::load_validated(file.path()) {
+            Err(AppError::Config(problems)) => {
+                assert_eq!(problems.0[0].path, "process_closure_behavior");
+                assert!(matches!(
+                    problems.0[0].kind,
+                    ConfigProblemKind::InvalidValue(_)
+                ));
+                assert_eq!(problems.0[0].line, Some(7));
+            }
+            other => panic!("Expected config error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_load_validated_returns_warnings() {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(valid_source().as_bytes()).unwrap();
+
+        let (config, warnings) = Config::load_validated(file.path()).unwrap();
+        assert_eq!(config.log_level, "info");
+        assert!(warnings.iter().all(|p| p.severity() == Severity::Warning));
+    }
+
+    #[test]
+    fn test_problems_display() {
+        let problems = ConfigProblems(vec![
+            ConfigProblem {
+                path: "log_level".to_string(),
+                kind: ConfigProblemKind::UnknownLogLevel("loud".to_string()),
+                line: Some(2),
+                column: Some(3),
+            },
+            ConfigProblem::new("extra", ConfigProblemKind::UnknownKey),
+        ]);
+        let text = problems.to_string();
+        assert!(text.starts_with("log_level (line 2, column 3): unknown log level 'loud'"));
+        assert!(text.ends_with("\nextra: unknown configuration option"));
+    }
+}
@@ -0,0 +1,259 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::app_state::AppState;
+use crate::config::Config;
+
+/// How often the configuration file is checked for modifications.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Result of a single poll of the configuration file.
+#[derive(Debug)]
+pub enum WatchEvent {
+    /// The file was modified and parsed successfully.
+    Reloaded(Box<Config>),
+    /// The file was modified but failed validation. `first_report` is true only
+    /// for the first failure of a given modification, so popups are not repeated.
+    Invalid { error: String, first_report: bool },
+    /// The file was deleted. The current configuration stays in effect.
+    Deleted,
+}
+
+/// Polling watcher for `configuration.json`.
+///
+/// Uses the file modification time to detect changes. A change is only acted upon
+/// once the modification time has been stable for one poll interval, so editors
+/// that save in several steps do not trigger reloads of half-written files.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    last_loaded: Option<SystemTime>,
+    pending: Option<SystemTime>,
+    last_reported_error: Option<SystemTime>,
+    exists: bool,
+}
+
+impl ConfigWatcher {
+    /// Creates a watcher for the given file, treating its current state as already loaded.
+    pub fn new(path: PathBuf) -> Self {
+        let last_loaded = Self::modified_time(&path).ok();
+        Self {
+            exists: last_loaded.is_some(),
+            path,
+            last_loaded,
+            pending: None,
+            last_reported_error: None,
+        }
+    }
+
+    /// Spawns a background thread that applies configuration changes to the app state.
+    pub fn spawn(app_state: Arc<Mutex<AppState>>, path: PathBuf) {
+        log::info!(
+            "Watching configuration file for changes: {}",
+            path.display()
+        );
+        let mut watcher = Self::new(path);
+
+        thread::spawn(move || {
+            loop {
+                thread::sleep(POLL_INTERVAL);
+
+                match watcher.poll() {
+                    Some(WatchEvent::Reloaded(config)) => match app_state.lock() {
+                        Ok(mut state) => {
+                            state.apply_config(*config);
+                        }
+                        Err(_) => log::error!("Failed to lock app state for configuration reload"),
+                    },
+                    Some(WatchEvent::Invalid {
+                        error,
+                        first_report,
+                    }) => {
+                        log::error!(
+                            "Configuration reload failed, keeping current settings: {error}"
+                        );
+                        if first_report {
+                            // Show the dialog without holding the state lock while it is open
+                            let dialogs = match app_state.lock() {
+                                Ok(state) => state.dialogs.clone(),
+                                Err(_) => continue,
+                            };
+                            dialogs.error(
+                                &format!(
+                                    "The configuration file could not be loaded and the previous settings are still in use.\n\n{error}"
+                                ),
+                                "Syncthingers Configuration Error",
+                            );
+                        }
+                    }
+                    Some(WatchEvent::Deleted) => {
+                        log::warn!(
+                            "Configuration file was deleted, keeping current settings until it is recreated"
+                        );
+                    }
+                    None => {}
+                }
+            }
+        });
+    }
+
+    /// Checks the file once and reports what, if anything, changed.
+    pub fn poll(&mut self) -> Option<WatchEvent> {
+        let modified = match Self::modified_time(&self.path) {
+            Ok(modified) => modified,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                self.pending = None;
+                if self.exists {
+                    self.exists = false;
+                    self.last_loaded = None;
+                    return Some(WatchEvent::Deleted);
+                }
+                return None;
+            }
+            Err(e) => {
+                log::debug!("Failed to read configuration file metadata: {e}");
+                return None;
+            }
+        };
+        self.exists = true;
+
+        if self.last_loaded == Some(modified) {
+            self.pending = None;
+            return None;
+        }
+
+        // Wait until the modification time has been stable for one poll
+        if self.pending != Some(modified) {
+            self.pending = Some(modified);
+            return None;
+        }
+        self.pending = None;
+
+        log::info!("Configuration file changed, reloading");
+        match Config::load_validated(&self.path) {
+            Ok((config, warnings)) => {
+                for warning in warnings {
+                    log::warn!("Configuration warning: {warning}");
+                }
+                // Loading may rewrite the file to add missing fields
+                self.last_loaded = Self::modified_time(&self.path).ok().or(Some(modified));
+                self.last_reported_error = None;
+                Some(WatchEvent::Reloaded(Box::new(config)))
+            }
+            Err(e) => {
+                self.last_loaded = Some(modified);
+                let first_report = self.last_reported_error != Some(modified);
+                self.last_reported_error = Some(modified);
+                Some(WatchEvent::Invalid {
+                    error: e.to_string(),
+                    first_report,
+                })
+            }
+        }
+    }
+
+    fn modified_time(path: &Path) -> io::Result<SystemTime> {
+        fs::metadata(path)?.modified()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    const VALID_CONFIG: &str = r#"{
+        "log_level": "debug",
+        "syncthing_path": "test/syncthing.exe",
+        "web_ui_url": "http://localhost:1234",
+        "api_key": "",
+        "startup_args": [],
+        "process_closure_behavior": "close_managed",
+        "auto_launch_internal": false
+    }"#;
+
+    fn write_with_mtime(path: &PathBuf, contents: &str, secs: u64) {
+        let mut file = File::create(path).unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_no_event_without_change() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("configuration.json");
+        write_with_mtime(&path, VALID_CONFIG, 1000);
+
+        let mut watcher = ConfigWatcher::new(path);
+        assert!(watcher.poll().is_none());
+        assert!(watcher.poll().is_none());
+    }
+
+    #[test]
+    fn test_reload_after_stable_change() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("configuration.json");
+        write_with_mtime(&path, VALID_CONFIG, 1000);
+        let mut watcher = ConfigWatcher::new(path.clone());
+
+        write_with_mtime(&path, &VALID_CONFIG.replace("debug", "warn"), 2000);
+
+        // First poll only notices the change, second poll reloads it
+        assert!(watcher.poll().is_none());
+        match watcher.poll() {
+            Some(WatchEvent::Reloaded(config)) => assert_eq!(config.log_level, "warn"),
+            other => panic!("Expected reload, got {:?}", other),
+        }
+        assert!(watcher.poll().is_none());
+    }
+
+    #[test]
+    fn test_invalid_config_reported_once_per_modification() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("configuration.json");
+        write_with_mtime(&path, VALID_CONFIG, 1000);
+        let mut watcher = ConfigWatcher::new(path.clone());
+
+        write_with_mtime(&path, "{ not json", 2000);
+        assert!(watcher.poll().is_none());
+        match watcher.poll() {
+            Some(WatchEvent::Invalid { first_report, .. }) => assert!(first_report),
+            other => panic!("Expected invalid config, got {:?}", other),
+        }
+        // The same broken modification is not reloaded again
+        assert!(watcher.poll().is_none());
+
+        // A new broken modification is reported again
+        write_with_mtime(&path, "{ still not json", 3000);
+        assert!(watcher.poll().is_none());
+        assert!(matches!(
+            watcher.poll(),
+            Some(WatchEvent::Invalid {
+                first_report: true,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_deletion_and_recreation() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("configuration.json");
+        write_with_mtime(&path, VALID_CONFIG, 1000);
+        let mut watcher = ConfigWatcher::new(path.clone());
+
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(watcher.poll(), Some(WatchEvent::Deleted)));
+        assert!(watcher.poll().is_none());
+
+        write_with_mtime(&path, VALID_CONFIG, 1000);
+        assert!(watcher.poll().is_none());
+        assert!(matches!(watcher.poll(), Some(WatchEvent::Reloaded(_))));
+    }
+}
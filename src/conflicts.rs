@@ -0,0 +1,570 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app_state::AppState;
+use crate::config::InstanceConfig;
+use crate::error_handling::AppError;
+use crate::events::{Event, EventKind};
+use crate::folders;
+use crate::instance::Instance;
+use crate::syncthing_api::{FolderConfig, SyncthingClient};
+use crate::syncthing_config;
+
+/// Marker Syncthing puts into the names of conflict copies.
+pub const CONFLICT_MARKER: &str = ".sync-conflict-";
+
+/// How often the synced folders are scanned when Syncthing reports no conflict copies.
+const SCAN_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Directories in synced folders that hold Syncthing's own data.
+const SKIPPED_DIRS: [&str; 2] = [".stfolder", ".stversions"];
+
+/// A conflict copy Syncthing created when a file was changed on two devices.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Conflict {
+    /// Display name of the synced folder
+    pub folder: String,
+    /// The conflict copy, e.g. `notes.sync-conflict-20250601-101010-ABCDEFG.txt`
+    pub path: PathBuf,
+    /// The file the conflict copy competes with, e.g. `notes.txt`
+    pub original: PathBuf,
+    /// Where a kept conflict copy is moved to, e.g. `notes (conflict 20250601-101010).txt`
+    renamed: PathBuf,
+    /// Short ID of the device that made the change kept in the copy
+    pub device: Option<String>,
+}
+
+/// The parts of a conflict copy's file name.
+#[derive(Debug, PartialEq, Eq)]
+struct ConflictName<'a> {
+    stem: &'a str,
+    /// Time of the conflict as `YYYYMMDD-HHMMSS`
+    timestamp: &'a str,
+    /// Short ID of the device that made the change kept in the copy; missing in
+    /// names created by old Syncthing versions
+    device: Option<&'a str>,
+    /// Extension including the dot, or empty
+    extension: &'a str,
+}
+
+/// Splits a file name of the form `stem.sync-conflict-YYYYMMDD-HHMMSS-DEVICE.ext`.
+///
+/// A conflict copy of a conflict copy is split at the last marker, so its original
+/// is the first copy.
+fn parse_name(name: &str) -> Option<ConflictName<'_>> {
+    let (stem, rest) = name.rsplit_once(CONFLICT_MARKER)?;
+    let (info, extension) = rest.split_at(rest.find('.').unwrap_or(rest.len()));
+    let is_digits = |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_digit());
+    if stem.is_empty()
+        || info.len() < 15
+        || !is_digits(&info[..8], 8)
+        || !is_digits(&info[9..15], 6)
+    {
+        return None;
+    }
+    let device = match &info[15..] {
+        "" => None,
+        rest => Some(rest.strip_prefix('-')?),
+    };
+    Some(ConflictName {
+        stem,
+        timestamp: &info[..15],
+        device,
+        extension,
+    })
+}
+
+impl Conflict {
+    /// Creates the conflict for `path`, or `None` if it is not a conflict copy.
+    pub fn new(folder: &str, path: PathBuf) -> Option<Self> {
+        let name = parse_name(path.file_name()?.to_str()?)?;
+        Some(Self {
+            folder: folder.to_string(),
+            original: path.with_file_name(format!("{}{}", name.stem, name.extension)),
+            renamed: path.with_file_name(format!(
+                "{} (conflict {}){}",
+                name.stem, name.timestamp, name.extension
+            )),
+            device: name.device.map(str::to_string),
+            path,
+        })
+    }
+
+    /// Resolves the conflict and describes what was done.
+    pub fn resolve(&self, resolution: Resolution) -> io::Result<String> {
+        let original = &self.original;
+        match resolution {
+            Resolution::Local => {
+                if !original.exists() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!(
+                            "{} no longer exists; use keep-remote or keep-both",
+                            original.display()
+                        ),
+                    ));
+                }
+                fs::remove_file(&self.path)?;
+                Ok(format!(
+                    "Kept {} and deleted the conflict copy.",
+                    original.display()
+                ))
+            }
+            Resolution::Remote => {
+                fs::rename(&self.path, original)?;
+                Ok(format!(
+                    "Replaced {} with the conflict copy.",
+                    original.display()
+                ))
+            }
+            Resolution::Both => {
+                let renamed = &self.renamed;
+                if renamed.exists() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AlreadyExists,
+                        format!("{} already exists", renamed.display()),
+                    ));
+                }
+                fs::rename(&self.path, renamed)?;
+                Ok(format!(
+                    "Kept both versions; renamed the conflict copy to {}.",
+                    renamed.display()
+                ))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.folder, self.path.display())?;
+        match &self.device {
+            Some(device) => write!(f, " (changed on device {device})"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// How to resolve a conflict. The local version is the file at the original path,
+/// the remote version the conflict copy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+pub enum Resolution {
+    /// Keep the file at the original path and delete the conflict copy
+    #[serde(rename = "keep-local")]
+    #[value(name = "keep-local")]
+    Local,
+    /// Replace the file at the original path with the conflict copy
+    #[serde(rename = "keep-remote")]
+    #[value(name = "keep-remote")]
+    Remote,
+    /// Keep both, renaming the conflict copy so it is no longer reported
+    #[serde(rename = "keep-both")]
+    #[value(name = "keep-both")]
+    Both,
+}
+
+/// Number of conflict copies in the folders of an instance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictCount {
+    pub instance: String,
+    pub count: usize,
+}
+
+/// Folders shared by an instance: from the REST API while Syncthing runs, otherwise
+/// from its `config.xml`.
+pub fn synced_folders(
+    config: &InstanceConfig,
+    client: Option<&SyncthingClient>,
+) -> Vec<FolderConfig> {
+    if let Some(client) = client {
+        match client.config_folders() {
+            Ok(folders) => return folders,
+            Err(e) => log::debug!("Cannot list folders of {}: {}", config.name, e),
+        }
+    }
+    syncthing_config::discover_folders(config).unwrap_or_default()
+}
+
+/// Scans the folders for conflict copies, sorted by folder and path.
+pub fn scan(folders: &[FolderConfig]) -> Vec<Conflict> {
+    let mut conflicts = Vec::new();
+    for folder in folders {
+        scan_dir(
+            &folders::local_path(&folder.path),
+            folder.display_name(),
+            &mut conflicts,
+        );
+    }
+    conflicts.sort();
+    conflicts
+}
+
+/// Adds the conflict copies in `dir` and its subdirectories, without following
+/// symbolic links.
+fn scan_dir(dir: &Path, folder: &str, conflicts: &mut Vec<Conflict>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::debug!("Cannot scan {} for conflicts: {}", dir.display(), e);
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if file_type.is_dir() {
+            if !SKIPPED_DIRS.contains(&name.as_ref()) {
+                scan_dir(&entry.path(), folder, conflicts);
+            }
+        } else if file_type.is_file()
+            && name.contains(CONFLICT_MARKER)
+            && let Some(conflict) = Conflict::new(folder, entry.path())
+        {
+            conflicts.push(conflict);
+        }
+    }
+}
+
+/// An instance whose synced folders are scanned for conflicts.
+///
+/// Copied out of the application state, so the folders can be listed and scanned
+/// after the state lock is released.
+pub struct ScanTarget {
+    pub config: InstanceConfig,
+    /// Client of the running Syncthing, used to list the folders over REST
+    client: Option<SyncthingClient>,
+}
+
+impl ScanTarget {
+    pub fn new(instance: &mut Instance) -> Self {
+        Self {
+            client: instance.syncthing_running().then(|| instance.api_client()),
+            config: instance.config.clone(),
+        }
+    }
+
+    /// The folders shared by the instance.
+    pub fn folders(&self) -> Vec<FolderConfig> {
+        synced_folders(&self.config, self.client.as_ref())
+    }
+
+    /// Lists the conflict copies in the folders of the instance.
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        scan(&self.folders())
+    }
+}
+
+/// Describes the conflicts of an instance for the command line.
+pub fn describe(conflicts: &[Conflict]) -> String {
+    if conflicts.is_empty() {
+        return "No sync conflicts.".to_string();
+    }
+    let mut lines = vec![format!("{} sync conflict(s):", conflicts.len())];
+    lines.extend(conflicts.iter().map(|conflict| format!("  {conflict}")));
+    lines.join("\n")
+}
+
+/// Resolves the conflict copy at `path`, which must be in a synced folder of one of
+/// the instances.
+///
+/// Only the folder containing `path` is looked up, the folders are not scanned.
+pub fn resolve_path(
+    targets: &[ScanTarget],
+    path: &Path,
+    resolution: Resolution,
+) -> Result<String, AppError> {
+    let not_found = || {
+        AppError::Command(format!(
+            "{} is not a conflict copy in a synced folder.",
+            path.display()
+        ))
+    };
+    let wanted = fs::canonicalize(path).map_err(|_| not_found())?;
+
+    // With nested folders the innermost one contains the file
+    let mut containing: Option<(PathBuf, String)> = None;
+    for target in targets {
+        for folder in target.folders() {
+            let Ok(root) = fs::canonicalize(folders::local_path(&folder.path)) else {
+                continue;
+            };
+            if wanted.starts_with(&root)
+                && containing
+                    .as_ref()
+                    .is_none_or(|(outer, _)| root.starts_with(outer))
+            {
+                containing = Some((root, folder.display_name().to_string()));
+            }
+        }
+    }
+    let (root, folder) = containing.ok_or_else(not_found)?;
+
+    let in_syncthing_dir = wanted
+        .strip_prefix(&root)
+        .map(|relative| {
+            relative
+                .components()
+                .any(|part| SKIPPED_DIRS.iter().any(|dir| part.as_os_str() == *dir))
+        })
+        .unwrap_or(true);
+    if in_syncthing_dir || !wanted.is_file() {
+        return Err(not_found());
+    }
+    let conflict = Conflict::new(&folder, wanted).ok_or_else(not_found)?;
+
+    let message = conflict
+        .resolve(resolution)
+        .map_err(|e| AppError::Command(format!("Failed to resolve conflict: {e}")))?;
+    log::info!("Resolved conflict {}: {}", conflict.path.display(), message);
+    Ok(message)
+}
+
+/// Whether Syncthing reported a change of a conflict copy.
+fn is_conflict_change(kind: &EventKind) -> bool {
+    match kind {
+        EventKind::LocalChangeDetected { path, .. }
+        | EventKind::RemoteChangeDetected { path, .. } => path.contains(CONFLICT_MARKER),
+        _ => false,
+    }
+}
+
+/// Waits up to `timeout` for Syncthing to report a changed conflict copy.
+fn wait_for_conflict_change(events: &Receiver<Event>, timeout: Duration) {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match events.recv_timeout(remaining) {
+            Ok(event) if is_conflict_change(&event.kind) => return,
+            Ok(_) => {}
+            Err(RecvTimeoutError::Timeout) => return,
+            Err(RecvTimeoutError::Disconnected) => {
+                thread::sleep(remaining);
+                return;
+            }
+        }
+    }
+}
+
+/// Scans the synced folders of all instances for conflict copies on a background
+/// thread, logging each new one.
+///
+/// Folders are scanned every `SCAN_INTERVAL` and whenever Syncthing reports a change
+/// of a conflict copy. `on_update` receives the number of conflicts per instance
+/// whenever it changes.
+pub fn spawn<F>(app_state: Arc<Mutex<AppState>>, mut on_update: F)
+where
+    F: FnMut(Vec<ConflictCount>) + Send + 'static,
+{
+    thread::spawn(move || {
+        let Ok(events) = app_state.lock().map(|state| state.events.subscribe()) else {
+            log::error!("Failed to lock app state for subscribing to events");
+            return;
+        };
+        let mut known: HashSet<PathBuf> = HashSet::new();
+        let mut last: Vec<ConflictCount> = Vec::new();
+
+        loop {
+            // Instances are re-read on every round so configuration reloads apply
+            let Ok(targets) = app_state.lock().map(|mut state| {
+                state
+                    .instances
+                    .iter_mut()
+                    .map(ScanTarget::new)
+                    .collect::<Vec<_>>()
+            }) else {
+                log::error!("Failed to lock app state for scanning conflicts");
+                return;
+            };
+
+            // The state lock is released before scanning the folders
+            let mut found = HashSet::new();
+            let mut counts = Vec::new();
+            for target in targets {
+                let conflicts = target.conflicts();
+                for conflict in &conflicts {
+                    if !known.contains(&conflict.path) {
+                        log::warn!("{}: sync conflict {}", target.config.name, conflict);
+                    }
+                    found.insert(conflict.path.clone());
+                }
+                counts.push(ConflictCount {
+                    instance: target.config.name,
+                    count: conflicts.len(),
+                });
+            }
+            known = found;
+
+            if counts != last {
+                on_update(counts.clone());
+                last = counts;
+            }
+
+            wait_for_conflict_change(&events, SCAN_INTERVAL);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const COPY: &str = "notes.sync-conflict-20250601-101010-ABCDEFG.txt";
+
+    fn folder(dir: &TempDir) -> FolderConfig {
+        FolderConfig {
+            id: "docs".to_string(),
+            label: "Documents".to_string(),
+            path: dir.path().to_string_lossy().to_string(),
+            paused: false,
+            devices: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_conflict_names() {
+        assert_eq!(
+            parse_name(COPY),
+            Some(ConflictName {
+                stem: "notes",
+                timestamp: "20250601-101010",
+                device: Some("ABCDEFG"),
+                extension: ".txt",
+            })
+        );
+
+        let old = parse_name("Makefile.sync-conflict-20250601-101010").unwrap();
+        assert_eq!(
+            (old.stem, old.device, old.extension),
+            ("Makefile", None, "")
+        );
+
+        let nested = parse_name(
+            "a.sync-conflict-20250601-101010-AAAAAAA.sync-conflict-20250602-111111-BBBBBBB.tar.gz",
+        )
+        .unwrap();
+        assert_eq!(nested.stem, "a.sync-conflict-20250601-101010-AAAAAAA");
+        assert_eq!(nested.extension, ".tar.gz");
+
+        assert_eq!(parse_name("notes.txt"), None);
+        assert_eq!(parse_name("notes.sync-conflict-later.txt"), None);
+        assert_eq!(parse_name(".sync-conflict-20250601-101010-ABCDEFG"), None);
+    }
+
+    #[test]
+    fn test_scan_finds_conflicts_outside_syncthing_dirs() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("sub/deeper")).unwrap();
+        fs::create_dir_all(dir.path().join(".stversions")).unwrap();
+        fs::write(dir.path().join("notes.txt"), "local").unwrap();
+        fs::write(dir.path().join(COPY), "remote").unwrap();
+        fs::write(dir.path().join("sub/deeper").join(COPY), "remote").unwrap();
+        fs::write(dir.path().join(".stversions").join(COPY), "old").unwrap();
+
+        let conflicts = scan(&[folder(&dir)]);
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0].path, dir.path().join(COPY));
+        assert_eq!(conflicts[0].original, dir.path().join("notes.txt"));
+        assert_eq!(
+            conflicts[0].to_string(),
+            format!(
+                "Documents: {} (changed on device ABCDEFG)",
+                dir.path().join(COPY).display()
+            )
+        );
+        assert_eq!(conflicts[1].path, dir.path().join("sub/deeper").join(COPY));
+    }
+
+    #[test]
+    fn test_resolve_conflicts() {
+        let dir = TempDir::new().unwrap();
+        let original = dir.path().join("notes.txt");
+        let copy = dir.path().join(COPY);
+        let conflict = Conflict::new("Documents", copy.clone()).unwrap();
+
+        // Keeping the local version needs the original file
+        fs::write(&copy, "remote").unwrap();
+        assert!(conflict.resolve(Resolution::Local).is_err());
+        assert!(copy.exists());
+
+        fs::write(&original, "local").unwrap();
+        conflict.resolve(Resolution::Local).unwrap();
+        assert!(!copy.exists());
+        assert_eq!(fs::read_to_string(&original).unwrap(), "local");
+
+        fs::write(&copy, "remote").unwrap();
+        conflict.resolve(Resolution::Remote).unwrap();
+        assert!(!copy.exists());
+        assert_eq!(fs::read_to_string(&original).unwrap(), "remote");
+
+        fs::write(&copy, "other").unwrap();
+        conflict.resolve(Resolution::Both).unwrap();
+        assert!(!copy.exists());
+        let renamed = dir.path().join("notes (conflict 20250601-101010).txt");
+        assert_eq!(fs::read_to_string(&renamed).unwrap(), "other");
+        assert!(scan(&[folder(&dir)]).is_empty());
+
+        // An earlier renamed copy is not overwritten
+        fs::write(&copy, "again").unwrap();
+        assert!(conflict.resolve(Resolution::Both).is_err());
+        assert_eq!(fs::read_to_string(&renamed).unwrap(), "other");
+    }
+
+    #[test]
+    fn test_resolve_path_uses_innermost_containing_folder() {
+        let home = TempDir::new().unwrap();
+        let outer = TempDir::new().unwrap();
+        let inner = outer.path().join("photos");
+        fs::create_dir_all(inner.join(".stversions")).unwrap();
+        fs::write(
+            home.path().join("config.xml"),
+            format!(
+                r#"<configuration>
+                    <folder id="all" label="All" path="{}"/>
+                    <folder id="photos" label="Photos" path="{}"/>
+                </configuration>"#,
+                outer.path().display(),
+                inner.display()
+            ),
+        )
+        .unwrap();
+        let targets = [ScanTarget {
+            config: InstanceConfig {
+                home: Some(home.path().to_string_lossy().to_string()),
+                ..InstanceConfig::default()
+            },
+            client: None,
+        }];
+
+        // Copies in Syncthing's own directories and missing files are rejected
+        let versioned = inner.join(".stversions").join(COPY);
+        fs::write(&versioned, "old").unwrap();
+        for path in [versioned, inner.join(COPY), outer.path().join("notes.txt")] {
+            let error = resolve_path(&targets, &path, Resolution::Both).unwrap_err();
+            assert!(
+                error.to_string().contains("is not a conflict copy"),
+                "{error}"
+            );
+        }
+
+        fs::write(inner.join(COPY), "remote").unwrap();
+        fs::write(outer.path().join("notes.txt"), "local").unwrap();
+        let message = resolve_path(&targets, &inner.join(COPY), Resolution::Both).unwrap();
+        assert!(
+            inner.join("notes (conflict 20250601-101010).txt").exists(),
+            "{message}"
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use crate::syncthing_api::{ApiError, SyncthingClient};
+use crate::utils;
+
+/// A remote device as shown in the tray menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceMenuState {
+    pub id: String,
+    /// The device name, or its short ID if it has none
+    pub name: String,
+    pub paused: bool,
+    pub connected: bool,
+    /// Address of the current connection, empty while disconnected
+    pub address: String,
+    /// How much of the shared data the device has, in percent
+    pub completion: u8,
+}
+
+impl DeviceMenuState {
+    /// Whether the menu built for `other` also fits this device, i.e. only the
+    /// connection state or completion differ.
+    pub fn same_menu(&self, other: &DeviceMenuState) -> bool {
+        self.id == other.id && self.name == other.name && self.paused == other.paused
+    }
+
+    /// Menu line describing the device, e.g. `build-server: connected, 10.0.0.5:22000 (87%)`.
+    pub fn label(&self) -> String {
+        if self.paused {
+            format!("{}: paused ({}%)", self.name, self.completion)
+        } else if self.connected {
+            format!(
+                "{}: connected, {} ({}%)",
+                self.name, self.address, self.completion
+            )
+        } else {
+            format!("{}: disconnected ({}%)", self.name, self.completion)
+        }
+    }
+}
+
+/// Queries the configured remote devices with their connection state and completion.
+pub fn query(client: &SyncthingClient) -> Result<Vec<DeviceMenuState>, ApiError> {
+    let connections = client.system_connections()?.connections;
+
+    // The configuration also lists this device, which has no connection entry
+    client
+        .config_devices()?
+        .into_iter()
+        .filter_map(|device| {
+            let connection = connections.get(&device.device_id)?;
+            Some((device, connection))
+        })
+        .map(|(device, connection)| {
+            let completion = client.db_completion(None, Some(&device.device_id))?;
+            Ok(DeviceMenuState {
+                name: device.display_name().to_string(),
+                id: device.device_id,
+                paused: device.paused,
+                connected: connection.connected,
+                address: connection.address.clone(),
+                completion: utils::completion_percent(completion.completion),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syncthing_api::mock::MockServer;
+
+    #[test]
+    fn test_query_devices() {
+        let server = MockServer::start(vec![
+            (
+                "/rest/config/devices",
+                200,
+                r#"[
+                    {"deviceID":"MYSELF1-AAAAAAA","name":"laptop"},
+                    {"deviceID":"BUILDSV-BBBBBBB","name":"build-server","paused":false},
+                    {"deviceID":"PHONE12-CCCCCCC","name":"","paused":true}
+                ]"#,
+            ),
+            (
+                "/rest/system/connections",
+                200,
+                r#"{
+                    "connections": {
+                        "BUILDSV-BBBBBBB": {"connected": true, "address": "10.0.0.5:22000"},
+                        "PHONE12-CCCCCCC": {"connected": false, "paused": true}
+                    },
+                    "total": {}
+                }"#,
+            ),
+            (
+                "/rest/db/completion",
+                200,
+                r#"{"completion":87.5,"needBytes":1024}"#,
+            ),
+        ]);
+        let client = SyncthingClient::new(&server.base_url, "key");
+
+        let devices = query(&client).unwrap();
+        assert_eq!(devices.len(), 2);
+        let build_server = devices.iter().find(|d| d.name == "build-server").unwrap();
+        assert_eq!(
+            build_server.label(),
+            "build-server: connected, 10.0.0.5:22000 (87%)"
+        );
+        let phone = devices.iter().find(|d| d.id == "PHONE12-CCCCCCC").unwrap();
+        assert_eq!(phone.label(), "PHONE12: paused (87%)");
+
+        let disconnected = DeviceMenuState {
+            connected: false,
+            ..build_server.clone()
+        };
+        assert_eq!(disconnected.label(), "build-server: disconnected (87%)");
+        assert!(disconnected.same_menu(build_server));
+    }
+}
@@ -0,0 +1,164 @@
+use std::sync::Arc;
+
+/// Modal dialogs shown to the user.
+///
+/// Code that may need an answer from the user takes the implementation as a
+/// dependency, so tests and headless runs can answer automatically.
+pub trait Dialogs: Send + Sync {
+    /// Shows an informational message.
+    fn info(&self, message: &str, caption: &str);
+
+    /// Shows an error message.
+    fn error(&self, message: &str, caption: &str);
+
+    /// Asks a yes/no question and returns whether the answer was yes.
+    fn ask_yes_no(&self, question: &str, caption: &str) -> bool;
+}
+
+/// Native dialogs of the platform: message boxes on Windows, zenity or kdialog
+/// elsewhere, falling back to stderr when neither is installed.
+pub fn native() -> Arc<dyn Dialogs> {
+    Arc::new(platform::NativeDialogs)
+}
+
+/// Dialogs for runs without a desktop session: messages are written to stderr and
+/// questions get a fixed answer.
+pub struct HeadlessDialogs {
+    pub answer: bool,
+}
+
+impl Dialogs for HeadlessDialogs {
+    fn info(&self, message: &str, caption: &str) {
+        eprintln!("{}: {}", caption, message);
+    }
+
+    fn error(&self, message: &str, caption: &str) {
+        eprintln!("{}: {}", caption, message);
+    }
+
+    fn ask_yes_no(&self, question: &str, caption: &str) -> bool {
+        log::info!(
+            "{}: {} Answering {} automatically",
+            caption,
+            question,
+            if self.answer { "yes" } else { "no" }
+        );
+        self.answer
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::ptr;
+    use winapi::um::winuser::{
+        IDYES, MB_ICONERROR, MB_ICONINFORMATION, MB_ICONQUESTION, MB_OK, MB_YESNO, MessageBoxW,
+    };
+
+    use super::Dialogs;
+
+    pub struct NativeDialogs;
+
+    impl NativeDialogs {
+        fn message_box(text: &str, caption: &str, flags: u32) -> i32 {
+            let text_w: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+            let caption_w: Vec<u16> = caption.encode_utf16().chain(std::iter::once(0)).collect();
+            unsafe { MessageBoxW(ptr::null_mut(), text_w.as_ptr(), caption_w.as_ptr(), flags) }
+        }
+    }
+
+    impl Dialogs for NativeDialogs {
+        fn info(&self, message: &str, caption: &str) {
+            Self::message_box(message, caption, MB_OK | MB_ICONINFORMATION);
+        }
+
+        fn error(&self, message: &str, caption: &str) {
+            Self::message_box(message, caption, MB_OK | MB_ICONERROR);
+        }
+
+        fn ask_yes_no(&self, question: &str, caption: &str) -> bool {
+            Self::message_box(question, caption, MB_YESNO | MB_ICONQUESTION) == IDYES
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use std::process::{Command, Stdio};
+
+    use super::Dialogs;
+
+    pub struct NativeDialogs;
+
+    /// Runs a dialog tool and returns whether it exited successfully, or `None` if
+    /// the tool is not installed.
+    fn run_tool(program: &str, args: &[&str]) -> Option<bool> {
+        Command::new(program)
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .ok()
+    }
+
+    impl Dialogs for NativeDialogs {
+        fn info(&self, message: &str, caption: &str) {
+            let shown = run_tool(
+                "zenity",
+                &[
+                    "--info",
+                    "--no-markup",
+                    "--title",
+                    caption,
+                    "--text",
+                    message,
+                ],
+            )
+            .or_else(|| run_tool("kdialog", &["--title", caption, "--msgbox", message]));
+            if shown.is_none() {
+                eprintln!("{}: {}", caption, message);
+            }
+        }
+
+        fn error(&self, message: &str, caption: &str) {
+            let shown = run_tool(
+                "zenity",
+                &[
+                    "--error",
+                    "--no-markup",
+                    "--title",
+                    caption,
+                    "--text",
+                    message,
+                ],
+            )
+            .or_else(|| run_tool("kdialog", &["--title", caption, "--error", message]));
+            if shown.is_none() {
+                eprintln!("{}: {}", caption, message);
+            }
+        }
+
+        fn ask_yes_no(&self, question: &str, caption: &str) -> bool {
+            // Both tools exit with 0 for yes and 1 for no
+            run_tool(
+                "zenity",
+                &[
+                    "--question",
+                    "--no-markup",
+                    "--title",
+                    caption,
+                    "--text",
+                    question,
+                ],
+            )
+            .or_else(|| run_tool("kdialog", &["--title", caption, "--yesno", question]))
+            .unwrap_or_else(|| {
+                eprintln!(
+                    "{}: {} (no dialog tool found, answering no)",
+                    caption, question
+                );
+                false
+            })
+        }
+    }
+}
@@ -1,57 +1,23 @@
 use thiserror::Error;
 
+use crate::config_validation::ConfigProblems;
+
 #[derive(Debug, Error)]
 pub enum AppError {
-    #[error("Configuration error: {0}")]
-    Config(String),
+    #[error("Configuration error:\n{0}")]
+    Config(ConfigProblems),
     #[error("Process error: {0}")]
     Process(String),
     #[error("Tray UI error: {0}")]
     TrayUi(String),
+    #[error("Syncthing API error: {0}")]
+    Api(String),
+    #[error("{0}")]
+    Command(String),
 }
 
-impl From<serde_json::Error> for AppError {
-    fn from(e: serde_json::Error) -> Self {
-        AppError::Config(e.to_string())
+impl From<crate::syncthing_api::ApiError> for AppError {
+    fn from(e: crate::syncthing_api::ApiError) -> Self {
+        AppError::Api(e.to_string())
     }
 }
-
-// Helper for showing native error dialogs on Windows
-#[cfg(target_os = "windows")]
-pub fn show_native_error_dialog(msg: &str, caption: &str) {
-    use std::ptr;
-    use winapi::um::winuser::{MB_ICONERROR, MB_OK, MessageBoxW};
-    let msg_w: Vec<u16> = msg.encode_utf16().chain(std::iter::once(0)).collect();
-    let caption_w: Vec<u16> = caption.encode_utf16().chain(std::iter::once(0)).collect();
-    unsafe {
-        MessageBoxW(
-            ptr::null_mut(),
-            msg_w.as_ptr(),
-            caption_w.as_ptr(),
-            MB_OK | MB_ICONERROR,
-        );
-    }
-}
-
-pub fn show_native_yes_no_question_dialog(msg: &str, caption: &str) -> bool {
-    use std::ptr;
-    use winapi::um::winuser::{IDYES, MB_ICONQUESTION, MB_YESNO, MessageBoxW};
-    let msg_w: Vec<u16> = msg.encode_utf16().chain(std::iter::once(0)).collect();
-    let caption_w: Vec<u16> = caption.encode_utf16().chain(std::iter::once(0)).collect();
-    let result: i32;
-    unsafe {
-        result = MessageBoxW(
-            ptr::null_mut(),
-            msg_w.as_ptr(),
-            caption_w.as_ptr(),
-            MB_YESNO | MB_ICONQUESTION,
-        );
-    }
-    result == IDYES
-}
-
-// Dummy alternative for non-Windows platforms: just log the error
-#[cfg(not(target_os = "windows"))]
-pub fn show_native_error_dialog(msg: &str, caption: &str) {
-    eprintln!("{}: {}", caption, msg);
-}
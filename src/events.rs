@@ -0,0 +1,488 @@
+use std::collections::HashSet;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+use crate::app_state::AppState;
+use crate::syncthing_api::{ApiError, RawEvent, SyncthingClient};
+
+/// How long Syncthing holds an event request open when there are no new events.
+const LONG_POLL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Reconnect delays after a failed request, doubling up to the maximum.
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How often newly configured instances are picked up.
+const INSTANCE_SCAN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A folder error from a `FolderErrors` event.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FolderError {
+    pub path: String,
+    pub error: String,
+}
+
+/// Syncthing events the app reacts to, including process events raised by the app.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EventKind {
+    /// A folder changed state, e.g. from `idle` to `scanning`
+    StateChanged {
+        folder: String,
+        from: String,
+        to: String,
+    },
+    /// A remote device's completion of a folder changed
+    FolderCompletion {
+        folder: String,
+        device: String,
+        completion: f64,
+    },
+    DeviceConnected {
+        device: String,
+        address: String,
+    },
+    DeviceDisconnected {
+        device: String,
+        error: String,
+    },
+    /// Files of a folder could not be synced
+    FolderErrors {
+        folder: String,
+        errors: Vec<FolderError>,
+    },
+    /// A file was changed locally, from `/rest/events/disk`
+    LocalChangeDetected {
+        folder: String,
+        path: String,
+        action: String,
+    },
+    /// A file was changed by a remote device, from `/rest/events/disk`
+    RemoteChangeDetected {
+        folder: String,
+        path: String,
+        action: String,
+    },
+    /// A managed Syncthing process exited on its own; raised by the app
+    ProcessExited {
+        code: Option<i32>,
+    },
+    /// Automatic restarts stopped after a crash loop; raised by the app
+    RestartGaveUp {
+        attempts: u32,
+    },
+}
+
+impl EventKind {
+    /// Decodes the events the app knows about; other event types yield `None`.
+    pub fn from_raw(event: &RawEvent) -> Option<Self> {
+        fn data<T: DeserializeOwned>(event: &RawEvent) -> Option<T> {
+            serde_json::from_value(event.data.clone())
+                .map_err(|e| log::debug!("Cannot decode {} event {}: {}", event.kind, event.id, e))
+                .ok()
+        }
+
+        #[derive(Deserialize)]
+        struct StateChanged {
+            folder: String,
+            from: String,
+            to: String,
+        }
+        #[derive(Deserialize)]
+        struct FolderCompletion {
+            folder: String,
+            device: String,
+            completion: f64,
+        }
+        #[derive(Deserialize)]
+        struct DeviceConnected {
+            id: String,
+            #[serde(default)]
+            addr: String,
+        }
+        #[derive(Deserialize)]
+        struct DeviceDisconnected {
+            id: String,
+            #[serde(default)]
+            error: String,
+        }
+        #[derive(Deserialize)]
+        struct FolderErrors {
+            folder: String,
+            #[serde(default)]
+            errors: Vec<FolderError>,
+        }
+        #[derive(Deserialize)]
+        struct ChangeDetected {
+            folder: String,
+            path: String,
+            action: String,
+        }
+
+        Some(match event.kind.as_str() {
+            "StateChanged" => {
+                let data: StateChanged = data(event)?;
+                EventKind::StateChanged {
+                    folder: data.folder,
+                    from: data.from,
+                    to: data.to,
+                }
+            }
+            "FolderCompletion" => {
+                let data: FolderCompletion = data(event)?;
+                EventKind::FolderCompletion {
+                    folder: data.folder,
+                    device: data.device,
+                    completion: data.completion,
+                }
+            }
+            "DeviceConnected" => {
+                let data: DeviceConnected = data(event)?;
+                EventKind::DeviceConnected {
+                    device: data.id,
+                    address: data.addr,
+                }
+            }
+            "DeviceDisconnected" => {
+                let data: DeviceDisconnected = data(event)?;
+                EventKind::DeviceDisconnected {
+                    device: data.id,
+                    error: data.error,
+                }
+            }
+            "FolderErrors" => {
+                let data: FolderErrors = data(event)?;
+                EventKind::FolderErrors {
+                    folder: data.folder,
+                    errors: data.errors,
+                }
+            }
+            "LocalChangeDetected" => {
+                let data: ChangeDetected = data(event)?;
+                EventKind::LocalChangeDetected {
+                    folder: data.folder,
+                    path: data.path,
+                    action: data.action,
+                }
+            }
+            "RemoteChangeDetected" => {
+                let data: ChangeDetected = data(event)?;
+                EventKind::RemoteChangeDetected {
+                    folder: data.folder,
+                    path: data.path,
+                    action: data.action,
+                }
+            }
+            _ => return None,
+        })
+    }
+
+    /// Whether the event changes what the tray shows for folders and devices.
+    ///
+    /// Completion progress is left to regular polling, since it is reported many
+    /// times per second while syncing.
+    pub fn affects_status(&self) -> bool {
+        matches!(
+            self,
+            EventKind::StateChanged { .. }
+                | EventKind::DeviceConnected { .. }
+                | EventKind::DeviceDisconnected { .. }
+                | EventKind::FolderErrors { .. }
+        )
+    }
+}
+
+/// An event received from a Syncthing instance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    /// Name of the instance the event came from
+    pub instance: String,
+    /// Event ID, increasing per endpoint until Syncthing restarts; 0 for events
+    /// raised by the app
+    pub id: u64,
+    pub kind: EventKind,
+}
+
+/// Delivers Syncthing events to subscribers inside the app.
+///
+/// Cheap to clone; all clones share the same subscribers.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<Mutex<Vec<Sender<Event>>>>,
+}
+
+impl EventBus {
+    /// Returns a receiver for all events published from now on.
+    pub fn subscribe(&self) -> Receiver<Event> {
+        let (sender, receiver) = mpsc::channel();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(sender);
+        }
+        receiver
+    }
+
+    /// Sends an event to every subscriber, forgetting those that went away.
+    pub fn publish(&self, event: Event) {
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        }
+    }
+}
+
+/// The event endpoints consumed for each instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Stream {
+    General,
+    Disk,
+}
+
+impl Stream {
+    fn path(self) -> &'static str {
+        match self {
+            Stream::General => "/rest/events",
+            Stream::Disk => "/rest/events/disk",
+        }
+    }
+
+    /// Event types requested from the endpoint; the disk endpoint only has change events.
+    fn filter(self) -> Option<&'static str> {
+        match self {
+            Stream::General => Some(
+                "StateChanged,FolderCompletion,DeviceConnected,DeviceDisconnected,FolderErrors",
+            ),
+            Stream::Disk => None,
+        }
+    }
+}
+
+/// Fetches the next batch of events of `stream`, advancing `cursor` to the last seen ID.
+///
+/// Without a cursor only the latest ID is fetched and nothing is returned, so
+/// events buffered before connecting are not replayed.
+fn poll_once(
+    client: &SyncthingClient,
+    stream: Stream,
+    cursor: &mut Option<u64>,
+) -> Result<Vec<RawEvent>, ApiError> {
+    let since = cursor.unwrap_or(0).to_string();
+    let mut query = vec![("since", since.as_str())];
+    if let Some(filter) = stream.filter() {
+        query.push(("events", filter));
+    }
+    if cursor.is_none() {
+        query.push(("limit", "1"));
+    }
+
+    let events = client.events(stream.path(), &query, LONG_POLL_TIMEOUT)?;
+    let Some(last) = events.iter().map(|event| event.id).max() else {
+        return Ok(Vec::new());
+    };
+    let previous = cursor.replace(last.max(cursor.unwrap_or(0)));
+    Ok(match previous {
+        Some(since) => events.into_iter().filter(|e| e.id > since).collect(),
+        None => Vec::new(),
+    })
+}
+
+/// Spawns background threads consuming the event endpoints of every configured
+/// instance and publishing them on `AppState::events`.
+///
+/// Instances added by a configuration reload are picked up within
+/// `INSTANCE_SCAN_INTERVAL`; consumers of removed instances stop.
+pub fn spawn(app_state: Arc<Mutex<AppState>>) {
+    let consumers: Arc<Mutex<HashSet<String>>> = Arc::default();
+
+    thread::spawn(move || {
+        loop {
+            let Ok(names) = app_state.lock().map(|state| {
+                state
+                    .instances
+                    .iter()
+                    .map(|instance| instance.name().to_string())
+                    .collect::<Vec<_>>()
+            }) else {
+                log::error!("Failed to lock app state for event streaming");
+                return;
+            };
+
+            for name in names {
+                let is_new = consumers
+                    .lock()
+                    .map(|mut consumers| consumers.insert(name.clone()))
+                    .unwrap_or(false);
+                if is_new {
+                    for stream in [Stream::General, Stream::Disk] {
+                        spawn_consumer(app_state.clone(), consumers.clone(), name.clone(), stream);
+                    }
+                }
+            }
+
+            thread::sleep(INSTANCE_SCAN_INTERVAL);
+        }
+    });
+}
+
+/// Consumes one event endpoint of an instance until the instance is removed.
+fn spawn_consumer(
+    app_state: Arc<Mutex<AppState>>,
+    consumers: Arc<Mutex<HashSet<String>>>,
+    name: String,
+    stream: Stream,
+) {
+    thread::spawn(move || {
+        let mut cursor = None;
+        let mut backoff = MIN_BACKOFF;
+        let mut connected = false;
+
+        loop {
+            // The client is looked up again on every request so configuration
+            // reloads apply
+            let Ok(state) = app_state.lock() else {
+                log::error!("Failed to lock app state for event streaming");
+                return;
+            };
+            let Some(instance) = state.instances.iter().find(|i| i.name() == name) else {
+                drop(state);
+                if let Ok(mut consumers) = consumers.lock() {
+                    consumers.remove(&name);
+                }
+                log::debug!("Stopped consuming {} events of {}", stream.path(), name);
+                return;
+            };
+            let client = instance.api_client();
+            let bus = state.events.clone();
+            drop(state);
+
+            match poll_once(&client, stream, &mut cursor) {
+                Ok(events) => {
+                    if !connected {
+                        log::debug!("Consuming {} events of {}", stream.path(), name);
+                        connected = true;
+                    }
+                    backoff = MIN_BACKOFF;
+                    for event in events {
+                        let Some(kind) = EventKind::from_raw(&event) else {
+                            continue;
+                        };
+                        log_event(&name, &kind);
+                        bus.publish(Event {
+                            instance: name.clone(),
+                            id: event.id,
+                            kind,
+                        });
+                    }
+                }
+                Err(e) => {
+                    if connected {
+                        log::info!("Lost {} events of {}: {}", stream.path(), name, e);
+                        connected = false;
+                    }
+                    // Event IDs start over when Syncthing restarts
+                    cursor = None;
+                    thread::sleep(backoff);
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+    });
+}
+
+/// Logs an event; folder errors are warnings, everything else is debug output.
+fn log_event(instance: &str, kind: &EventKind) {
+    match kind {
+        EventKind::FolderErrors { folder, errors } => {
+            for error in errors {
+                log::warn!(
+                    "{}: cannot sync {} in folder {}: {}",
+                    instance,
+                    error.path,
+                    folder,
+                    error.error
+                );
+            }
+        }
+        kind => log::debug!("{}: {:?}", instance, kind),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syncthing_api::mock::MockServer;
+
+    const EVENTS: &str = r#"[
+        {"id":5,"type":"StateChanged","time":"2025-06-01T10:00:00Z",
+         "data":{"folder":"docs","from":"idle","to":"scanning","duration":0.5}},
+        {"id":6,"type":"ConfigSaved","time":"2025-06-01T10:00:01Z","data":{}},
+        {"id":7,"type":"FolderErrors","time":"2025-06-01T10:00:02Z",
+         "data":{"folder":"docs","errors":[{"error":"permission denied","path":"a.txt"}]}}
+    ]"#;
+
+    #[test]
+    fn test_poll_once_skips_history_then_advances() {
+        let server = MockServer::start(vec![("/rest/events", 200, EVENTS)]);
+        let client = SyncthingClient::new(&server.base_url, "key");
+
+        let mut cursor = None;
+        assert!(
+            poll_once(&client, Stream::General, &mut cursor)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(cursor, Some(7));
+        assert!(server.recorded()[0].url.contains("limit=1"));
+
+        let mut cursor = Some(5);
+        let events = poll_once(&client, Stream::General, &mut cursor).unwrap();
+        assert_eq!(cursor, Some(7));
+        let ids: Vec<u64> = events.iter().map(|event| event.id).collect();
+        assert_eq!(ids, vec![6, 7]);
+        let request = &server.recorded()[1].url;
+        assert!(request.contains("since=5"));
+        assert!(request.contains("timeout=60"));
+    }
+
+    #[test]
+    fn test_typed_events() {
+        let events: Vec<RawEvent> = serde_json::from_str(EVENTS).unwrap();
+        let kinds: Vec<Option<EventKind>> = events.iter().map(EventKind::from_raw).collect();
+        assert_eq!(
+            kinds[0],
+            Some(EventKind::StateChanged {
+                folder: "docs".to_string(),
+                from: "idle".to_string(),
+                to: "scanning".to_string(),
+            })
+        );
+        assert_eq!(kinds[1], None);
+        assert!(matches!(
+            &kinds[2],
+            Some(EventKind::FolderErrors { errors, .. }) if errors[0].path == "a.txt"
+        ));
+        assert!(kinds[2].as_ref().unwrap().affects_status());
+    }
+
+    #[test]
+    fn test_event_bus_delivers_to_live_subscribers() {
+        let bus = EventBus::default();
+        let first = bus.subscribe();
+        let second = bus.subscribe();
+        drop(second);
+
+        let event = Event {
+            instance: "Syncthing".to_string(),
+            id: 1,
+            kind: EventKind::DeviceConnected {
+                device: "BUILDSV".to_string(),
+                address: "10.0.0.5:22000".to_string(),
+            },
+        };
+        bus.publish(event.clone());
+        assert_eq!(first.try_recv().unwrap(), event);
+        assert_eq!(bus.subscribers.lock().unwrap().len(), 1);
+    }
+}
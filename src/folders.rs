@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+use crate::syncthing_api::{ApiError, SyncthingClient};
+use crate::utils;
+
+/// A shared folder as shown in the tray menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FolderMenuState {
+    pub id: String,
+    /// The folder label, or its ID if it has none
+    pub name: String,
+    /// Local path as configured in Syncthing, possibly starting with `~`
+    pub path: String,
+    pub paused: bool,
+    /// State reported by `/rest/db/status`, e.g. `idle`, `scanning` or `syncing`
+    pub state: String,
+    /// Local completion in percent
+    pub completion: u8,
+}
+
+impl FolderMenuState {
+    /// Whether the menu built for `other` also fits this folder, i.e. only the
+    /// state or completion differ.
+    pub fn same_menu(&self, other: &FolderMenuState) -> bool {
+        self.id == other.id
+            && self.name == other.name
+            && self.path == other.path
+            && self.paused == other.paused
+    }
+
+    /// Menu line describing the folder, e.g. `Documents: syncing (45%)`.
+    pub fn label(&self) -> String {
+        let state = if self.paused { "paused" } else { &self.state };
+        format!("{}: {} ({}%)", self.name, state, self.completion)
+    }
+}
+
+/// Queries the configured folders and their status.
+pub fn query(client: &SyncthingClient) -> Result<Vec<FolderMenuState>, ApiError> {
+    client
+        .config_folders()?
+        .into_iter()
+        .map(|folder| {
+            let status = client.db_status(&folder.id)?;
+            Ok(FolderMenuState {
+                name: folder.display_name().to_string(),
+                completion: utils::completion_percent(status.completion()),
+                id: folder.id,
+                path: folder.path,
+                paused: folder.paused,
+                state: status.state,
+            })
+        })
+        .collect()
+}
+
+/// Expands a leading `~` in a folder path the way Syncthing does.
+pub fn local_path(path: &str) -> PathBuf {
+    let rest = path
+        .strip_prefix("~/")
+        .or_else(|| path.strip_prefix("~\\"))
+        .or_else(|| (path == "~").then_some(""));
+    match (rest, dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syncthing_api::mock::MockServer;
+
+    #[test]
+    fn test_query_folders() {
+        let server = MockServer::start(vec![
+            (
+                "/rest/config/folders",
+                200,
+                r#"[{"id":"docs","label":"Documents","path":"~/Documents","paused":true}]"#,
+            ),
+            (
+                "/rest/db/status",
+                200,
+                r#"{"state":"idle","globalBytes":300,"inSyncBytes":299,"needBytes":1}"#,
+            ),
+        ]);
+        let client = SyncthingClient::new(&server.base_url, "key");
+
+        let folders = query(&client).unwrap();
+        assert_eq!(folders.len(), 1);
+        assert_eq!(folders[0].completion, 99);
+        assert_eq!(folders[0].label(), "Documents: paused (99%)");
+
+        let resumed = FolderMenuState {
+            paused: false,
+            ..folders[0].clone()
+        };
+        assert_eq!(resumed.label(), "Documents: idle (99%)");
+        assert!(!resumed.same_menu(&folders[0]));
+    }
+
+    #[test]
+    fn test_local_path_expands_home() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(local_path("~/Sync"), home.join("Sync"));
+        assert_eq!(local_path("~"), home);
+        assert_eq!(local_path("/data/Sync"), PathBuf::from("/data/Sync"));
+    }
+}
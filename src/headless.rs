@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::app_state::AppState;
+use crate::conflicts;
+use crate::error_handling::AppError;
+use crate::remote;
+use crate::transfer_speed;
+
+/// How often Syncthing is checked and supervised, matching the tray's monitor thread.
+const MONITOR_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Runs Syncthingers without a system tray until SIGTERM or SIGINT is received.
+///
+/// Syncthing is monitored and supervised just like in tray mode. On termination
+/// the configured process closure behavior is applied before returning.
+pub fn run(app_state: Arc<Mutex<AppState>>) -> Result<(), AppError> {
+    let (sender, signals) = mpsc::channel();
+    ctrlc::set_handler(move || {
+        let _ = sender.send(());
+    })
+    .map_err(|e| AppError::Command(format!("Failed to install signal handler: {}", e)))?;
+
+    // Transfer speeds, remote health changes and conflicts are only logged without a tray
+    transfer_speed::spawn(app_state.clone(), |_| {});
+    remote::spawn(app_state.clone(), |_| {});
+    conflicts::spawn(app_state.clone(), |_| {});
+
+    log::info!("Running headless. Send SIGTERM or SIGINT to exit.");
+    monitor(&app_state, &signals, MONITOR_INTERVAL);
+
+    log::info!("Termination signal received, exiting");
+    let mut state = app_state
+        .lock()
+        .map_err(|_| AppError::Command("Failed to lock app state".to_string()))?;
+    state.handle_exit_closure()
+}
+
+/// Supervises Syncthing every `interval` until a message arrives on `signals`.
+fn monitor(app_state: &Arc<Mutex<AppState>>, signals: &Receiver<()>, interval: Duration) {
+    let mut last_running: HashMap<String, bool> = HashMap::new();
+    loop {
+        match signals.recv_timeout(interval) {
+            Ok(()) | Err(RecvTimeoutError::Disconnected) => return,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+
+        let Ok(mut state) = app_state.lock() else {
+            log::error!("Failed to lock app state for monitoring");
+            continue;
+        };
+        state.supervise();
+
+        for instance in &mut state.instances {
+            let running = instance.syncthing_running();
+            if last_running.insert(instance.name().to_string(), running) != Some(running) {
+                log::info!(
+                    "{} is {}",
+                    instance.name(),
+                    if running { "running" } else { "not running" }
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_dirs::AppDirs;
+    use crate::config::Config;
+    use std::thread;
+    use std::time::Instant;
+
+    #[test]
+    fn test_monitor_returns_on_signal() {
+        let config = Config {
+            syncthing_path: "nonexistent_test_syncthing.exe".to_string(),
+            ..Config::default()
+        };
+        let app_state = Arc::new(Mutex::new(AppState::new(
+            config,
+            AppDirs::new(None).unwrap(),
+        )));
+        let (sender, signals) = mpsc::channel();
+
+        let started = Instant::now();
+        let handle = thread::spawn(move || {
+            monitor(&app_state, &signals, Duration::from_millis(10));
+        });
+        thread::sleep(Duration::from_millis(50));
+        sender.send(()).unwrap();
+        handle.join().unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
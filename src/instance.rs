@@ -0,0 +1,762 @@
+use crate::config::{Config, InstanceConfig, MatchStrictness, ProcessClosureBehavior};
+use crate::dialogs::{Dialogs, HeadlessDialogs};
+use crate::error_handling::AppError;
+use crate::events::{Event, EventBus, EventKind};
+use crate::output_capture::OutputCapture;
+use crate::process;
+use crate::process::{Shutdown, StopOptions, SyncthingProcess};
+use crate::process_match::ProcessMatcher;
+use crate::supervisor::{ExitInfo, Supervisor, SupervisorDecision};
+use crate::syncthing_api::SyncthingClient;
+use crate::syncthing_config::{self, ResolvedGui};
+use crate::utils::is_test_environment;
+
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Snapshot of the Syncthing process state, reported to command-line and IPC clients.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncthingStatus {
+    /// Name of the instance
+    pub name: String,
+    pub running: bool,
+    /// Whether the process was started by this application
+    pub managed: bool,
+    pub pid: Option<u32>,
+    /// Version reported by the REST API, if reachable
+    pub version: Option<String>,
+    pub restart_required: bool,
+    /// Whether all remote devices are paused, so nothing is synced
+    #[serde(default)]
+    pub paused: bool,
+}
+
+impl SyncthingStatus {
+    /// Fills in the version and paused state from the REST API.
+    pub fn query_api(&mut self, client: &SyncthingClient) {
+        self.version = client.system_version().ok().map(|version| version.version);
+        self.paused = client.system_connections().is_ok_and(|connections| {
+            let devices = connections.connections.values();
+            devices.len() > 0 && devices.into_iter().all(|device| device.paused)
+        });
+    }
+}
+
+impl fmt::Display for SyncthingStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.running {
+            return write!(f, "Syncthing is not running.");
+        }
+        write!(f, "Syncthing")?;
+        if let Some(version) = &self.version {
+            write!(f, " {}", version)?;
+        }
+        write!(
+            f,
+            " is running ({}",
+            if self.managed { "managed" } else { "external" }
+        )?;
+        if let Some(pid) = self.pid {
+            write!(f, ", PID {}", pid)?;
+        }
+        write!(f, ").")?;
+        if self.paused {
+            write!(f, " Syncing is paused.")?;
+        }
+        if self.restart_required {
+            write!(f, " Restart required to apply configuration changes.")?;
+        }
+        Ok(())
+    }
+}
+
+/// A configured Syncthing instance and the process tracked for it.
+pub struct Instance {
+    pub config: InstanceConfig,
+    pub syncthing_process: Option<SyncthingProcess>,
+    /// Set when a configuration reload changed settings that only take effect
+    /// after the managed Syncthing process is restarted.
+    pub restart_required: bool,
+    /// Restarts the managed Syncthing process according to the supervision policy.
+    pub supervisor: Supervisor,
+    /// Set when a started Syncthing must keep running after this process exits, as for
+    /// command-line control commands. Its output is then not captured.
+    pub detached: bool,
+    match_strictness: MatchStrictness,
+    shutdown_grace_period: Duration,
+    /// File capturing the output of a Syncthing started for this instance
+    log_file: PathBuf,
+    /// Receives process exits and supervision results; set by `AppState`
+    pub events: EventBus,
+    /// Asks the user how to proceed when starting Syncthing fails; set by `AppState`
+    pub dialogs: Arc<dyn Dialogs>,
+    /// Web UI URL and API key as last resolved; see `gui`
+    gui: RefCell<Option<CachedGui>>,
+}
+
+/// Resolved GUI settings and the modification time of the `config.xml` they were
+/// read from.
+struct CachedGui {
+    gui: ResolvedGui,
+    config_xml_modified: Option<SystemTime>,
+}
+
+impl Instance {
+    /// Creates the instance and attaches to its Syncthing process if one is already running.
+    ///
+    /// Supervision, process matching and shutdown settings are shared by all instances
+    /// and taken from `global`.
+    pub fn new(config: InstanceConfig, global: &Config, log_file: PathBuf) -> Self {
+        let mut instance = Self {
+            config,
+            syncthing_process: None,
+            restart_required: false,
+            supervisor: Supervisor::new(global.supervision.clone()),
+            detached: false,
+            match_strictness: global.process_match_strictness,
+            shutdown_grace_period: Duration::from_secs(global.shutdown_grace_period_secs),
+            log_file,
+            events: EventBus::default(),
+            dialogs: Arc::new(HeadlessDialogs { answer: false }),
+            gui: RefCell::new(None),
+        };
+
+        // Skip process detection for test environments
+        if is_test_environment(&instance.config.syncthing_path) {
+            log::debug!(
+                "Skipping process detection for test path: {}",
+                instance.config.syncthing_path
+            );
+        } else {
+            // Try to detect and attach to an external Syncthing process
+            let _ = instance.detect_and_attach_external();
+        }
+        instance
+    }
+
+    /// Name of the instance, as configured.
+    pub fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    /// Replaces the instance configuration and the shared settings with reloaded ones.
+    ///
+    /// Changes to the executable, home directory or startup arguments only apply the
+    /// next time Syncthing is started, so a running managed process is marked as
+    /// requiring a restart. Returns true if a restart is required.
+    pub fn apply_config(&mut self, new_config: InstanceConfig, global: &Config) -> bool {
+        if new_config.web_ui_url != self.config.web_ui_url {
+            log::info!(
+                "Web UI URL of {} changed to {}",
+                new_config.name,
+                new_config.web_ui_url
+            );
+        }
+
+        let launch_settings_changed = new_config.syncthing_path != self.config.syncthing_path
+            || new_config.launch_args() != self.config.launch_args();
+
+        self.supervisor.set_config(global.supervision.clone());
+        self.match_strictness = global.process_match_strictness;
+        self.shutdown_grace_period = Duration::from_secs(global.shutdown_grace_period_secs);
+        self.config = new_config;
+        self.gui.get_mut().take();
+
+        let managed_running = self.syncthing_running()
+            && self
+                .syncthing_process
+                .as_ref()
+                .is_some_and(|p| p.started_by_app);
+        if launch_settings_changed && managed_running {
+            log::warn!(
+                "Syncthing path, home or startup arguments of {} changed; restart Syncthing to apply them",
+                self.name()
+            );
+            self.restart_required = true;
+        }
+
+        self.restart_required
+    }
+
+    /// Attempts to detect and attach to an external Syncthing process, updating state.
+    pub fn detect_and_attach_external(&mut self) -> Result<bool, AppError> {
+        match SyncthingProcess::detect_process(&self.process_matcher(), true) {
+            Ok(Some(proc)) => {
+                self.syncthing_process = Some(proc);
+                log::info!(
+                    "Attached to external Syncthing process for {}.",
+                    self.name()
+                );
+                Ok(true)
+            }
+            Ok(None) => Ok(false),
+            Err(e) => {
+                log::warn!("Failed to detect external Syncthing process: {}", e);
+                Err(AppError::Process(format!(
+                    "Failed to detect external Syncthing: {}",
+                    e
+                )))
+            }
+        }
+    }
+
+    /// Returns a REST API client for this instance.
+    pub fn api_client(&self) -> SyncthingClient {
+        let gui = self.gui();
+        SyncthingClient::new(&gui.web_ui_url, &gui.api_key)
+    }
+
+    /// The web UI URL and API key, with unset ones taken from Syncthing's `config.xml`.
+    ///
+    /// The result is cached until the configuration is reloaded or `config.xml`
+    /// changes, so polling does not read and parse the file on every request.
+    pub fn gui(&self) -> ResolvedGui {
+        let modified = syncthing_config::config_xml_modified(&self.config);
+        let mut cache = self.gui.borrow_mut();
+        if let Some(cached) = cache.as_ref()
+            && cached.config_xml_modified == modified
+        {
+            return cached.gui.clone();
+        }
+
+        let gui = syncthing_config::resolve(&self.config);
+        *cache = Some(CachedGui {
+            gui: gui.clone(),
+            config_xml_modified: modified,
+        });
+        gui
+    }
+
+    /// Checks if Syncthing is currently running.
+    /// This method only checks tracked processes and does not attempt to detect or attach to external instances.
+    pub fn syncthing_running(&mut self) -> bool {
+        // Check our tracked process state
+        if let Some(proc) = &mut self.syncthing_process {
+            // We already know about a process - check if it's still running
+            let managed = proc.started_by_app;
+            if proc.is_running() {
+                return true;
+            } else {
+                // Process is no longer running, clear our reference to it
+                let exit = proc.take_exit_status();
+                self.syncthing_process = None;
+                if managed {
+                    self.on_managed_process_exit(exit.map(ExitInfo::from));
+                }
+                return false;
+            }
+        }
+
+        // No process being tracked
+        false
+    }
+
+    /// Describes how the tracked process was started: "started by app", "external"
+    /// or "not running".
+    pub fn process_origin(&mut self) -> &'static str {
+        if !self.syncthing_running() {
+            return "not running";
+        }
+        match &self.syncthing_process {
+            Some(proc) if proc.started_by_app => "started by app",
+            Some(_) => "external",
+            None => "not running",
+        }
+    }
+
+    /// Reports whether Syncthing is running and, if so, how it was started.
+    pub fn status(&mut self) -> SyncthingStatus {
+        let (mut status, client) = self.local_status();
+        if let Some(client) = client {
+            status.query_api(&client);
+        }
+        status
+    }
+
+    /// The part of `status` known without asking Syncthing, and the REST client for
+    /// the rest of it while Syncthing is running.
+    pub fn local_status(&mut self) -> (SyncthingStatus, Option<SyncthingClient>) {
+        let running = self.syncthing_running();
+        let (managed, pid) = match &self.syncthing_process {
+            Some(process) if running => (process.started_by_app, process.pid),
+            _ => (false, None),
+        };
+        let status = SyncthingStatus {
+            name: self.config.name.clone(),
+            running,
+            managed,
+            pid,
+            version: None,
+            restart_required: self.restart_required,
+            paused: false,
+        };
+        (status, running.then(|| self.api_client()))
+    }
+
+    /// Starts the Syncthing process if it's not already running.
+    pub fn start_syncthing(&mut self) -> Result<(), AppError> {
+        if self.syncthing_running() {
+            return Ok(());
+        }
+
+        if !Path::new(&self.config.syncthing_path).exists() {
+            log::error!(
+                "Syncthing executable not found at path: {}",
+                self.config.syncthing_path
+            );
+
+            if self.dialogs.ask_yes_no(
+                "Configured path for Syncthing does not exist. Would you like to autodetect a new path?",
+                "Syncthing path not found",
+            ) {
+                match Config::find_syncthing_in_path() {
+                    Some(path) => {
+                        log::info!("Autodetected Syncthing path: {}", path);
+                        self.config.syncthing_path = path;
+                    }
+                    None => {
+                        log::error!("Could not autodetect Syncthing path");
+                    }
+                }
+            }
+        }
+
+        self.supervisor.reset();
+        self.launch_syncthing()
+    }
+
+    /// Spawns the configured Syncthing executable and tracks it as a managed process.
+    fn launch_syncthing(&mut self) -> Result<(), AppError> {
+        let args = self.config.launch_args();
+        let mut process = SyncthingProcess::new(&self.config.syncthing_path);
+        if !self.detached {
+            process.set_output_capture(OutputCapture::new(self.log_file.clone()));
+        }
+        if self.match_strictness == MatchStrictness::Strict {
+            process.restrict_children(self.process_matcher());
+        }
+        process
+            .start(&args)
+            .map_err(|e| AppError::Process(format!("Failed to start Syncthing: {}", e)))?;
+        self.syncthing_process = Some(process);
+        self.restart_required = false;
+
+        log::info!(
+            "Syncthing process for {} started successfully.",
+            self.name()
+        );
+        Ok(())
+    }
+
+    /// Applies the supervision policy after the managed process exited on its own.
+    fn on_managed_process_exit(&mut self, exit: Option<ExitInfo>) {
+        let exit = exit.unwrap_or_else(ExitInfo::unknown);
+        self.publish(EventKind::ProcessExited { code: exit.code });
+        match exit.code {
+            Some(code) => log::warn!(
+                "Managed Syncthing process of {} exited with code {}",
+                self.name(),
+                code
+            ),
+            None => log::warn!(
+                "Managed Syncthing process of {} exited without an exit code",
+                self.name()
+            ),
+        }
+        self.apply_supervision(exit);
+    }
+
+    /// Lets the supervisor decide whether to restart Syncthing after an exit or a
+    /// failed restart.
+    fn apply_supervision(&mut self, exit: ExitInfo) {
+        match self.supervisor.on_exit(exit, Instant::now()) {
+            SupervisorDecision::Ignore => {
+                log::info!("Supervision policy does not restart Syncthing after this exit");
+            }
+            SupervisorDecision::Restart { attempt, delay } => {
+                log::warn!(
+                    "Restarting Syncthing in {}s (attempt {}/{})",
+                    delay.as_secs(),
+                    attempt,
+                    self.supervisor.max_retries()
+                );
+            }
+            SupervisorDecision::GiveUp { attempts } => {
+                self.log_restart_history();
+                self.publish(EventKind::RestartGaveUp { attempts });
+                let message = format!(
+                    "Syncthing ({}) keeps exiting and was restarted {} times in a row. Automatic restarts have been stopped.\n\nCheck the Syncthing logs; a corrupted index database is a common cause.",
+                    self.name(),
+                    attempts
+                );
+                log::error!("{}", message);
+                // Show the dialog on its own thread so the state lock is not held while it is open
+                let dialogs = self.dialogs.clone();
+                std::thread::spawn(move || {
+                    dialogs.error(&message, "Syncthing Restart Failed");
+                });
+            }
+        }
+    }
+
+    /// Publishes an event raised by the app about this instance.
+    fn publish(&self, kind: EventKind) {
+        self.events.publish(Event {
+            instance: self.name().to_string(),
+            id: 0,
+            kind,
+        });
+    }
+
+    /// Writes the supervisor's restart history to the log.
+    fn log_restart_history(&self) {
+        for record in self.supervisor.history() {
+            let since = record
+                .at
+                .elapsed()
+                .map(|d| format!("{}s ago", d.as_secs()))
+                .unwrap_or_else(|_| "just now".to_string());
+            log::info!(
+                "Restart history: attempt {} {}, after exit code {:?}, delay {}s",
+                record.attempt,
+                since,
+                record.exit.code,
+                record.delay.as_secs()
+            );
+        }
+    }
+
+    /// Detects exits of the managed process and performs due automatic restarts.
+    ///
+    /// Called periodically by the monitoring thread.
+    pub fn supervise(&mut self) {
+        self.syncthing_running();
+
+        if self.supervisor.restart_due(Instant::now()) {
+            log::info!("Performing automatic Syncthing restart for {}", self.name());
+            if let Err(e) = self.launch_syncthing() {
+                log::error!("Automatic Syncthing restart failed: {}", e);
+                // Counts as a failed attempt, but nothing that was running has crashed
+                self.apply_supervision(ExitInfo::unknown());
+            } else {
+                self.log_restart_history();
+            }
+        }
+    }
+
+    /// Stops the Syncthing process if it's running.
+    pub fn stop_syncthing(&mut self) -> Result<(), AppError> {
+        self.begin_stop_syncthing()?.wait();
+        Ok(())
+    }
+
+    /// Asks the Syncthing process to stop and stops tracking it.
+    ///
+    /// Waiting for it to exit is left to the returned `Shutdown`, so callers can
+    /// release the application state lock during the grace period.
+    pub fn begin_stop_syncthing(&mut self) -> Result<Shutdown, AppError> {
+        let shutdown = match &self.syncthing_process {
+            // For app-started processes, use the normal stop method
+            Some(process) if process.started_by_app => self.begin_stop_managed()?,
+            // For external processes, use the external process stopping function
+            Some(_) => self.begin_stop_external(),
+            None => {
+                log::info!("No Syncthing process is currently tracked.");
+                Shutdown::default()
+            }
+        };
+
+        self.syncthing_process = None;
+        self.restart_required = false;
+        self.supervisor.reset();
+        Ok(shutdown)
+    }
+
+    /// Restarts Syncthing, stopping the currently tracked process first.
+    pub fn restart_syncthing(&mut self) -> Result<(), AppError> {
+        log::info!("Restarting Syncthing ({})", self.name());
+        self.stop_syncthing()?;
+        self.start_syncthing()
+    }
+
+    /// Handles process closure on application exit based on the instance's configuration.
+    ///
+    /// This method implements the configured process closure behavior:
+    /// - CloseAll: Stops both managed and external Syncthing processes
+    /// - CloseManaged: Only stops processes started by this app
+    /// - DontClose: Leaves all processes running
+    pub fn handle_exit_closure(&mut self) -> Result<(), AppError> {
+        for shutdown in self.begin_exit_closure() {
+            shutdown.wait();
+        }
+        Ok(())
+    }
+
+    /// Like `handle_exit_closure`, but leaves waiting for the processes to exit to the
+    /// returned shutdowns.
+    pub fn begin_exit_closure(&mut self) -> Vec<Shutdown> {
+        let mut shutdowns = Vec::new();
+        match self.config.process_closure_behavior {
+            ProcessClosureBehavior::CloseAll => {
+                log::info!(
+                    "Exit closure behavior of {}: Closing all Syncthing processes",
+                    self.name()
+                );
+                shutdowns.extend(self.begin_stop_managed().ok());
+                shutdowns.push(self.begin_stop_external());
+            }
+            ProcessClosureBehavior::CloseManaged => {
+                log::info!(
+                    "Exit closure behavior of {}: Closing only managed processes",
+                    self.name()
+                );
+                shutdowns.extend(self.begin_stop_managed().ok());
+            }
+            ProcessClosureBehavior::DontClose => {
+                log::info!(
+                    "Exit closure behavior of {}: Leaving all processes running",
+                    self.name()
+                );
+            }
+        }
+
+        self.supervisor.reset();
+        self.syncthing_process = None;
+        shutdowns
+    }
+
+    /// Requests a stop of the process if it was started by this application.
+    fn begin_stop_managed(&mut self) -> Result<Shutdown, AppError> {
+        let stop_options = self.stop_options();
+        match &mut self.syncthing_process {
+            Some(process) if process.started_by_app => process
+                .begin_stop(&stop_options)
+                .map_err(|e| AppError::Process(format!("Failed to stop Syncthing: {}", e))),
+            Some(_) => Ok(Shutdown::default()),
+            None => {
+                log::info!("No Syncthing process is currently tracked.");
+                Ok(Shutdown::default())
+            }
+        }
+    }
+
+    /// Requests a stop of external Syncthing processes.
+    fn begin_stop_external(&self) -> Shutdown {
+        process::begin_stop_external(&self.process_matcher(), &self.stop_options())
+    }
+
+    /// Matcher recognizing processes of this Syncthing instance.
+    fn process_matcher(&self) -> ProcessMatcher {
+        ProcessMatcher::from_instance(&self.config, self.match_strictness)
+    }
+
+    /// Options for stopping Syncthing: graceful REST shutdown first, then force-kill
+    /// after the configured grace period.
+    fn stop_options(&self) -> StopOptions {
+        StopOptions {
+            api: Some(self.api_client()),
+            grace_period: self.shutdown_grace_period,
+        }
+    }
+
+    /// Starts Syncthing if the instance is set to auto-launch and it is not running yet.
+    pub fn check_and_autostart_syncthing(&mut self) -> Result<(), AppError> {
+        if self.config.auto_launch {
+            // If not running, start internal syncthing
+            if !self.syncthing_running() {
+                log::info!(
+                    "Auto-launching {} as no external process is running and auto-launch is enabled.",
+                    self.name()
+                );
+                self.start_syncthing()?;
+            } else {
+                log::info!(
+                    "{} is already running, auto-launch not needed.",
+                    self.name()
+                );
+            }
+        } else {
+            log::debug!("Auto-launch of {} is disabled in config.", self.name());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_instance(closure_behavior: ProcessClosureBehavior) -> Instance {
+        let config = Config {
+            syncthing_path: "nonexistent_test_syncthing.exe".to_string(),
+            ..Config::default()
+        };
+        let instance_config = InstanceConfig {
+            process_closure_behavior: closure_behavior,
+            ..config.instance_configs().remove(0)
+        };
+        Instance::new(instance_config, &config, PathBuf::from("syncthing.log"))
+    }
+
+    #[test]
+    fn test_handle_exit_closure_dont_close() {
+        let mut instance = create_test_instance(ProcessClosureBehavior::DontClose);
+
+        // Should succeed without doing anything
+        let result = instance.handle_exit_closure();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_handle_exit_closure_close_managed_no_process() {
+        let mut instance = create_test_instance(ProcessClosureBehavior::CloseManaged);
+
+        // Should succeed when no process is running
+        let result = instance.handle_exit_closure();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_handle_exit_closure_close_managed_with_external() {
+        let mut instance = create_test_instance(ProcessClosureBehavior::CloseManaged);
+        // Simulate an external process
+        instance.syncthing_process = Some(SyncthingProcess::mock_for_testing(false));
+
+        // Should succeed and leave external process running
+        let result = instance.handle_exit_closure();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_handle_exit_closure_close_managed_with_managed() {
+        let mut instance = create_test_instance(ProcessClosureBehavior::CloseManaged);
+
+        // Simulate a managed process
+        instance.syncthing_process = Some(SyncthingProcess::mock_for_testing(true));
+
+        // Should succeed and stop the managed process
+        let result = instance.handle_exit_closure();
+        assert!(result.is_ok());
+        assert!(instance.syncthing_process.is_none());
+    }
+
+    #[test]
+    fn test_handle_exit_closure_close_all() {
+        let mut instance = create_test_instance(ProcessClosureBehavior::CloseAll);
+
+        // Simulate a process
+        instance.syncthing_process = Some(SyncthingProcess::mock_for_testing(false));
+
+        // For CloseAll behavior, it calls stop_all_syncthing_processes which:
+        // 1. Calls stop_syncthing() to clean up tracked process (sets syncthing_process = None)
+        // 2. Stops external processes (skipped in test env)
+        let result = instance.handle_exit_closure();
+        assert!(result.is_ok());
+        // The process should be None after cleanup since stop_syncthing() was called
+        assert!(instance.syncthing_process.is_none());
+    }
+
+    #[test]
+    fn test_failed_restart_is_not_reported_as_crash() {
+        let mut instance = create_test_instance(ProcessClosureBehavior::DontClose);
+        instance.supervisor = Supervisor::new(crate::config::SupervisionConfig {
+            policy: crate::config::RestartPolicy::OnFailure,
+            max_retries: 1,
+            initial_backoff_secs: 1,
+            max_backoff_secs: 1,
+            reset_window_secs: 300,
+        });
+        instance.on_managed_process_exit(Some(ExitInfo::unknown()));
+        let events = instance.events.subscribe();
+
+        // Relaunching the missing executable fails and uses up the only retry
+        std::thread::sleep(Duration::from_millis(1100));
+        instance.supervise();
+        let kinds: Vec<EventKind> = events.try_iter().map(|event| event.kind).collect();
+        assert!(
+            matches!(kinds.as_slice(), [EventKind::RestartGaveUp { attempts: 1 }]),
+            "{kinds:?}"
+        );
+    }
+
+    #[test]
+    fn test_gui_is_reread_only_when_config_xml_changes() {
+        let home = tempfile::TempDir::new().unwrap();
+        let config_xml = home.path().join("config.xml");
+        let write_key = |key: &str, modified: SystemTime| {
+            let xml = format!(
+                "<configuration><gui><address>127.0.0.1:8384</address>\
+                 <apikey>{key}</apikey></gui></configuration>"
+            );
+            std::fs::write(&config_xml, xml).unwrap();
+            let file = std::fs::File::options()
+                .write(true)
+                .open(&config_xml)
+                .unwrap();
+            file.set_modified(modified).unwrap();
+        };
+        let first = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        write_key("first", first);
+
+        let mut instance = create_test_instance(ProcessClosureBehavior::DontClose);
+        instance.config.home = Some(home.path().to_string_lossy().to_string());
+        instance.config.api_key = String::new();
+        assert_eq!(instance.gui().api_key, "first");
+
+        // Same modification time: the cached key is used
+        write_key("second", first);
+        assert_eq!(instance.gui().api_key, "first");
+
+        write_key("second", first + Duration::from_secs(60));
+        assert_eq!(instance.gui().api_key, "second");
+
+        // A configuration reload resolves again
+        let config = InstanceConfig {
+            api_key: "configured".to_string(),
+            ..instance.config.clone()
+        };
+        instance.apply_config(config, &Config::default());
+        assert_eq!(instance.gui().api_key, "configured");
+    }
+
+    /// Answers questions with a fixed answer and records them.
+    struct RecordingDialogs {
+        answer: bool,
+        questions: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl Dialogs for RecordingDialogs {
+        fn info(&self, _message: &str, _caption: &str) {}
+
+        fn error(&self, _message: &str, _caption: &str) {}
+
+        fn ask_yes_no(&self, question: &str, _caption: &str) -> bool {
+            self.questions.lock().unwrap().push(question.to_string());
+            self.answer
+        }
+    }
+
+    #[test]
+    fn test_start_with_missing_path_asks_before_autodetecting() {
+        let mut instance = create_test_instance(ProcessClosureBehavior::DontClose);
+        let dialogs = Arc::new(RecordingDialogs {
+            answer: false,
+            questions: Default::default(),
+        });
+        instance.dialogs = dialogs.clone();
+
+        // Declining keeps the configured path, so launching it fails
+        assert!(instance.start_syncthing().is_err());
+        assert_eq!(
+            instance.config.syncthing_path,
+            "nonexistent_test_syncthing.exe"
+        );
+        let questions = dialogs.questions.lock().unwrap();
+        assert_eq!(questions.len(), 1);
+        assert!(questions[0].contains("autodetect"));
+    }
+}
@@ -0,0 +1,829 @@
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use crate::app_dirs::AppDirs;
+use crate::app_state::AppState;
+use crate::cli::Cli;
+use crate::conflicts::{self, Resolution, ScanTarget};
+use crate::error_handling::AppError;
+use crate::instance::SyncthingStatus;
+use crate::logging;
+use crate::syncthing_api::SyncthingClient;
+use crate::tray_ui::{TrayMenuAction, TrayUi};
+
+/// How many times a client tries to reach the running instance.
+const CONNECT_ATTEMPTS: u32 = 10;
+const CONNECT_RETRY_DELAY: Duration = Duration::from_millis(200);
+/// How long the server waits for a connected client to send its request.
+#[cfg(not(target_os = "windows"))]
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A request sent to the running instance, one JSON object per line.
+///
+/// Requests about Syncthing apply to the named `instance`, or to all configured
+/// instances if it is omitted.
+///
+/// Example: `{"command":"restart","instance":"work"}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum IpcRequest {
+    /// Report whether Syncthing is running
+    Status {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        instance: Option<String>,
+    },
+    /// Start Syncthing if it is not running
+    Start {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        instance: Option<String>,
+    },
+    /// Stop Syncthing if it is running
+    Stop {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        instance: Option<String>,
+    },
+    /// Toggle Syncthing like the tray's Start/Stop item
+    StartStop {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        instance: Option<String>,
+    },
+    Restart {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        instance: Option<String>,
+    },
+    OpenWebUi {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        instance: Option<String>,
+    },
+    /// Pause syncing with all devices, keeping Syncthing running
+    Pause {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        instance: Option<String>,
+    },
+    /// Resume syncing with all devices
+    Resume {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        instance: Option<String>,
+    },
+    /// List the conflict copies in the synced folders
+    Conflicts {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        instance: Option<String>,
+    },
+    /// Resolve the conflict copy at an absolute path
+    ResolveConflict {
+        path: PathBuf,
+        resolution: Resolution,
+    },
+    OpenConfig,
+    /// Exit the running instance, applying the process closure behavior
+    Exit,
+    /// Command-line arguments of a second launch
+    Args {
+        args: Vec<String>,
+    },
+}
+
+/// The reply to an `IpcRequest`, one JSON object per line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpcResponse {
+    pub ok: bool,
+    pub message: String,
+    /// Status of each selected instance, only for `status` requests
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub instances: Vec<SyncthingStatus>,
+    /// Set by the handler when the instance exits after sending this reply
+    #[serde(skip)]
+    pub exit_after_reply: bool,
+}
+
+impl IpcResponse {
+    pub fn ok(message: impl Into<String>) -> Self {
+        Self {
+            ok: true,
+            message: message.into(),
+            instances: Vec::new(),
+            exit_after_reply: false,
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            ..Self::ok(message)
+        }
+    }
+}
+
+/// Carries out a request against the application state.
+///
+/// Used by the IPC server of the running instance, and directly by command-line
+/// control commands when no instance is running.
+pub fn execute(state: &mut AppState, request: IpcRequest) -> IpcResponse {
+    match request {
+        IpcRequest::Status { instance } => {
+            let mut statuses = Vec::new();
+            let mut response = for_each_instance(state, instance.as_deref(), |state, name| {
+                let status = state.instance_mut(name)?.status();
+                let message = status.to_string();
+                statuses.push(status);
+                Ok(message)
+            });
+            response.instances = statuses;
+            response
+        }
+        IpcRequest::Start { instance } => {
+            for_each_instance(state, instance.as_deref(), |state, name| {
+                let instance = state.instance_mut(name)?;
+                if instance.syncthing_running() {
+                    return Ok("Syncthing is already running.".to_string());
+                }
+                instance
+                    .start_syncthing()
+                    .map(|_| "Syncthing started.".to_string())
+            })
+        }
+        IpcRequest::Stop { instance } => {
+            for_each_instance(state, instance.as_deref(), |state, name| {
+                let instance = state.instance_mut(name)?;
+                if !instance.syncthing_running() {
+                    return Ok("Syncthing is not running.".to_string());
+                }
+                instance
+                    .stop_syncthing()
+                    .map(|_| "Syncthing stopped.".to_string())
+            })
+        }
+        IpcRequest::StartStop { instance } => {
+            for_each_instance(state, instance.as_deref(), |state, name| {
+                TrayUi::process_menu_action(state, TrayMenuAction::StartStop(name.to_string()))?;
+                Ok(if state.instance_mut(name)?.syncthing_running() {
+                    "Syncthing started.".to_string()
+                } else {
+                    "Syncthing stopped.".to_string()
+                })
+            })
+        }
+        IpcRequest::Restart { instance } => {
+            for_each_instance(state, instance.as_deref(), |state, name| {
+                TrayUi::process_menu_action(state, TrayMenuAction::Restart(name.to_string()))
+                    .map(|_| "Syncthing restarted.".to_string())
+            })
+        }
+        IpcRequest::OpenWebUi { instance } => {
+            for_each_instance(state, instance.as_deref(), |state, name| {
+                TrayUi::process_menu_action(state, TrayMenuAction::OpenWebUI(name.to_string()))
+                    .map(|_| "Opened the Syncthing web UI.".to_string())
+            })
+        }
+        IpcRequest::Pause { instance } => {
+            for_each_instance(state, instance.as_deref(), |state, name| {
+                if !state.instance_mut(name)?.syncthing_running() {
+                    return Err(AppError::Command("Syncthing is not running.".to_string()));
+                }
+                TrayUi::process_menu_action(state, TrayMenuAction::PauseAll(name.to_string()))
+                    .map(|_| "Syncing paused.".to_string())
+            })
+        }
+        IpcRequest::Resume { instance } => {
+            for_each_instance(state, instance.as_deref(), |state, name| {
+                if !state.instance_mut(name)?.syncthing_running() {
+                    return Err(AppError::Command("Syncthing is not running.".to_string()));
+                }
+                TrayUi::process_menu_action(state, TrayMenuAction::ResumeAll(name.to_string()))
+                    .map(|_| "Syncing resumed.".to_string())
+            })
+        }
+        IpcRequest::Conflicts { instance } => list_conflicts(state, instance)(),
+        IpcRequest::ResolveConflict { path, resolution } => {
+            resolve_conflict(state, path, resolution)()
+        }
+        IpcRequest::OpenConfig => {
+            match TrayUi::process_menu_action(state, TrayMenuAction::OpenConfig) {
+                Ok(()) => IpcResponse::ok("Opened the configuration file."),
+                Err(e) => IpcResponse::error(e.to_string()),
+            }
+        }
+        IpcRequest::Exit => {
+            if let Err(e) = state.handle_exit_closure() {
+                log::warn!("Error during exit closure: {}", e);
+            }
+            IpcResponse {
+                exit_after_reply: true,
+                ..IpcResponse::ok("Syncthingers is exiting.")
+            }
+        }
+        IpcRequest::Args { args } => apply_args(state, args),
+    }
+}
+
+/// The part of a request that runs after the state lock has been released.
+type Job = Box<dyn FnOnce() -> IpcResponse>;
+
+/// Collects the selected instances for listing their conflicts; scanning their
+/// folders is left to the returned job.
+fn list_conflicts(state: &mut AppState, instance: Option<String>) -> Job {
+    let names = match state.selected_instance_names(instance.as_deref()) {
+        Ok(names) => names,
+        Err(e) => {
+            let message = e.to_string();
+            return Box::new(move || IpcResponse::error(message));
+        }
+    };
+    let prefix = state.has_multiple_instances();
+    let targets: Vec<ScanTarget> = state
+        .instances
+        .iter_mut()
+        .filter(|instance| names.contains(&instance.config.name))
+        .map(ScanTarget::new)
+        .collect();
+
+    Box::new(move || {
+        let lines: Vec<String> = targets
+            .iter()
+            .map(|target| {
+                let line = conflicts::describe(&target.conflicts());
+                if prefix {
+                    format!("{}: {}", target.config.name, line)
+                } else {
+                    line
+                }
+            })
+            .collect();
+        IpcResponse::ok(lines.join("\n"))
+    })
+}
+
+/// Collects all instances for resolving a conflict copy; looking up its folder is
+/// left to the returned job.
+fn resolve_conflict(state: &mut AppState, path: PathBuf, resolution: Resolution) -> Job {
+    let targets: Vec<ScanTarget> = state.instances.iter_mut().map(ScanTarget::new).collect();
+    Box::new(
+        move || match conflicts::resolve_path(&targets, &path, resolution) {
+            Ok(message) => IpcResponse::ok(message),
+            Err(e) => IpcResponse::error(e.to_string()),
+        },
+    )
+}
+
+/// Runs `op` for each selected instance and combines the results into one reply.
+///
+/// With several instances configured, each line is prefixed with the instance name.
+fn for_each_instance<F>(state: &mut AppState, instance: Option<&str>, mut op: F) -> IpcResponse
+where
+    F: FnMut(&mut AppState, &str) -> Result<String, AppError>,
+{
+    let names = match state.selected_instance_names(instance) {
+        Ok(names) => names,
+        Err(e) => return IpcResponse::error(e.to_string()),
+    };
+    let prefix = state.has_multiple_instances();
+    combine_instance_results(names, prefix, |name| op(state, name))
+}
+
+/// Like `for_each_instance`, but `op` locks the shared state itself as needed.
+fn for_each_shared_instance<F>(
+    app_state: &Mutex<AppState>,
+    instance: Option<&str>,
+    op: F,
+) -> IpcResponse
+where
+    F: FnMut(&str) -> Result<String, AppError>,
+{
+    let selection = match app_state.lock() {
+        Ok(mut state) => state
+            .selected_instance_names(instance)
+            .map(|names| (names, state.has_multiple_instances())),
+        Err(_) => return IpcResponse::error("Failed to lock application state"),
+    };
+    match selection {
+        Ok((names, prefix)) => combine_instance_results(names, prefix, op),
+        Err(e) => IpcResponse::error(e.to_string()),
+    }
+}
+
+fn combine_instance_results<F>(names: Vec<String>, prefix: bool, mut op: F) -> IpcResponse
+where
+    F: FnMut(&str) -> Result<String, AppError>,
+{
+    let mut ok = true;
+    let mut lines = Vec::new();
+    for name in names {
+        let line = op(&name).unwrap_or_else(|e| {
+            ok = false;
+            e.to_string()
+        });
+        lines.push(if prefix {
+            format!("{name}: {line}")
+        } else {
+            line
+        });
+    }
+
+    IpcResponse {
+        ok,
+        ..IpcResponse::ok(lines.join("\n"))
+    }
+}
+
+/// Applies the command-line arguments of a second launch.
+fn apply_args(state: &mut AppState, args: Vec<String>) -> IpcResponse {
+    let cli = match Cli::try_parse_args(args) {
+        Ok(cli) => cli,
+        Err(e) => return IpcResponse::error(e),
+    };
+
+    if let Some(level) = &cli.log_level {
+        logging::set_log_level(logging::log_level_from_str(level));
+        log::info!("Log level set to {level} by another launch");
+    }
+
+    if let Some(request) = cli.ipc_request() {
+        return execute(state, request);
+    }
+
+    IpcResponse::ok(match &cli.log_level {
+        Some(level) => format!("Syncthingers is already running; applied --log-level={level}."),
+        None => "Syncthingers is already running.".to_string(),
+    })
+}
+
+/// Like `execute`, but locks the shared state only while needed.
+///
+/// Stopping Syncthing, REST calls, scanning folders and opening the web UI happen
+/// without holding the lock, so the tray and other requests are not blocked
+/// meanwhile.
+fn execute_shared(app_state: &Mutex<AppState>, request: IpcRequest) -> IpcResponse {
+    match request {
+        IpcRequest::Status { instance } => {
+            let mut statuses = Vec::new();
+            let mut response = for_each_shared_instance(app_state, instance.as_deref(), |name| {
+                let (mut status, client) = lock(app_state)?.instance_mut(name)?.local_status();
+                if let Some(client) = client {
+                    status.query_api(&client);
+                }
+                let message = status.to_string();
+                statuses.push(status);
+                Ok(message)
+            });
+            response.instances = statuses;
+            response
+        }
+        IpcRequest::Stop { instance } => {
+            for_each_shared_instance(app_state, instance.as_deref(), |name| {
+                Ok(if AppState::stop_instance(app_state, name)? {
+                    "Syncthing stopped.".to_string()
+                } else {
+                    "Syncthing is not running.".to_string()
+                })
+            })
+        }
+        IpcRequest::StartStop { instance } => {
+            for_each_shared_instance(app_state, instance.as_deref(), |name| {
+                if AppState::stop_instance(app_state, name)? {
+                    return Ok("Syncthing stopped.".to_string());
+                }
+                lock(app_state)?.instance_mut(name)?.start_syncthing()?;
+                Ok("Syncthing started.".to_string())
+            })
+        }
+        IpcRequest::Restart { instance } => {
+            for_each_shared_instance(app_state, instance.as_deref(), |name| {
+                AppState::restart_instance(app_state, name)
+                    .map(|_| "Syncthing restarted.".to_string())
+            })
+        }
+        IpcRequest::OpenWebUi { instance } => {
+            for_each_shared_instance(app_state, instance.as_deref(), |name| {
+                let gui = lock(app_state)?.instance_mut(name)?.gui();
+                opener::open(&gui.web_ui_url)
+                    .map_err(|e| AppError::Command(format!("Failed to open web UI: {}", e)))?;
+                Ok("Opened the Syncthing web UI.".to_string())
+            })
+        }
+        IpcRequest::Pause { instance } => {
+            for_each_shared_instance(app_state, instance.as_deref(), |name| {
+                running_client(app_state, name)?.pause_all()?;
+                Ok("Syncing paused.".to_string())
+            })
+        }
+        IpcRequest::Resume { instance } => {
+            for_each_shared_instance(app_state, instance.as_deref(), |name| {
+                running_client(app_state, name)?.resume_all()?;
+                Ok("Syncing resumed.".to_string())
+            })
+        }
+        IpcRequest::Exit => {
+            if let Err(e) = AppState::exit_closure(app_state) {
+                log::warn!("Error during exit closure: {}", e);
+            }
+            IpcResponse {
+                exit_after_reply: true,
+                ..IpcResponse::ok("Syncthingers is exiting.")
+            }
+        }
+        request => {
+            let Ok(mut state) = app_state.lock() else {
+                return IpcResponse::error("Failed to lock application state");
+            };
+            let job = match request {
+                IpcRequest::Conflicts { instance } => list_conflicts(&mut state, instance),
+                IpcRequest::ResolveConflict { path, resolution } => {
+                    resolve_conflict(&mut state, path, resolution)
+                }
+                request => return execute(&mut state, request),
+            };
+            // Scanning synced folders can take a while, so it runs without the lock
+            drop(state);
+            job()
+        }
+    }
+}
+
+fn lock(app_state: &Mutex<AppState>) -> Result<MutexGuard<'_, AppState>, AppError> {
+    app_state
+        .lock()
+        .map_err(|_| AppError::Command("Failed to lock application state".to_string()))
+}
+
+/// The REST client of a running instance, for use after the lock is released.
+fn running_client(app_state: &Mutex<AppState>, name: &str) -> Result<SyncthingClient, AppError> {
+    let mut state = lock(app_state)?;
+    let instance = state.instance_mut(name)?;
+    if !instance.syncthing_running() {
+        return Err(AppError::Command("Syncthing is not running.".to_string()));
+    }
+    Ok(instance.api_client())
+}
+
+/// Starts serving requests for the running instance on a background thread.
+///
+/// The caller must hold the singleton lock, so a leftover endpoint is known to be stale.
+pub fn serve(app_dirs: &AppDirs, app_state: std::sync::Arc<Mutex<AppState>>) -> io::Result<()> {
+    platform::serve(app_dirs, move |request| {
+        log::info!("Received IPC request: {:?}", request);
+        execute_shared(&app_state, request)
+    })
+}
+
+/// Sends a request to the running instance and returns its reply.
+pub fn send(app_dirs: &AppDirs, request: &IpcRequest) -> io::Result<IpcResponse> {
+    // The running instance may still be starting up and not listening yet
+    let mut attempt = 0;
+    let stream = loop {
+        match platform::connect(app_dirs) {
+            Ok(stream) => break stream,
+            Err(e) if attempt + 1 >= CONNECT_ATTEMPTS => return Err(e),
+            Err(_) => {
+                attempt += 1;
+                thread::sleep(CONNECT_RETRY_DELAY);
+            }
+        }
+    };
+
+    let mut writer = &stream;
+    writeln!(writer, "{}", serde_json::to_string(request)?)?;
+    writer.flush()?;
+
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// Reads one request from a connection, answers it and reports whether to exit.
+fn handle_connection<S, F>(stream: &S, handler: &F) -> io::Result<bool>
+where
+    for<'a> &'a S: Read + Write,
+    F: Fn(IpcRequest) -> IpcResponse,
+{
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    let response = match serde_json::from_str::<IpcRequest>(&line) {
+        Ok(request) => handler(request),
+        Err(e) => IpcResponse::error(format!("Invalid request: {}", e)),
+    };
+
+    let mut writer = stream;
+    writeln!(writer, "{}", serde_json::to_string(&response)?)?;
+    writer.flush()?;
+    Ok(response.exit_after_reply)
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use std::fs;
+    use std::io;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::{IpcRequest, IpcResponse, READ_TIMEOUT, handle_connection};
+    use crate::app_dirs::AppDirs;
+
+    pub fn serve<F>(app_dirs: &AppDirs, handler: F) -> io::Result<()>
+    where
+        F: Fn(IpcRequest) -> IpcResponse + Send + Sync + 'static,
+    {
+        let socket_path = app_dirs.socket_path();
+        if socket_path.exists() {
+            fs::remove_file(&socket_path)?;
+        }
+        let listener = UnixListener::bind(&socket_path)?;
+        log::info!("Listening for IPC requests on {}", socket_path.display());
+
+        // Each connection is handled on its own thread, so a slow request or a client
+        // that never sends anything does not hold up the others
+        let handler = Arc::new(handler);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        log::warn!("Failed to accept IPC connection: {}", e);
+                        continue;
+                    }
+                };
+                let handler = handler.clone();
+                let socket_path = socket_path.clone();
+                thread::spawn(move || {
+                    let result = stream
+                        .set_read_timeout(Some(READ_TIMEOUT))
+                        .and_then(|_| handle_connection(&stream, &*handler));
+                    match result {
+                        Ok(true) => {
+                            let _ = fs::remove_file(&socket_path);
+                            std::process::exit(0);
+                        }
+                        Ok(false) => {}
+                        Err(e) => log::warn!("Failed to handle IPC request: {}", e),
+                    }
+                });
+            }
+        });
+        Ok(())
+    }
+
+    pub fn connect(app_dirs: &AppDirs) -> io::Result<UnixStream> {
+        UnixStream::connect(app_dirs.socket_path())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::fs::{File, OpenOptions};
+    use std::io;
+    use std::os::windows::io::FromRawHandle;
+    use std::ptr;
+    use std::sync::Arc;
+    use std::thread;
+    use winapi::shared::winerror::ERROR_PIPE_CONNECTED;
+    use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+    use winapi::um::namedpipeapi::{ConnectNamedPipe, CreateNamedPipeW};
+    use winapi::um::winbase::{
+        FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX, PIPE_READMODE_BYTE,
+        PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+    };
+
+    use super::{IpcRequest, IpcResponse, handle_connection};
+    use crate::app_dirs::AppDirs;
+
+    const PIPE_BUFFER_SIZE: u32 = 4096;
+
+    /// Machine-wide pipe name, matching the global singleton mutex. The default pipe
+    /// security only lets the owner and administrators write to it.
+    const PIPE_NAME: &str = r"\\.\pipe\syncthingers";
+
+    /// Creates a pipe instance and waits for a client to connect to it.
+    fn accept(name: &[u16], first: bool) -> io::Result<File> {
+        let mut open_mode = PIPE_ACCESS_DUPLEX;
+        if first {
+            open_mode |= FILE_FLAG_FIRST_PIPE_INSTANCE;
+        }
+        let handle = unsafe {
+            CreateNamedPipeW(
+                name.as_ptr(),
+                open_mode,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                PIPE_BUFFER_SIZE,
+                PIPE_BUFFER_SIZE,
+                0,
+                ptr::null_mut(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+        // Closing the File closes the pipe handle
+        let pipe = unsafe { File::from_raw_handle(handle as _) };
+
+        let connected = unsafe { ConnectNamedPipe(handle, ptr::null_mut()) } != 0;
+        if !connected {
+            let error = io::Error::last_os_error();
+            if error.raw_os_error() != Some(ERROR_PIPE_CONNECTED as i32) {
+                return Err(error);
+            }
+        }
+        Ok(pipe)
+    }
+
+    pub fn serve<F>(_app_dirs: &AppDirs, handler: F) -> io::Result<()>
+    where
+        F: Fn(IpcRequest) -> IpcResponse + Send + Sync + 'static,
+    {
+        let wide: Vec<u16> = PIPE_NAME.encode_utf16().chain(std::iter::once(0)).collect();
+        log::info!("Listening for IPC requests on {}", PIPE_NAME);
+
+        // Each connection is handled on its own thread, so a slow request does not hold
+        // up the others
+        let handler = Arc::new(handler);
+        thread::spawn(move || {
+            let mut first = true;
+            loop {
+                let pipe = match accept(&wide, first) {
+                    Ok(pipe) => pipe,
+                    Err(e) => {
+                        log::error!("Failed to accept IPC connection: {}", e);
+                        if first {
+                            return;
+                        }
+                        continue;
+                    }
+                };
+                first = false;
+
+                let handler = handler.clone();
+                thread::spawn(move || match handle_connection(&pipe, &*handler) {
+                    Ok(exit) => {
+                        // Wait until the client has read the reply before closing the pipe
+                        let _ = pipe.sync_all();
+                        if exit {
+                            std::process::exit(0);
+                        }
+                    }
+                    Err(e) => log::warn!("Failed to handle IPC request: {}", e),
+                });
+            }
+        });
+        Ok(())
+    }
+
+    pub fn connect(_app_dirs: &AppDirs) -> io::Result<File> {
+        OpenOptions::new().read(true).write(true).open(PIPE_NAME)
+    }
+}
+
+#[cfg(all(test, not(target_os = "windows")))]
+mod tests {
+    use super::*;
+    use crate::singleton::platform::SingletonGuard;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_request_wire_format() {
+        assert_eq!(
+            serde_json::to_string(&IpcRequest::OpenWebUi { instance: None }).unwrap(),
+            r#"{"command":"open_web_ui"}"#
+        );
+        let request: IpcRequest =
+            serde_json::from_str(r#"{"command":"restart","instance":"work"}"#).unwrap();
+        assert_eq!(
+            request,
+            IpcRequest::Restart {
+                instance: Some("work".to_string())
+            }
+        );
+        let request: IpcRequest =
+            serde_json::from_str(r#"{"command":"args","args":["--log-level=debug"]}"#).unwrap();
+        assert_eq!(
+            request,
+            IpcRequest::Args {
+                args: vec!["--log-level=debug".to_string()]
+            }
+        );
+    }
+
+    #[test]
+    fn test_request_roundtrip_to_running_instance() {
+        let dir = TempDir::new().unwrap();
+        let app_dirs = AppDirs::new(Some(dir.path().to_path_buf())).unwrap();
+        let _guard = SingletonGuard::acquire(&app_dirs).unwrap();
+
+        platform::serve(&app_dirs, |request| match request {
+            IpcRequest::Status { .. } => IpcResponse::ok("running"),
+            other => IpcResponse::error(format!("rejected {:?}", other)),
+        })
+        .unwrap();
+
+        let reply = send(&app_dirs, &IpcRequest::Status { instance: None }).unwrap();
+        assert!(reply.ok);
+        assert_eq!(reply.message, "running");
+
+        let reply = send(&app_dirs, &IpcRequest::OpenConfig).unwrap();
+        assert!(!reply.ok);
+        assert_eq!(reply.message, "rejected OpenConfig");
+    }
+
+    #[test]
+    fn test_idle_client_does_not_block_other_requests() {
+        let dir = TempDir::new().unwrap();
+        let app_dirs = AppDirs::new(Some(dir.path().to_path_buf())).unwrap();
+        let _guard = SingletonGuard::acquire(&app_dirs).unwrap();
+        platform::serve(&app_dirs, |_| IpcResponse::ok("running")).unwrap();
+
+        // Connects, but never sends a request
+        let _idle = platform::connect(&app_dirs).unwrap();
+
+        let started = std::time::Instant::now();
+        let reply = send(&app_dirs, &IpcRequest::Status { instance: None }).unwrap();
+        assert_eq!(reply.message, "running");
+        assert!(started.elapsed() < READ_TIMEOUT);
+    }
+
+    #[test]
+    fn test_pause_requires_running_syncthing() {
+        let config = crate::config::Config {
+            syncthing_path: "nonexistent_test_syncthing".to_string(),
+            ..Default::default()
+        };
+        let mut state = AppState::new(config, AppDirs::new(None).unwrap());
+
+        let reply = execute(&mut state, IpcRequest::Pause { instance: None });
+        assert!(!reply.ok);
+        assert_eq!(reply.message, "Syncthing is not running.");
+
+        let state = Mutex::new(state);
+        let reply = execute_shared(&state, IpcRequest::Resume { instance: None });
+        assert!(!reply.ok);
+        assert_eq!(reply.message, "Syncthing is not running.");
+        let reply = execute_shared(&state, IpcRequest::Status { instance: None });
+        assert!(reply.ok);
+        assert!(!reply.instances[0].running);
+    }
+
+    #[test]
+    fn test_list_and_resolve_conflicts_from_config_xml() {
+        let home = TempDir::new().unwrap();
+        let synced = TempDir::new().unwrap();
+        fs::write(
+            home.path().join("config.xml"),
+            format!(
+                r#"<configuration><folder id="docs" label="Docs" path="{}"/></configuration>"#,
+                synced.path().display()
+            ),
+        )
+        .unwrap();
+        let copy = synced
+            .path()
+            .join("a.sync-conflict-20250601-101010-ABCDEFG.txt");
+        fs::write(synced.path().join("a.txt"), "local").unwrap();
+        fs::write(&copy, "remote").unwrap();
+
+        let config = crate::config::Config {
+            syncthing_path: "nonexistent_test_syncthing".to_string(),
+            startup_args: vec![
+                "--home".to_string(),
+                home.path().to_string_lossy().to_string(),
+            ],
+            ..Default::default()
+        };
+        let mut state = AppState::new(config, AppDirs::new(None).unwrap());
+
+        let reply = execute(&mut state, IpcRequest::Conflicts { instance: None });
+        assert!(reply.ok);
+        assert!(reply.message.starts_with("1 sync conflict(s):"));
+        assert!(reply.message.contains("Docs: "));
+
+        // Only conflict copies in synced folders can be resolved
+        let outside = home.path().join("config.xml");
+        let reply = execute(
+            &mut state,
+            IpcRequest::ResolveConflict {
+                path: outside.clone(),
+                resolution: Resolution::Local,
+            },
+        );
+        assert!(!reply.ok);
+        assert!(outside.exists());
+
+        let reply = execute(
+            &mut state,
+            IpcRequest::ResolveConflict {
+                path: copy.clone(),
+                resolution: Resolution::Remote,
+            },
+        );
+        assert!(reply.ok, "{}", reply.message);
+        assert_eq!(
+            fs::read_to_string(synced.path().join("a.txt")).unwrap(),
+            "remote"
+        );
+        let reply = execute(&mut state, IpcRequest::Conflicts { instance: None });
+        assert_eq!(reply.message, "No sync conflicts.");
+    }
+}
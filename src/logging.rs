@@ -2,6 +2,9 @@ use crate::app_dirs::AppDirs;
 use simplelog::{Config as LogConfig, ConfigBuilder, LevelFilter, WriteLogger};
 use std::fs::File;
 
+/// Log level names accepted in the configuration and on the command line.
+pub const LOG_LEVELS: &[&str] = &["off", "error", "warn", "info", "debug"];
+
 pub fn init_logging(log_level: LevelFilter, app_dirs: &AppDirs) {
     // Always use AppDirs for log file path and directory management
     app_dirs.ensure_exists().ok();
@@ -2,35 +2,66 @@
 
 mod app_dirs;
 mod app_state;
+mod cli;
 pub mod config;
+mod config_validation;
+mod config_watcher;
+mod conflicts;
+mod devices;
+mod dialogs;
 mod error_handling;
+mod events;
+mod folders;
+mod headless;
+mod instance;
+mod ipc;
 mod logging;
+mod notifications;
+mod output_capture;
 mod process;
+mod process_match;
+mod process_tree;
+mod remote;
 mod singleton;
+mod supervisor;
+mod sync_status;
+mod syncthing_api;
+mod syncthing_config;
+mod transfer_speed;
+mod tray_menu;
 mod tray_ui;
 mod utils;
+mod wait_synced;
 
+use clap::Parser;
+use cli::{Cli, Command};
 use config::Config;
 use simplelog::LevelFilter;
-use std::env;
 
 fn main() {
-    let args: Vec<String> = env::args().collect(); // Parse command line arguments
-    let mut log_level = LevelFilter::Error;
-    let mut app_dirs_override = None;
-    for arg in &args {
-        if let Some(lvl) = arg.strip_prefix("--log-level=") {
-            log_level = logging::log_level_from_str(lvl);
-        } else if arg == "--portable" {
-            // In portable mode, use the current working directory
-            if let Ok(current_dir) = std::env::current_dir() {
-                app_dirs_override = Some(current_dir);
-            } else {
+    let cli = Cli::parse();
+    let command = cli.subcommand();
+    // Error dialogs are only shown when running with a tray
+    let interactive = command == Command::Tray && !cli.headless;
+    let native_dialogs = dialogs::native();
+    let log_level = cli
+        .log_level
+        .as_deref()
+        .map(logging::log_level_from_str)
+        .unwrap_or(LevelFilter::Error);
+
+    // In portable mode, use the current working directory
+    let app_dirs_override = if cli.portable {
+        match std::env::current_dir() {
+            Ok(current_dir) => Some(current_dir),
+            Err(_) => {
                 eprintln!("Error: failed to get current working directory for portable mode");
                 std::process::exit(1);
             }
         }
-    }
+    } else {
+        None
+    };
 
     // Create the AppDirs instance (stateful)
     let app_dirs = match app_dirs::AppDirs::new(app_dirs_override) {
@@ -47,6 +78,80 @@ fn main() {
         std::process::exit(1);
     }
 
+    // Config subcommands only touch the configuration file
+    if let Command::Config { action } = command {
+        match cli::run_config_command(action, &app_dirs.config_file_path()) {
+            Ok(output) => println!("{output}"),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // Waiting only reads Syncthing's REST API, so it also runs next to a tray instance
+    if let Command::WaitSynced {
+        folders,
+        devices,
+        timeout,
+    } = &command
+    {
+        let filter = wait_synced::WaitFilter {
+            folders: folders.clone(),
+            devices: devices.clone(),
+        };
+        let timeout = timeout.map(std::time::Duration::from_secs);
+        match wait_synced::run_command(
+            &app_dirs.config_file_path(),
+            cli.instance.as_deref(),
+            &filter,
+            timeout,
+        ) {
+            Ok(wait_synced::WaitOutcome::Synced) => println!("All selected folders are in sync."),
+            Ok(wait_synced::WaitOutcome::TimedOut(pending)) => {
+                eprintln!("Timed out; still out of sync: {}", pending.join(", "));
+                std::process::exit(wait_synced::EXIT_TIMED_OUT);
+            }
+            Err(e) => {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // Check for a running instance before logging, so its log file is not truncated
+    let Some(_singleton_guard) = singleton::platform::SingletonGuard::acquire(&app_dirs) else {
+        let request = cli.ipc_request().unwrap_or_else(|| ipc::IpcRequest::Args {
+            args: std::env::args().skip(1).collect(),
+        });
+        match ipc::send(&app_dirs, &request) {
+            Ok(reply) if reply.ok => {
+                println!("{}", reply.message);
+                // Launched from a desktop menu or without a console, the reply would go unnoticed
+                if interactive {
+                    native_dialogs.info(&reply.message, "Syncthingers");
+                }
+            }
+            Ok(reply) => {
+                eprintln!("Error: {}", reply.message);
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Error: another instance is running but could not be reached: {e}");
+                if interactive {
+                    native_dialogs.error(
+                        "Another instance of Syncthingers is already running.",
+                        "Syncthingers Singleton",
+                    );
+                }
+                std::process::exit(1);
+            }
+        }
+        return;
+    };
+
     // Get log file path and initialize logging
     logging::init_logging(log_level, &app_dirs);
 
@@ -56,13 +161,28 @@ fn main() {
     let config_file_path = app_dirs.config_file_path();
     log::debug!("Using configuration file: {}", config_file_path.display());
 
-    let config = Config::load_or_create(config_file_path).unwrap_or_else(|e| {
-        eprintln!("Error: failed to load or create configuration file: {e}");
-        std::process::exit(1);
-    });
+    let config = match Config::load_validated(&config_file_path) {
+        Ok((config, warnings)) => {
+            for warning in warnings {
+                log::warn!("Configuration warning: {warning}");
+            }
+            config
+        }
+        Err(e) => {
+            log::error!("Failed to load configuration file: {e}");
+            eprintln!("Error: failed to load or create configuration file: {e}");
+            if interactive {
+                native_dialogs.error(
+                    &format!("Failed to load {}\n\n{e}", config_file_path.display()),
+                    "Syncthingers Configuration Error",
+                );
+            }
+            std::process::exit(1);
+        }
+    };
 
     // Check if --create-config argument is present and exit
-    if args.iter().any(|a| a == "--create-config") {
+    if cli.create_config {
         return;
     }
 
@@ -72,18 +192,41 @@ fn main() {
 
     log::info!("Startup arguments: {:?}", config.startup_args);
 
-    if singleton::platform::SingletonGuard::acquire().is_none() {
-        log::warn!("Another instance of the application is already running. Exiting.");
+    // No tray instance is running, so control commands act directly
+    if let Some(request) = cli.ipc_request() {
+        let mut state = app_state::AppState::new(config, app_dirs);
+        state.set_detached();
+        let reply = ipc::execute(&mut state, request);
+        if reply.ok {
+            println!("{}", reply.message);
+        } else {
+            log::error!("Command failed: {}", reply.message);
+            eprintln!("Error: {}", reply.message);
+            std::process::exit(1);
+        }
         return;
     }
 
     log::info!("Application starting");
 
     // Create shared app state
-    let app_state = std::sync::Arc::new(std::sync::Mutex::new(app_state::AppState::new(
-        config,
-        app_dirs.clone(),
-    )));
+    let mut state = app_state::AppState::new(config, app_dirs.clone());
+    if interactive {
+        state.set_dialogs(native_dialogs.clone());
+    }
+    let app_state = std::sync::Arc::new(std::sync::Mutex::new(state));
+
+    // Accept control requests and arguments forwarded by later launches
+    if let Err(e) = ipc::serve(&app_dirs, app_state.clone()) {
+        log::warn!("Failed to start IPC server: {e}");
+    }
+
+    // Apply configuration file changes without requiring a restart
+    config_watcher::ConfigWatcher::spawn(app_state.clone(), config_file_path);
+
+    // Stream Syncthing events to the tray and the log
+    events::spawn(app_state.clone());
+    notifications::spawn(app_state.clone());
 
     // Auto-launch internal syncthing if configured
     {
@@ -93,10 +236,21 @@ fn main() {
         }
     }
 
+    if cli.headless {
+        if let Err(e) = headless::run(app_state) {
+            log::error!("{e}");
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        }
+        log::info!("Application exited");
+        return;
+    }
+
     // Create tray UI
     let tray_ui = tray_ui::TrayUi::new(app_state.clone()).unwrap_or_else(|e| {
         log::error!("Failed to create tray UI: {e}");
-        error_handling::show_native_error_dialog(
+        eprintln!("Error: failed to create tray UI: {e}. Use --headless to run without a tray.");
+        native_dialogs.error(
             &format!("Failed to create tray UI: {e}"),
             "Syncthingers Error",
         );
@@ -106,7 +260,7 @@ fn main() {
         let mut tray_ui_guard = tray_ui.lock().unwrap();
         tray_ui_guard.setup_tray_menu().unwrap_or_else(|e| {
             log::error!("Failed to set up tray menu: {e}");
-            error_handling::show_native_error_dialog(
+            native_dialogs.error(
                 &format!("Failed to set up tray menu: {e}"),
                 "Syncthingers Error",
             );
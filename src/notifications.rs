@@ -0,0 +1,463 @@
+use std::collections::HashMap;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::app_state::AppState;
+use crate::config::NotificationsConfig;
+use crate::conflicts::CONFLICT_MARKER;
+use crate::events::{Event, EventKind};
+use crate::syncthing_api::SyncthingClient;
+
+/// Identical notifications are not repeated within this interval, since Syncthing
+/// reports folder errors again after every failed pull.
+const REPEAT_INTERVAL: Duration = Duration::from_secs(600);
+
+/// A desktop notification.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+    /// Shown as critical where the platform supports it
+    pub urgent: bool,
+}
+
+/// Shows notifications on the desktop.
+pub trait NotificationBackend: Send {
+    fn show(&mut self, notification: &Notification) -> Result<(), String>;
+}
+
+/// The notification backend of the current platform: freedesktop notifications
+/// over D-Bus on Linux, toasts on Windows, and the log elsewhere.
+pub fn default_backend() -> Box<dyn NotificationBackend> {
+    Box::new(platform::Backend::default())
+}
+
+/// Builds the notification for an event, or `None` if the event is not worth a
+/// notification or its category is disabled.
+///
+/// `name` maps a folder or device ID to its label; it is only called for
+/// notifications that are shown.
+pub fn notification_for(
+    event: &Event,
+    config: &NotificationsConfig,
+    name: &dyn Fn(&str) -> String,
+) -> Option<Notification> {
+    let instance = &event.instance;
+    let (title, body, urgent) = match &event.kind {
+        EventKind::ProcessExited { code } if config.syncthing_crashed && *code != Some(0) => (
+            "Syncthing crashed",
+            match code {
+                Some(code) => format!("{instance} exited with code {code}."),
+                None => format!("{instance} exited unexpectedly."),
+            },
+            true,
+        ),
+        EventKind::RestartGaveUp { attempts } if config.restart_gave_up => (
+            "Syncthing restart failed",
+            format!(
+                "{instance} was restarted {attempts} times in a row. Automatic restarts have been stopped."
+            ),
+            true,
+        ),
+        EventKind::DeviceDisconnected { device, .. } if config.device_disconnected => (
+            "Device disconnected",
+            format!("{instance}: {} disconnected.", name(device)),
+            false,
+        ),
+        EventKind::FolderErrors { folder, errors } if config.folder_errors => {
+            let first = errors.first()?;
+            (
+                "Folder errors",
+                format!(
+                    "{instance}: {} file(s) in {} could not be synced, e.g. {}: {}",
+                    errors.len(),
+                    name(folder),
+                    first.path,
+                    first.error
+                ),
+                false,
+            )
+        }
+        EventKind::LocalChangeDetected {
+            folder,
+            path,
+            action,
+        }
+        | EventKind::RemoteChangeDetected {
+            folder,
+            path,
+            action,
+        } if config.conflicts && path.contains(CONFLICT_MARKER) && action != "deleted" => (
+            "Sync conflict",
+            format!(
+                "{instance}: conflicting changes in {}: {path}",
+                name(folder)
+            ),
+            false,
+        ),
+        EventKind::StateChanged { folder, from, to }
+            if config.sync_complete && from == "syncing" && to == "idle" =>
+        {
+            (
+                "Sync complete",
+                format!("{instance}: {} is up to date.", name(folder)),
+                false,
+            )
+        }
+        _ => return None,
+    };
+
+    Some(Notification {
+        title: title.to_string(),
+        body,
+        urgent,
+    })
+}
+
+/// Looks up the label of a folder or the name of a device.
+fn lookup_name(client: &SyncthingClient, id: &str) -> Option<String> {
+    if let Ok(folders) = client.config_folders()
+        && let Some(folder) = folders.iter().find(|folder| folder.id == id)
+    {
+        return Some(folder.display_name().to_string());
+    }
+    client
+        .config_devices()
+        .ok()?
+        .iter()
+        .find(|device| device.device_id == id)
+        .map(|device| device.display_name().to_string())
+}
+
+/// Spawns a background thread that shows notifications for events on
+/// `AppState::events`, as enabled in `config.notifications`.
+pub fn spawn(app_state: Arc<Mutex<AppState>>) {
+    let events = match app_state.lock() {
+        Ok(state) => state.events.subscribe(),
+        Err(_) => {
+            log::error!("Failed to lock app state for notifications");
+            return;
+        }
+    };
+    thread::spawn(move || run(&app_state, &events, default_backend()));
+}
+
+/// Shows notifications for the received events until the event bus goes away.
+fn run(
+    app_state: &Arc<Mutex<AppState>>,
+    events: &Receiver<Event>,
+    mut backend: Box<dyn NotificationBackend>,
+) {
+    let mut last_shown: HashMap<Notification, Instant> = HashMap::new();
+
+    for event in events {
+        // Settings are re-read for every event so configuration reloads apply
+        let Ok((config, client)) = app_state.lock().map(|state| {
+            let client = state
+                .instances
+                .iter()
+                .find(|instance| instance.name() == event.instance)
+                .map(|instance| instance.api_client());
+            (state.config.notifications.clone(), client)
+        }) else {
+            log::error!("Failed to lock app state for notifications");
+            return;
+        };
+
+        // The state lock is released before names are looked up
+        let name = |id: &str| {
+            client
+                .as_ref()
+                .and_then(|client| lookup_name(client, id))
+                .unwrap_or_else(|| id.to_string())
+        };
+        let Some(notification) = notification_for(&event, &config, &name) else {
+            continue;
+        };
+
+        let now = Instant::now();
+        if last_shown
+            .get(&notification)
+            .is_some_and(|shown| now.duration_since(*shown) < REPEAT_INTERVAL)
+        {
+            continue;
+        }
+        last_shown.retain(|_, shown| now.duration_since(*shown) < REPEAT_INTERVAL);
+        last_shown.insert(notification.clone(), now);
+
+        log::info!(
+            "Notification: {}: {}",
+            notification.title,
+            notification.body
+        );
+        if let Err(e) = backend.show(&notification) {
+            log::debug!("Cannot show notification: {}", e);
+        }
+    }
+}
+
+/// Escapes text for the markup subset understood by notification servers and toasts.
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::collections::HashMap;
+
+    use zbus::blocking::Connection;
+    use zbus::zvariant::Value;
+
+    use super::{Notification, NotificationBackend, escape_markup};
+
+    /// Freedesktop notifications (`org.freedesktop.Notifications`) over the session bus.
+    #[derive(Default)]
+    pub struct Backend {
+        connection: Option<Connection>,
+    }
+
+    impl NotificationBackend for Backend {
+        fn show(&mut self, notification: &Notification) -> Result<(), String> {
+            let connection = match &self.connection {
+                Some(connection) => connection,
+                None => self
+                    .connection
+                    .insert(Connection::session().map_err(|e| e.to_string())?),
+            };
+
+            // Urgency levels of the specification: 1 is normal, 2 is critical
+            let urgency = if notification.urgent { 2u8 } else { 1u8 };
+            let hints = HashMap::from([("urgency", Value::U8(urgency))]);
+            let actions: Vec<&str> = Vec::new();
+            connection
+                .call_method(
+                    Some("org.freedesktop.Notifications"),
+                    "/org/freedesktop/Notifications",
+                    Some("org.freedesktop.Notifications"),
+                    "Notify",
+                    &(
+                        "Syncthingers",
+                        0u32,
+                        "syncthing",
+                        notification.title.as_str(),
+                        escape_markup(&notification.body),
+                        actions,
+                        hints,
+                        -1i32,
+                    ),
+                )
+                .map_err(|e| {
+                    // Reconnect on the next notification, e.g. after a session bus restart
+                    self.connection = None;
+                    e.to_string()
+                })?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::Data::Xml::Dom::XmlDocument;
+    use windows::UI::Notifications::{ToastNotification, ToastNotificationManager};
+    use windows::core::HSTRING;
+    use winreg::RegKey;
+    use winreg::enums::HKEY_CURRENT_USER;
+
+    use super::{Notification, NotificationBackend, escape_markup};
+
+    /// App user model ID the toasts are sent with.
+    const APP_ID: &str = "Syncthingers";
+
+    /// Registers `APP_ID` for the current user. Toasts of an unpackaged app are only
+    /// shown for a registered ID, which also gives them the app's display name.
+    fn register_app_id() -> std::io::Result<()> {
+        let (key, _) = RegKey::predef(HKEY_CURRENT_USER)
+            .create_subkey(format!(r"Software\Classes\AppUserModelId\{APP_ID}"))?;
+        key.set_value("DisplayName", &"Syncthingers")
+    }
+
+    /// Windows toast notifications.
+    #[derive(Default)]
+    pub struct Backend {
+        registered: bool,
+    }
+
+    impl NotificationBackend for Backend {
+        fn show(&mut self, notification: &Notification) -> Result<(), String> {
+            if !self.registered {
+                register_app_id().map_err(|e| format!("Failed to register app ID: {e}"))?;
+                self.registered = true;
+            }
+            let xml = format!(
+                r#"<toast><visual><binding template="ToastGeneric"><text>{}</text><text>{}</text></binding></visual></toast>"#,
+                escape_markup(&notification.title),
+                escape_markup(&notification.body)
+            );
+
+            let show = || -> windows::core::Result<()> {
+                let document = XmlDocument::new()?;
+                document.LoadXml(&HSTRING::from(xml))?;
+                let toast = ToastNotification::CreateToastNotification(&document)?;
+                ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(APP_ID))?
+                    .Show(&toast)
+            };
+            show().map_err(|e| e.to_string())
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+mod platform {
+    use super::{Notification, NotificationBackend};
+
+    /// Fallback for platforms without a native backend; `run` already logs every
+    /// notification.
+    #[derive(Default)]
+    pub struct Backend;
+
+    impl NotificationBackend for Backend {
+        fn show(&mut self, _notification: &Notification) -> Result<(), String> {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_dirs::AppDirs;
+    use crate::config::Config;
+    use crate::events::FolderError;
+    use std::sync::mpsc;
+
+    /// Records notifications instead of showing them.
+    #[derive(Clone, Default)]
+    struct RecordingBackend {
+        shown: Arc<Mutex<Vec<Notification>>>,
+    }
+
+    impl NotificationBackend for RecordingBackend {
+        fn show(&mut self, notification: &Notification) -> Result<(), String> {
+            self.shown.lock().unwrap().push(notification.clone());
+            Ok(())
+        }
+    }
+
+    fn event(kind: EventKind) -> Event {
+        Event {
+            instance: "work".to_string(),
+            id: 1,
+            kind,
+        }
+    }
+
+    fn name(id: &str) -> String {
+        format!("<{id}>")
+    }
+
+    #[test]
+    fn test_notification_categories() {
+        let config = NotificationsConfig::default();
+
+        let crashed = notification_for(
+            &event(EventKind::ProcessExited { code: Some(2) }),
+            &config,
+            &name,
+        )
+        .unwrap();
+        assert_eq!(crashed.title, "Syncthing crashed");
+        assert_eq!(crashed.body, "work exited with code 2.");
+        assert!(crashed.urgent);
+        assert_eq!(
+            notification_for(
+                &event(EventKind::ProcessExited { code: Some(0) }),
+                &config,
+                &name
+            ),
+            None
+        );
+
+        let conflict = EventKind::LocalChangeDetected {
+            folder: "docs".to_string(),
+            path: "a.sync-conflict-20250601-101010-ABCDEFG.txt".to_string(),
+            action: "added".to_string(),
+        };
+        assert_eq!(
+            notification_for(&event(conflict), &config, &name)
+                .unwrap()
+                .body,
+            "work: conflicting changes in <docs>: a.sync-conflict-20250601-101010-ABCDEFG.txt"
+        );
+
+        // Disabled by default
+        let disconnected = event(EventKind::DeviceDisconnected {
+            device: "BUILDSV".to_string(),
+            error: "EOF".to_string(),
+        });
+        assert_eq!(notification_for(&disconnected, &config, &name), None);
+        let config = NotificationsConfig {
+            device_disconnected: true,
+            ..config
+        };
+        assert_eq!(
+            notification_for(&disconnected, &config, &name)
+                .unwrap()
+                .body,
+            "work: <BUILDSV> disconnected."
+        );
+    }
+
+    #[test]
+    fn test_run_shows_enabled_notifications_once() {
+        let config = Config {
+            syncthing_path: "nonexistent_test_syncthing".to_string(),
+            ..Config::default()
+        };
+        let app_state = Arc::new(Mutex::new(AppState::new(
+            config,
+            AppDirs::new(None).unwrap(),
+        )));
+        let (sender, events) = mpsc::channel();
+
+        let folder_errors = EventKind::FolderErrors {
+            folder: "docs".to_string(),
+            errors: vec![FolderError {
+                path: "a.txt".to_string(),
+                error: "permission denied".to_string(),
+            }],
+        };
+        sender.send(event(folder_errors.clone())).unwrap();
+        sender.send(event(folder_errors)).unwrap();
+        sender
+            .send(event(EventKind::StateChanged {
+                folder: "docs".to_string(),
+                from: "syncing".to_string(),
+                to: "idle".to_string(),
+            }))
+            .unwrap();
+        sender
+            .send(event(EventKind::RestartGaveUp { attempts: 5 }))
+            .unwrap();
+        // Ends the loop once the queued events are handled
+        drop(sender);
+
+        let backend = RecordingBackend::default();
+        let shown = backend.shown.clone();
+        run(&app_state, &events, Box::new(backend));
+
+        let titles: Vec<String> = shown
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|n| n.title.clone())
+            .collect();
+        assert_eq!(titles, vec!["Folder errors", "Syncthing restart failed"]);
+    }
+}
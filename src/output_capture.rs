@@ -0,0 +1,223 @@
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+/// Size at which `syncthing.log` is rotated.
+const MAX_LOG_SIZE: u64 = 5 * 1024 * 1024;
+/// Number of rotated files kept (`syncthing.log.1` .. `syncthing.log.N`).
+const MAX_ROTATED_FILES: usize = 3;
+/// Number of recent output lines included in startup errors.
+const TAIL_LINES: usize = 50;
+
+/// Shifts `log` -> `log.1` -> `log.2` ..., dropping the oldest file, once `log` has
+/// grown past `max_size`. Returns whether the log was rotated.
+///
+/// `log` is copied and then truncated rather than renamed, since a running Syncthing
+/// keeps writing to its open handle. The handle is in append mode, so writes continue
+/// at the start of the truncated file; output written between the copy and the
+/// truncation is lost.
+fn rotate_if_larger(path: &Path, max_size: u64, max_files: usize) -> io::Result<bool> {
+    let size = match fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    if size <= max_size {
+        return Ok(false);
+    }
+
+    if max_files > 0 {
+        let _ = fs::remove_file(rotated_path(path, max_files));
+        for index in (1..max_files).rev() {
+            let from = rotated_path(path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(path, index + 1))?;
+            }
+        }
+        fs::copy(path, rotated_path(path, 1))?;
+    }
+    OpenOptions::new().write(true).open(path)?.set_len(0)?;
+    Ok(true)
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// Captures the stdout and stderr of a Syncthing process.
+///
+/// The process writes straight into `syncthing.log`, so it keeps running when this
+/// app exits or forgets about it; a pipe would break with SIGPIPE instead. The log
+/// is rotated before each start and by `rotate_if_needed` while the process runs.
+/// The lines written since the start are read back from the file, so a startup
+/// failure can report what Syncthing actually printed.
+pub struct OutputCapture {
+    path: PathBuf,
+    /// Length of the log file when the process was started
+    start_offset: u64,
+}
+
+impl OutputCapture {
+    /// Creates a capture writing to the given log file.
+    pub fn new(log_path: PathBuf) -> Self {
+        Self {
+            path: log_path,
+            start_offset: 0,
+        }
+    }
+
+    /// Opens the log file for a new process and returns its stdout and stderr.
+    ///
+    /// If the file cannot be opened, the output is discarded.
+    pub fn child_stdio(&mut self) -> (Stdio, Stdio) {
+        match self.open() {
+            Ok((stdout, stderr)) => (Stdio::from(stdout), Stdio::from(stderr)),
+            Err(e) => {
+                log::warn!(
+                    "Failed to open Syncthing output log at {}: {}",
+                    self.path.display(),
+                    e
+                );
+                (Stdio::null(), Stdio::null())
+            }
+        }
+    }
+
+    fn open(&mut self) -> io::Result<(File, File)> {
+        self.rotate_if_needed();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.start_offset = file.metadata()?.len();
+        Ok((file.try_clone()?, file))
+    }
+
+    /// Rotates the log once it has grown past the size limit.
+    ///
+    /// Called on every monitor tick while the process runs.
+    pub fn rotate_if_needed(&mut self) {
+        match rotate_if_larger(&self.path, MAX_LOG_SIZE, MAX_ROTATED_FILES) {
+            Ok(true) => {
+                log::debug!("Rotated Syncthing output log {}", self.path.display());
+                self.start_offset = 0;
+            }
+            Ok(false) => {}
+            Err(e) => log::debug!("Failed to rotate Syncthing output log: {}", e),
+        }
+    }
+
+    /// The most recent output lines of the process, oldest first.
+    pub fn tail(&self) -> Vec<String> {
+        let Ok(mut file) = File::open(&self.path) else {
+            return Vec::new();
+        };
+        if file.seek(SeekFrom::Start(self.start_offset)).is_err() {
+            return Vec::new();
+        }
+
+        let mut tail = VecDeque::with_capacity(TAIL_LINES);
+        let mut reader = BufReader::new(file);
+        let mut buffer = Vec::new();
+        while let Ok(read) = reader.read_until(b'\n', &mut buffer) {
+            if read == 0 {
+                break;
+            }
+            let line = String::from_utf8_lossy(&buffer);
+            if tail.len() >= TAIL_LINES {
+                tail.pop_front();
+            }
+            tail.push_back(line.trim_end_matches(['\r', '\n']).to_string());
+            buffer.clear();
+        }
+        tail.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_rotate_if_larger_drops_oldest() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("syncthing.log");
+
+        for line in ["first", "second", "third", "fourth"] {
+            fs::write(&path, format!("{line}\n")).unwrap();
+            rotate_if_larger(&path, 5, 2).unwrap();
+        }
+        fs::write(&path, "fifth\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fifth\n");
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 1)).unwrap(),
+            "fourth\n"
+        );
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 2)).unwrap(),
+            "third\n"
+        );
+        assert!(!rotated_path(&path, 3).exists());
+
+        // Small files are appended to
+        rotate_if_larger(&path, 100, 2).unwrap();
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_tail_only_contains_output_since_start() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("syncthing.log");
+        fs::write(&path, "previous run\n").unwrap();
+
+        let mut capture = OutputCapture::new(path.clone());
+        let (mut stdout, _) = capture.open().unwrap();
+        let output: String = (0..TAIL_LINES + 5)
+            .map(|i| format!("line {}\r\n", i))
+            .collect();
+        io::Write::write_all(&mut stdout, output.as_bytes()).unwrap();
+
+        let tail = capture.tail();
+        assert_eq!(tail.len(), TAIL_LINES);
+        assert_eq!(tail.first().unwrap(), "line 5");
+        assert_eq!(tail.last().unwrap(), &format!("line {}", TAIL_LINES + 4));
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert_eq!(contents.lines().count(), TAIL_LINES + 6);
+        assert!(contents.starts_with("previous run\nline 0\r\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_child_keeps_writing_after_capture_is_dropped() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("syncthing.log");
+
+        let mut capture = OutputCapture::new(path.clone());
+        let (stdout, stderr) = capture.child_stdio();
+        let mut child = std::process::Command::new("sh")
+            .args([
+                "-c",
+                "echo started; sleep 0.3; echo still running; echo done >&2",
+            ])
+            .stdout(stdout)
+            .stderr(stderr)
+            .spawn()
+            .unwrap();
+        drop(capture);
+
+        // A closed pipe would make the later writes fail or kill the shell with SIGPIPE
+        let status = child.wait().unwrap();
+        assert!(status.success());
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "started\nstill running\ndone\n"
+        );
+    }
+}
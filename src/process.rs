@@ -1,21 +1,29 @@
+use crate::output_capture::OutputCapture;
+use crate::process_match::{ProcessMatcher, ProcessSnapshot};
+use crate::process_tree;
+use crate::syncthing_api::SyncthingClient;
 use crate::utils;
 use std::io;
-use std::path::Path;
-use std::process::{Child, Command, Stdio};
-use sysinfo::{Pid, ProcessesToUpdate, System};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessStatus, ProcessesToUpdate, System};
 
 /// Represents a Syncthing process that can be managed by the application.
 ///
 /// This struct handles both processes started by the app and external processes.
 /// Uses sysinfo for cross-platform process management.
-/// Tracks all Syncthing processes (parent and children) for complete shutdown.
+/// Tracks the process tree rooted at the spawned process for complete shutdown.
 pub struct SyncthingProcess {
     child: Option<Child>,
     pub started_by_app: bool,
     pub syncthing_path: String,
-    pub pid: Option<u32>,   // Main process ID
-    tracked_pids: Vec<u32>, // All Syncthing process IDs (parent + children)
-    system: System,         // sysinfo System instance for process monitoring
+    pub pid: Option<u32>,                  // Main process ID
+    tracked_pids: Vec<u32>,                // Spawned process and all of its descendants
+    system: System,                        // sysinfo System instance for process monitoring
+    exit_status: Option<ExitStatus>, // Exit status of the app-started process once it has exited
+    output: Option<OutputCapture>,   // Log file receiving stdout/stderr of app-started processes
+    started_at: Option<Instant>,     // When this app started the process
+    child_matcher: Option<ProcessMatcher>, // Restricts tracked children to matching processes
 }
 
 // Mark SyncthingProcess as safe to send and share between threads
@@ -33,84 +41,92 @@ impl SyncthingProcess {
             pid: None,
             tracked_pids: Vec::new(),
             system: System::new(),
+            exit_status: None,
+            output: None,
+            started_at: None,
+            child_matcher: None,
         }
     }
 
-    /// Detects if a Syncthing process is currently running and creates a SyncthingProcess instance.
-    pub fn detect_process(syncthing_path: &str, external_only: bool) -> io::Result<Option<Self>> {
-        use sysinfo::{ProcessesToUpdate, System};
-        let mut system = System::new();
-        // Only refresh what we need - just process names and PIDs
-        system.refresh_processes(ProcessesToUpdate::All, false);
-
-        // Get the executable name to search for
-        let exe_name = Path::new(syncthing_path)
-            .file_name()
-            .and_then(|name| name.to_str())
-            .unwrap_or("syncthing");
-
-        // Normalize executable name for cross-platform compatibility
-        let exe_name = utils::normalize_exe_name(exe_name);
-
-        // Find processes matching the Syncthing executable
-        for (pid, process) in system.processes() {
-            let process_name = process.name().to_string_lossy();
-            let process_name = utils::normalize_exe_name(&process_name);
-
-            if process_name.eq_ignore_ascii_case(exe_name) {
-                let mut syncthing_proc = Self::new(syncthing_path);
-                syncthing_proc.pid = Some(pid.as_u32());
-                syncthing_proc.started_by_app = false; // External process
-
-                log::info!(
-                    "Detected {} Syncthing process with PID: {}",
-                    if external_only {
-                        "external"
-                    } else {
-                        "existing"
-                    },
-                    pid
-                );
-
-                return Ok(Some(syncthing_proc));
-            }
-        }
-        Ok(None)
+    /// Only tracks direct children of the spawned process that match `matcher`,
+    /// instead of the whole process tree.
+    ///
+    /// Used in strict matching mode, so stopping Syncthing never touches unrelated
+    /// processes it started, e.g. external versioning commands.
+    pub fn restrict_children(&mut self, matcher: ProcessMatcher) {
+        self.child_matcher = Some(matcher);
     }
 
-    /// Finds all Syncthing processes currently running on the system.
-    fn find_all_syncthing_processes(&mut self) -> Vec<u32> {
-        self.system.refresh_processes(ProcessesToUpdate::All, false);
-
-        // Get the executable name to search for
-        let exe_name = Path::new(&self.syncthing_path)
-            .file_name()
-            .and_then(|name| name.to_str())
-            .unwrap_or("syncthing");
+    /// Captures stdout and stderr of the next started process instead of discarding them.
+    pub fn set_output_capture(&mut self, capture: OutputCapture) {
+        self.output = Some(capture);
+    }
 
-        // Normalize executable name for cross-platform compatibility
-        let exe_name = utils::normalize_exe_name(exe_name);
+    /// Detects if a Syncthing process is currently running and creates a SyncthingProcess instance.
+    pub fn detect_process(
+        matcher: &ProcessMatcher,
+        external_only: bool,
+    ) -> io::Result<Option<Self>> {
+        let mut system = System::new();
 
-        let mut pids = Vec::new();
-        for (pid, process) in self.system.processes() {
-            let process_name = process.name().to_string_lossy();
-            let process_name = utils::normalize_exe_name(&process_name);
+        // Find processes matching the configured Syncthing
+        if let Some(found) = matcher.find(&mut system).into_iter().next() {
+            let mut syncthing_proc = Self::new(matcher.syncthing_path());
+            syncthing_proc.pid = Some(found.pid);
+            syncthing_proc.started_by_app = false; // External process
 
-            if process_name.eq_ignore_ascii_case(exe_name) {
-                pids.push(pid.as_u32());
-            }
+            log::info!(
+                "Detected {} Syncthing process with PID: {} ({})",
+                if external_only {
+                    "external"
+                } else {
+                    "existing"
+                },
+                found.pid,
+                found
+                    .exe
+                    .as_deref()
+                    .map(|exe| exe.display().to_string())
+                    .unwrap_or_else(|| found.name.clone())
+            );
+
+            return Ok(Some(syncthing_proc));
         }
-
-        log::debug!("Found {} Syncthing processes: {:?}", pids.len(), pids);
-        pids
+        Ok(None)
     }
 
-    /// Updates the list of tracked Syncthing processes.
+    /// Updates the tracked process tree of an app-started process.
+    ///
+    /// Walks parent/child relationships from the spawned PID, so children restarted by
+    /// Syncthing's monitor process are picked up and unrelated instances are never tracked.
     fn update_tracked_processes(&mut self) {
-        if self.started_by_app {
-            self.tracked_pids = self.find_all_syncthing_processes();
+        if !self.started_by_app {
+            return;
+        }
+        let Some(root) = self.pid else {
+            return;
+        };
+
+        let refresh_kind = self
+            .child_matcher
+            .as_ref()
+            .map_or_else(ProcessRefreshKind::nothing, ProcessMatcher::refresh_kind);
+        self.system
+            .refresh_processes_specifics(ProcessesToUpdate::All, true, refresh_kind);
+        let table: Vec<ProcessSnapshot> = self
+            .system
+            .processes()
+            .iter()
+            .map(|(pid, process)| ProcessSnapshot::from_process(*pid, process))
+            .collect();
+
+        let tree = tracked_tree(&table, root, self.child_matcher.as_ref());
+        if tree != self.tracked_pids {
+            log::debug!("Tracked Syncthing process tree: {:?}", tree);
+            self.tracked_pids = tree;
         }
     }
+
     /// Starts a new Syncthing process.
     pub fn start(&mut self, args: &[String]) -> io::Result<()> {
         if self.child.is_some() {
@@ -124,8 +140,12 @@ impl SyncthingProcess {
 
         let mut command = Command::new(&self.syncthing_path);
         command.args(args);
-        command.stdout(Stdio::null());
-        command.stderr(Stdio::null());
+        let (stdout, stderr) = match &mut self.output {
+            Some(output) => output.child_stdio(),
+            None => (Stdio::null(), Stdio::null()),
+        };
+        command.stdout(stdout);
+        command.stderr(stderr);
 
         // On Windows, prevent console window from appearing
         #[cfg(windows)]
@@ -141,6 +161,7 @@ impl SyncthingProcess {
 
         self.child = Some(child);
         self.started_by_app = true;
+        self.started_at = Some(Instant::now());
 
         // Give the process a moment to potentially spawn children
         std::thread::sleep(std::time::Duration::from_millis(500));
@@ -157,9 +178,7 @@ impl SyncthingProcess {
                     self.pid = None;
                     self.started_by_app = false;
                     self.tracked_pids.clear();
-                    return Err(io::Error::other(
-                        "Syncthing process exited immediately after start",
-                    ));
+                    return Err(io::Error::other(self.early_exit_message(status)));
                 }
                 Ok(None) => {
                     // Process is alive
@@ -170,7 +189,7 @@ impl SyncthingProcess {
             }
         }
 
-        // Track all Syncthing processes (including any children that may have spawned)
+        // Track the spawned process and any children it started
         self.update_tracked_processes();
         log::info!(
             "Tracking {} Syncthing processes: {:?}",
@@ -182,7 +201,11 @@ impl SyncthingProcess {
     }
 
     /// Stops the Syncthing process if it was started by this application.
-    pub fn stop(&mut self) -> io::Result<()> {
+    ///
+    /// Requests a graceful shutdown and stops tracking the process. `Shutdown::wait`
+    /// then force-kills the processes that are still running once the grace period
+    /// has elapsed; it is separate so callers can release locks first.
+    pub fn begin_stop(&mut self, options: &StopOptions) -> io::Result<Shutdown> {
         if !self.started_by_app {
             return Err(io::Error::new(
                 io::ErrorKind::PermissionDenied,
@@ -190,48 +213,34 @@ impl SyncthingProcess {
             ));
         }
 
-        log::info!(
-            "Stopping Syncthing process tree (main PID: {:?}, {} tracked processes)",
-            self.pid,
-            self.tracked_pids.len()
-        );
-
-        // First, try to stop all tracked processes using sysinfo
-        let mut stopped_count = 0;
-        for &pid in &self.tracked_pids {
-            let pid_obj = Pid::from(pid as usize);
-            self.system
-                .refresh_processes(ProcessesToUpdate::Some(&[pid_obj]), false);
-
-            if let Some(process) = self.system.process(pid_obj) {
-                if process.kill() {
-                    log::info!(
-                        "Successfully terminated tracked Syncthing process PID: {}",
-                        pid
-                    );
-                    stopped_count += 1;
-                } else {
-                    log::warn!("Failed to terminate tracked Syncthing process PID: {}", pid);
-                }
-            }
-        }
-
-        if stopped_count > 0 {
-            log::info!("Stopped {} tracked Syncthing processes", stopped_count);
-        }
-
-        // Also handle the main child process if we have it
-        if let Some(mut child) = self.child.take() {
-            match child.kill() {
-                Ok(()) => {
-                    log::info!("Main Syncthing process terminated successfully");
-                    let _ = child.wait(); // Clean up zombie process
-                }
-                Err(e) => {
-                    log::warn!("Failed to kill main Syncthing process: {}", e);
-                }
+        // Skip real process operations for clearly test-related paths
+        let shutdown = if utils::is_test_environment(&self.syncthing_path) {
+            log::debug!(
+                "Skipping process termination for test path: {}",
+                self.syncthing_path
+            );
+            Shutdown::default()
+        } else {
+            self.update_tracked_processes();
+            log::info!(
+                "Stopping Syncthing process tree (main PID: {:?}, {} tracked processes)",
+                self.pid,
+                self.tracked_pids.len()
+            );
+
+            let mut pids = self.tracked_pids.clone();
+            if let Some(pid) = self.pid
+                && !pids.contains(&pid)
+            {
+                pids.push(pid);
             }
-        }
+            Shutdown::begin(
+                std::mem::take(&mut self.system),
+                pids,
+                self.child.take(),
+                options,
+            )
+        };
 
         // Clear all tracking
         self.child = None;
@@ -239,16 +248,37 @@ impl SyncthingProcess {
         self.started_by_app = false;
         self.tracked_pids.clear();
 
-        Ok(())
+        Ok(shutdown)
+    }
+
+    /// Builds the error for a process that exited during startup, including its last output.
+    fn early_exit_message(&self, status: ExitStatus) -> String {
+        let mut message = match status.code() {
+            Some(code) => format!("Syncthing process exited immediately with code {}", code),
+            None => "Syncthing process exited immediately after start".to_string(),
+        };
+
+        if let Some(output) = &self.output {
+            let tail = output.tail();
+            if !tail.is_empty() {
+                for line in &tail {
+                    log::error!("Syncthing output: {}", line);
+                }
+                message.push_str(":\n");
+                message.push_str(&tail.join("\n"));
+            }
+        }
+        message
     }
 
     /// Checks if the process is currently running.
     pub fn is_running(&mut self) -> bool {
         if let Some(child) = &mut self.child {
             match child.try_wait() {
-                Ok(Some(_)) => {
+                Ok(Some(status)) => {
                     // Process has exited
-                    log::debug!("Process has exited, cleaning up");
+                    log::debug!("Process has exited with {}, cleaning up", status);
+                    self.exit_status = Some(status);
                     self.child = None;
                     self.pid = None;
                     self.started_by_app = false;
@@ -256,7 +286,11 @@ impl SyncthingProcess {
                     false
                 }
                 Ok(None) => {
-                    // Process is still running
+                    // Process is still running; pick up children restarted by its monitor
+                    self.update_tracked_processes();
+                    if let Some(output) = &mut self.output {
+                        output.rotate_if_needed();
+                    }
                     true
                 }
                 Err(_) => {
@@ -291,6 +325,16 @@ impl SyncthingProcess {
         }
     }
 
+    /// Takes the exit status recorded when an app-started process was found to have exited.
+    pub fn take_exit_status(&mut self) -> Option<ExitStatus> {
+        self.exit_status.take()
+    }
+
+    /// Time since this app started the process, or `None` for external processes.
+    pub fn uptime(&self) -> Option<Duration> {
+        self.started_at.map(|started| started.elapsed())
+    }
+
     /// Checks if this process was started by the application.
     #[allow(dead_code)]
     pub fn is_started_by_app(&self) -> bool {
@@ -305,60 +349,367 @@ impl SyncthingProcess {
             pid: Some(12345),
             tracked_pids: Vec::new(),
             system: System::new(),
+            exit_status: None,
+            output: None,
+            started_at: None,
+            child_matcher: None,
         }
     }
 }
 
-/// Stops all external Syncthing processes running on the system.
-pub fn stop_external_syncthing_processes(syncthing_path: &str) -> io::Result<()> {
-    use sysinfo::{ProcessesToUpdate, System};
+/// Requests a graceful shutdown of all external Syncthing processes running on the
+/// system, leaving the wait for them to exit to the returned `Shutdown`.
+/// The processes tracked for an app-started process with PID `root`.
+///
+/// Without a matcher this is the whole tree below `root`; with one, only `root` and its
+/// direct children that match.
+fn tracked_tree(
+    table: &[ProcessSnapshot],
+    root: u32,
+    matcher: Option<&ProcessMatcher>,
+) -> Vec<u32> {
+    let tree = process_tree::descendants(table, root);
+    let Some(matcher) = matcher else {
+        return tree;
+    };
+    let matcher = matcher.clone().with_parent(root);
+    tree.into_iter()
+        .filter(|pid| {
+            *pid == root
+                || table
+                    .iter()
+                    .any(|process| process.pid == *pid && matcher.matches(process))
+        })
+        .collect()
+}
+
+pub fn begin_stop_external(matcher: &ProcessMatcher, options: &StopOptions) -> Shutdown {
     // Skip process killing for clearly test-related paths
-    if utils::is_test_environment(syncthing_path) {
+    if utils::is_test_environment(matcher.syncthing_path()) {
         log::debug!(
             "Skipping external process termination for test path: {}",
-            syncthing_path
+            matcher.syncthing_path()
         );
-        return Ok(());
+        return Shutdown::default();
     }
     let mut system = System::new();
-    // Only refresh what we need - process names for matching
-    system.refresh_processes(ProcessesToUpdate::All, false);
-
-    // Get the executable name to search for
-    let exe_name = Path::new(syncthing_path)
-        .file_name()
-        .and_then(|name| name.to_str())
-        .unwrap_or("syncthing");
-
-    // Normalize executable name for cross-platform compatibility
-    let exe_name = utils::normalize_exe_name(exe_name);
-
-    let mut terminated_count = 0; // Find and terminate all Syncthing processes
-    for (pid, process) in system.processes() {
-        let process_name = process.name().to_string_lossy();
-        let process_name = utils::normalize_exe_name(&process_name);
-
-        if process_name.eq_ignore_ascii_case(exe_name) {
-            log::info!("Terminating external Syncthing process with PID: {}", pid);
-
-            // Use sysinfo's cross-platform process killing
-            if process.kill() {
-                terminated_count += 1;
-                log::info!("Successfully terminated process {}", pid);
-            } else {
-                log::warn!("Failed to terminate process {}", pid);
+    let pids: Vec<u32> = matcher
+        .find(&mut system)
+        .into_iter()
+        .map(|process| process.pid)
+        .collect();
+
+    if pids.is_empty() {
+        log::debug!("No external Syncthing processes found to terminate");
+        return Shutdown::default();
+    }
+
+    log::info!("Stopping external Syncthing processes: {:?}", pids);
+    Shutdown::begin(system, pids, None, options)
+}
+
+/// How a Syncthing process was stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopMethod {
+    /// Exited after `POST /rest/system/shutdown`.
+    RestShutdown,
+    /// Exited after SIGTERM (Unix only).
+    Terminate,
+    /// Still running after the grace period and killed.
+    ForceKill,
+    /// Still running after the grace period and could not be killed.
+    Failed,
+}
+
+impl std::fmt::Display for StopMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StopMethod::RestShutdown => write!(f, "REST shutdown"),
+            StopMethod::Terminate => write!(f, "SIGTERM"),
+            StopMethod::ForceKill => write!(f, "force kill"),
+            StopMethod::Failed => write!(f, "failed to stop"),
+        }
+    }
+}
+
+/// Options controlling how Syncthing processes are stopped.
+pub struct StopOptions {
+    /// Client used to request a graceful shutdown over REST, if available.
+    pub api: Option<SyncthingClient>,
+    /// How long to wait for processes to exit before force-killing them.
+    pub grace_period: Duration,
+}
+
+/// Records which method actually stopped each process.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StopReport {
+    pub stopped: Vec<(u32, StopMethod)>,
+}
+
+impl StopReport {
+    /// Writes one log line per stopped process.
+    pub fn log(&self) {
+        for (pid, method) in &self.stopped {
+            match method {
+                StopMethod::Failed => {
+                    log::warn!("Syncthing process PID {} could not be stopped", pid)
+                }
+                StopMethod::ForceKill => log::warn!(
+                    "Syncthing process PID {} did not exit within the grace period and was killed",
+                    pid
+                ),
+                _ => log::info!("Syncthing process PID {} stopped via {}", pid, method),
             }
         }
     }
+}
 
-    if terminated_count > 0 {
-        log::info!(
-            "Terminated {} external Syncthing process(es)",
-            terminated_count
-        );
-    } else {
-        log::debug!("No external Syncthing processes found to terminate");
+/// Interval at which processes are checked while waiting for them to exit.
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Processes asked to shut down that have not been waited for yet.
+///
+/// Created by `SyncthingProcess::begin_stop`; `wait` blocks for up to the grace
+/// period, so it is called without holding the application state lock.
+#[derive(Default)]
+pub struct Shutdown {
+    system: System,
+    pids: Vec<u32>,
+    /// Handle of the process we spawned, if any; reaped so it does not linger as a zombie
+    child: Option<Child>,
+    /// How the graceful shutdown was requested, `None` if no request was delivered
+    graceful: Option<StopMethod>,
+    grace_period: Duration,
+}
+
+impl Shutdown {
+    /// Requests a graceful shutdown of the given processes.
+    fn begin(
+        mut system: System,
+        pids: Vec<u32>,
+        child: Option<Child>,
+        options: &StopOptions,
+    ) -> Self {
+        let graceful = request_graceful_shutdown(&mut system, &pids, options.api.as_ref());
+        Self {
+            system,
+            pids,
+            child,
+            graceful,
+            grace_period: options.grace_period,
+        }
     }
 
-    Ok(())
+    /// Waits for the grace period and force-kills whatever is still running.
+    pub fn wait(mut self) -> StopReport {
+        let system = &mut self.system;
+        let mut remaining = std::mem::take(&mut self.pids);
+        let mut report = StopReport::default();
+        if let Some(method) = self.graceful {
+            let deadline = Instant::now() + self.grace_period;
+            loop {
+                remaining.retain(|&pid| {
+                    let alive = is_pid_alive(system, pid, self.child.as_mut());
+                    if !alive {
+                        report.stopped.push((pid, method));
+                    }
+                    alive
+                });
+                if remaining.is_empty() || Instant::now() >= deadline {
+                    break;
+                }
+                std::thread::sleep(EXIT_POLL_INTERVAL);
+            }
+        }
+
+        for pid in remaining {
+            let pid_obj = Pid::from(pid as usize);
+            system.refresh_processes(ProcessesToUpdate::Some(&[pid_obj]), false);
+            let killed = match system.process(pid_obj) {
+                Some(process) => process.kill(),
+                // Exited between the last check and now
+                None => true,
+            };
+            report.stopped.push((
+                pid,
+                if killed {
+                    StopMethod::ForceKill
+                } else {
+                    StopMethod::Failed
+                },
+            ));
+        }
+
+        if let Some(child) = &mut self.child {
+            let _ = child.kill();
+            let _ = child.wait(); // Clean up zombie process
+        }
+
+        report.log();
+        report
+    }
+}
+
+/// Asks Syncthing to shut down, returning the method used if the request was delivered.
+fn request_graceful_shutdown(
+    system: &mut System,
+    pids: &[u32],
+    api: Option<&SyncthingClient>,
+) -> Option<StopMethod> {
+    if let Some(api) = api {
+        match api.shutdown() {
+            Ok(()) => {
+                log::info!("Requested Syncthing shutdown via REST API");
+                return Some(StopMethod::RestShutdown);
+            }
+            Err(e) => log::warn!("REST shutdown request failed: {}", e),
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        use sysinfo::Signal;
+        let pid_objs: Vec<Pid> = pids.iter().map(|&pid| Pid::from(pid as usize)).collect();
+        system.refresh_processes(ProcessesToUpdate::Some(&pid_objs), false);
+        let mut sent = false;
+        for pid in &pid_objs {
+            if let Some(process) = system.process(*pid) {
+                sent |= process.kill_with(Signal::Term).unwrap_or(false);
+            }
+        }
+        if sent {
+            log::info!("Sent SIGTERM to Syncthing processes: {:?}", pids);
+            return Some(StopMethod::Terminate);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = (system, pids);
+
+    None
+}
+
+/// Checks whether a process is still running, reaping our own child if it exited.
+fn is_pid_alive(system: &mut System, pid: u32, child: Option<&mut Child>) -> bool {
+    if let Some(child) = child
+        && child.id() == pid
+    {
+        return matches!(child.try_wait(), Ok(None));
+    }
+
+    let pid_obj = Pid::from(pid as usize);
+    system.refresh_processes(ProcessesToUpdate::Some(&[pid_obj]), true);
+    system
+        .process(pid_obj)
+        .is_some_and(|p| p.status() != ProcessStatus::Zombie)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::config::MatchStrictness;
+
+    fn options(grace_period: Duration) -> StopOptions {
+        StopOptions {
+            api: None,
+            grace_period,
+        }
+    }
+
+    #[test]
+    fn test_strict_tracking_only_follows_matching_children() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let exe = dir.path().join("syncthing");
+        std::fs::write(&exe, b"").unwrap();
+        let process = |pid, parent, name: &str, exe: Option<&std::path::Path>| ProcessSnapshot {
+            pid,
+            parent: Some(parent),
+            name: name.to_string(),
+            exe: exe.map(std::path::Path::to_path_buf),
+            cmd: Vec::new(),
+            user_id: None,
+            start_time: 0,
+        };
+        let table = vec![
+            process(10, 1, "syncthing", Some(&exe)),
+            process(11, 10, "syncthing", Some(&exe)),
+            process(12, 10, "rsync", None),
+            process(13, 11, "syncthing", Some(&exe)),
+        ];
+
+        assert_eq!(tracked_tree(&table, 10, None), vec![10, 11, 12, 13]);
+        let matcher = ProcessMatcher::new(exe.to_str().unwrap(), MatchStrictness::Strict);
+        assert_eq!(tracked_tree(&table, 10, Some(&matcher)), vec![10, 11]);
+    }
+
+    #[test]
+    fn test_stop_terminates_gracefully() {
+        let mut process = SyncthingProcess::new("sleep");
+        process.start(&["30".to_string()]).unwrap();
+        let pid = process.pid.unwrap();
+
+        let shutdown = process
+            .begin_stop(&options(Duration::from_secs(5)))
+            .unwrap();
+        // The process is forgotten right away; only the wait takes time
+        assert!(!process.is_running());
+        assert_eq!(shutdown.wait().stopped, vec![(pid, StopMethod::Terminate)]);
+    }
+
+    #[test]
+    fn test_output_log_rotates_while_running() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let log = dir.path().join("syncthing.log");
+        let mut process = SyncthingProcess::new("sh");
+        process.set_output_capture(OutputCapture::new(log.clone()));
+        // Writes a bit more than the 5 MB limit, then keeps running
+        process
+            .start(&[
+                "-c".to_string(),
+                "head -c 5300000 /dev/zero | tr '\\0' x; echo; sleep 30".to_string(),
+            ])
+            .unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while fs_len(&log) < 5_300_000 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        assert!(process.is_running());
+        assert!(dir.path().join("syncthing.log.1").exists());
+        assert!(fs_len(&log) < 5_300_000);
+
+        process
+            .begin_stop(&options(Duration::from_secs(5)))
+            .unwrap()
+            .wait();
+    }
+
+    fn fs_len(path: &std::path::Path) -> u64 {
+        std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+    }
+
+    #[test]
+    fn test_stop_force_kills_after_grace_period() {
+        let mut process = SyncthingProcess::new("sh");
+        process
+            .start(&["-c".to_string(), "trap '' TERM; sleep 30; true".to_string()])
+            .unwrap();
+        let pid = process.pid.unwrap();
+
+        let started = Instant::now();
+        let report = process
+            .begin_stop(&options(Duration::from_millis(300)))
+            .unwrap()
+            .wait();
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        // The shell and its sleep both ignore SIGTERM
+        assert_eq!(report.stopped.len(), 2, "{:?}", report);
+        assert!(report.stopped.contains(&(pid, StopMethod::ForceKill)));
+        assert!(
+            report
+                .stopped
+                .iter()
+                .all(|(_, method)| *method == StopMethod::ForceKill)
+        );
+        assert!(!is_pid_alive(&mut System::new(), pid, None));
+    }
 }
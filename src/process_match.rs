@@ -0,0 +1,438 @@
+use std::path::{Path, PathBuf};
+use sysinfo::{Pid, Process, ProcessRefreshKind, ProcessesToUpdate, System, Uid, UpdateKind};
+
+use crate::config::{InstanceConfig, MatchStrictness};
+use crate::utils;
+
+/// Command-line flags that select Syncthing's configuration/data directory.
+const HOME_FLAGS: &[&str] = &["home", "config"];
+
+/// The information about a running process that is relevant for matching.
+///
+/// Decoupled from `sysinfo::Process` so matching can be tested against a fake process table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessSnapshot {
+    pub pid: u32,
+    pub parent: Option<u32>,
+    pub name: String,
+    pub exe: Option<PathBuf>,
+    pub cmd: Vec<String>,
+    pub user_id: Option<Uid>,
+    /// Start time in seconds since the epoch, used to detect reused PIDs
+    pub start_time: u64,
+}
+
+impl ProcessSnapshot {
+    pub fn from_process(pid: Pid, process: &Process) -> Self {
+        Self {
+            pid: pid.as_u32(),
+            parent: process.parent().map(|p| p.as_u32()),
+            name: process.name().to_string_lossy().into_owned(),
+            exe: process.exe().map(Path::to_path_buf),
+            cmd: process
+                .cmd()
+                .iter()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+            user_id: process.user_id().cloned(),
+            start_time: process.start_time(),
+        }
+    }
+}
+
+/// Decides whether a running process is the Syncthing instance configured in this app.
+///
+/// Depending on the strictness, a process matches by name only, by canonical executable
+/// path, or additionally by its `--home`/`--config` argument and owning user. An optional
+/// parent PID restricts matches to children of a specific process.
+#[derive(Debug, Clone)]
+pub struct ProcessMatcher {
+    strictness: MatchStrictness,
+    syncthing_path: String,
+    exe_name: String,
+    /// Canonical executable path, `None` if the configured path could not be resolved
+    exe_path: Option<PathBuf>,
+    home: Option<String>,
+    user_id: Option<Uid>,
+    parent_pid: Option<u32>,
+}
+
+impl ProcessMatcher {
+    /// Creates a matcher for the given executable without home, user or parent constraints.
+    pub fn new(syncthing_path: &str, strictness: MatchStrictness) -> Self {
+        let exe_name = Path::new(syncthing_path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("syncthing");
+        let exe_path = resolve_executable(syncthing_path).map(|p| normalize_path(&p));
+        if exe_path.is_none() && strictness != MatchStrictness::Name {
+            log::warn!(
+                "Syncthing executable '{}' not found; no running process will match it",
+                syncthing_path
+            );
+        }
+        Self {
+            strictness,
+            syncthing_path: syncthing_path.to_string(),
+            exe_name: utils::normalize_exe_name(exe_name).to_string(),
+            exe_path,
+            home: None,
+            user_id: None,
+            parent_pid: None,
+        }
+    }
+
+    /// Creates a matcher for a configured Syncthing instance.
+    ///
+    /// In path and strict mode the home directory is taken from `home` or `startup_args`,
+    /// so instances sharing an executable are told apart. Strict mode also requires the
+    /// owning user to be the user running this app.
+    pub fn from_instance(instance: &InstanceConfig, strictness: MatchStrictness) -> Self {
+        let matcher = Self::new(&instance.syncthing_path, strictness);
+        match strictness {
+            MatchStrictness::Name => matcher,
+            MatchStrictness::Path => matcher.with_home(instance.home_dir()),
+            MatchStrictness::Strict => matcher
+                .with_home(instance.home_dir())
+                .with_user(current_user_id()),
+        }
+    }
+
+    /// Requires the given `--home`/`--config` directory in path and strict mode.
+    pub fn with_home(mut self, home: Option<String>) -> Self {
+        self.home = home;
+        self
+    }
+
+    /// Requires the given owning user in strict mode.
+    pub fn with_user(mut self, user_id: Option<Uid>) -> Self {
+        self.user_id = user_id;
+        self
+    }
+
+    /// Only matches direct children of the given process.
+    pub fn with_parent(mut self, parent_pid: u32) -> Self {
+        self.parent_pid = Some(parent_pid);
+        self
+    }
+
+    /// The configured Syncthing executable path.
+    pub fn syncthing_path(&self) -> &str {
+        &self.syncthing_path
+    }
+
+    /// Process information that must be refreshed before calling `find`.
+    pub fn refresh_kind(&self) -> ProcessRefreshKind {
+        let kind = ProcessRefreshKind::nothing();
+        match self.strictness {
+            MatchStrictness::Name => kind,
+            MatchStrictness::Path if self.home.is_some() => kind
+                .with_exe(UpdateKind::OnlyIfNotSet)
+                .with_cmd(UpdateKind::OnlyIfNotSet),
+            MatchStrictness::Path => kind.with_exe(UpdateKind::OnlyIfNotSet),
+            MatchStrictness::Strict => kind
+                .with_exe(UpdateKind::OnlyIfNotSet)
+                .with_cmd(UpdateKind::OnlyIfNotSet)
+                .with_user(UpdateKind::OnlyIfNotSet),
+        }
+    }
+
+    /// Refreshes the process list and returns all matching processes.
+    pub fn find(&self, system: &mut System) -> Vec<ProcessSnapshot> {
+        system.refresh_processes_specifics(ProcessesToUpdate::All, true, self.refresh_kind());
+        let mut matches: Vec<ProcessSnapshot> = system
+            .processes()
+            .iter()
+            .map(|(pid, process)| ProcessSnapshot::from_process(*pid, process))
+            .filter(|snapshot| self.matches(snapshot))
+            .collect();
+        matches.sort_by_key(|snapshot| snapshot.pid);
+        matches
+    }
+
+    /// Checks a single process against all configured constraints.
+    pub fn matches(&self, process: &ProcessSnapshot) -> bool {
+        if let Some(parent) = self.parent_pid
+            && process.parent != Some(parent)
+        {
+            return false;
+        }
+
+        let name_matches =
+            utils::normalize_exe_name(&process.name).eq_ignore_ascii_case(&self.exe_name);
+        if self.strictness == MatchStrictness::Name {
+            return name_matches;
+        }
+
+        let path_matches = match (&self.exe_path, &process.exe) {
+            (Some(expected), Some(actual)) => paths_equal(expected, actual),
+            // The configured path does not exist, so no process can be ours
+            (None, _) => false,
+            // Executable of another user's process is often not readable; not ours
+            (Some(_), None) => false,
+        };
+        if !path_matches {
+            return false;
+        }
+
+        if self.strictness == MatchStrictness::Strict {
+            let user_matches = self.user_id.is_none() || self.user_id == process.user_id;
+            return self.home_matches(process) && user_matches;
+        }
+
+        // In path mode the home directory is only compared when one is required
+        self.home.is_none() || self.home_matches(process)
+    }
+
+    /// Compares the process's `--home`/`--config` argument with the required one.
+    fn home_matches(&self, process: &ProcessSnapshot) -> bool {
+        match (&self.home, &home_argument(&process.cmd)) {
+            (Some(expected), Some(actual)) => paths_equal(Path::new(expected), Path::new(actual)),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Extracts the value of `--home`/`--config` (or their single-dash forms) from arguments.
+pub fn home_argument<S: AsRef<str>>(args: &[S]) -> Option<String> {
+    let mut args = args.iter().map(AsRef::as_ref);
+    while let Some(arg) = args.next() {
+        let flag = arg.trim_start_matches('-');
+        if flag.len() == arg.len() {
+            continue;
+        }
+        match flag.split_once('=') {
+            Some((name, value)) if HOME_FLAGS.contains(&name) => return Some(value.to_string()),
+            None if HOME_FLAGS.contains(&flag) => return args.next().map(str::to_string),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Canonical path of the configured executable.
+///
+/// A bare file name such as `syncthing` is looked up in `PATH`, the same way it is
+/// found when the process is started.
+fn resolve_executable(syncthing_path: &str) -> Option<PathBuf> {
+    if let Ok(path) = std::fs::canonicalize(syncthing_path) {
+        return Some(path);
+    }
+    let path = Path::new(syncthing_path);
+    if path.components().count() != 1 {
+        return None;
+    }
+    let mut names = vec![path.to_path_buf()];
+    if cfg!(windows) && path.extension().is_none() {
+        names.push(path.with_extension("exe"));
+    }
+    let dirs = std::env::var_os("PATH")?;
+    std::env::split_paths(&dirs)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| candidate.is_file())
+        .and_then(|candidate| std::fs::canonicalize(candidate).ok())
+}
+
+/// User ID of the process running this app.
+fn current_user_id() -> Option<Uid> {
+    let pid = sysinfo::get_current_pid().ok()?;
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        false,
+        ProcessRefreshKind::nothing().with_user(UpdateKind::OnlyIfNotSet),
+    );
+    system.process(pid)?.user_id().cloned()
+}
+
+/// Makes paths from `canonicalize` and from the OS process table comparable.
+fn normalize_path(path: &Path) -> PathBuf {
+    let text = path.to_string_lossy();
+    // Windows verbatim prefix added by canonicalize
+    let text = text.strip_prefix(r"\\?\").unwrap_or(&text);
+    // Linux reports replaced binaries (e.g. after a Syncthing auto-upgrade) as deleted
+    let text = text.strip_suffix(" (deleted)").unwrap_or(text);
+    PathBuf::from(text)
+}
+
+fn paths_equal(a: &Path, b: &Path) -> bool {
+    let resolve = |p: &Path| normalize_path(&std::fs::canonicalize(p).unwrap_or_else(|_| p.into()));
+    let (a, b) = (resolve(a), resolve(b));
+    if cfg!(windows) {
+        a.to_string_lossy()
+            .eq_ignore_ascii_case(&b.to_string_lossy())
+    } else {
+        a == b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn snapshot(pid: u32, exe: Option<&Path>, cmd: &[&str]) -> ProcessSnapshot {
+        ProcessSnapshot {
+            pid,
+            parent: Some(1),
+            name: "syncthing".to_string(),
+            exe: exe.map(Path::to_path_buf),
+            cmd: cmd.iter().map(|s| s.to_string()).collect(),
+            user_id: None,
+            start_time: 0,
+        }
+    }
+
+    fn fake_executables() -> (TempDir, PathBuf, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let ours = dir.path().join("ours").join("syncthing");
+        let other = dir.path().join("other").join("syncthing");
+        for path in [&ours, &other] {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"").unwrap();
+        }
+        (dir, ours, other)
+    }
+
+    #[test]
+    fn test_home_argument_forms() {
+        assert_eq!(home_argument(&["--home=/a"]), Some("/a".to_string()));
+        assert_eq!(home_argument(&["-home", "/b"]), Some("/b".to_string()));
+        assert_eq!(
+            home_argument(&["serve", "--config", "/c"]),
+            Some("/c".to_string())
+        );
+        assert_eq!(home_argument(&["--no-browser", "home"]), None);
+        assert_eq!(home_argument::<&str>(&[]), None);
+    }
+
+    #[test]
+    fn test_name_strictness_ignores_path() {
+        let (_dir, ours, other) = fake_executables();
+        let matcher = ProcessMatcher::new(ours.to_str().unwrap(), MatchStrictness::Name);
+        assert!(matcher.matches(&snapshot(10, Some(&other), &[])));
+    }
+
+    #[test]
+    fn test_path_strictness_rejects_other_executable() {
+        let (_dir, ours, other) = fake_executables();
+        let matcher = ProcessMatcher::new(ours.to_str().unwrap(), MatchStrictness::Path);
+
+        assert!(matcher.matches(&snapshot(10, Some(&ours), &[])));
+        assert!(!matcher.matches(&snapshot(11, Some(&other), &[])));
+        assert!(!matcher.matches(&snapshot(12, None, &[])));
+    }
+
+    #[test]
+    fn test_strict_requires_same_home() {
+        let (_dir, ours, _other) = fake_executables();
+        let matcher = ProcessMatcher::new(ours.to_str().unwrap(), MatchStrictness::Strict)
+            .with_home(Some("/data/syncthing".to_string()));
+
+        assert!(matcher.matches(&snapshot(10, Some(&ours), &["--home=/data/syncthing"])));
+        assert!(!matcher.matches(&snapshot(11, Some(&ours), &["--home=/srv/other"])));
+        assert!(!matcher.matches(&snapshot(12, Some(&ours), &[])));
+    }
+
+    #[test]
+    fn test_instances_sharing_executable_are_told_apart_by_home() {
+        let (_dir, ours, _other) = fake_executables();
+        let instance = InstanceConfig {
+            name: "work".to_string(),
+            syncthing_path: ours.to_string_lossy().to_string(),
+            home: Some("/data/work".to_string()),
+            ..InstanceConfig::default()
+        };
+
+        let matcher = ProcessMatcher::from_instance(&instance, MatchStrictness::Path);
+        assert!(matcher.matches(&snapshot(10, Some(&ours), &["--home", "/data/work"])));
+        assert!(!matcher.matches(&snapshot(11, Some(&ours), &["--home=/data/personal"])));
+
+        let matcher = ProcessMatcher::from_instance(&instance, MatchStrictness::Name);
+        assert!(matcher.matches(&snapshot(12, Some(&ours), &["--home=/data/personal"])));
+    }
+
+    #[test]
+    fn test_path_strictness_uses_home_from_startup_args() {
+        let (_dir, ours, _other) = fake_executables();
+        let instance = InstanceConfig {
+            name: "work".to_string(),
+            syncthing_path: ours.to_string_lossy().to_string(),
+            startup_args: vec![
+                "--no-browser".to_string(),
+                "--home".to_string(),
+                "/data/work".to_string(),
+            ],
+            ..InstanceConfig::default()
+        };
+
+        let matcher = ProcessMatcher::from_instance(&instance, MatchStrictness::Path);
+        assert!(matcher.matches(&snapshot(10, Some(&ours), &["--home=/data/work"])));
+        assert!(!matcher.matches(&snapshot(11, Some(&ours), &["--home=/data/personal"])));
+        assert!(!matcher.matches(&snapshot(12, Some(&ours), &[])));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_strict_requires_same_user() {
+        let (_dir, ours, _other) = fake_executables();
+        let matcher = ProcessMatcher::new(ours.to_str().unwrap(), MatchStrictness::Strict)
+            .with_user(Some("1000".parse().unwrap()));
+
+        let mut process = snapshot(10, Some(&ours), &[]);
+        process.user_id = Some("1000".parse().unwrap());
+        assert!(matcher.matches(&process));
+        process.user_id = Some("1001".parse().unwrap());
+        assert!(!matcher.matches(&process));
+    }
+
+    #[test]
+    fn test_parent_pid_constraint() {
+        let (_dir, ours, _other) = fake_executables();
+        let matcher = ProcessMatcher::new(ours.to_str().unwrap(), MatchStrictness::Path);
+
+        assert!(
+            matcher
+                .clone()
+                .with_parent(1)
+                .matches(&snapshot(10, Some(&ours), &[]))
+        );
+        assert!(
+            !matcher
+                .with_parent(2)
+                .matches(&snapshot(10, Some(&ours), &[]))
+        );
+    }
+
+    #[test]
+    fn test_unresolved_path_matches_nothing() {
+        let (dir, ours, _other) = fake_executables();
+        let missing = dir.path().join("missing").join("syncthing");
+        let matcher = ProcessMatcher::new(missing.to_str().unwrap(), MatchStrictness::Path);
+
+        assert!(!matcher.matches(&snapshot(10, Some(&ours), &[])));
+        assert!(!matcher.matches(&snapshot(11, Some(&missing), &[])));
+        assert!(!matcher.matches(&snapshot(12, None, &[])));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bare_name_is_resolved_through_path() {
+        let sh = resolve_executable("sh").expect("sh should be on PATH");
+        assert!(sh.is_absolute());
+
+        let matcher = ProcessMatcher::new("sh", MatchStrictness::Path);
+        let mut process = snapshot(10, Some(&sh), &[]);
+        process.name = "sh".to_string();
+        assert!(matcher.matches(&process));
+    }
+
+    #[test]
+    fn test_replaced_binary_still_matches() {
+        let (_dir, ours, _other) = fake_executables();
+        let matcher = ProcessMatcher::new(ours.to_str().unwrap(), MatchStrictness::Path);
+        let deleted = PathBuf::from(format!("{} (deleted)", ours.display()));
+        assert!(matcher.matches(&snapshot(10, Some(&deleted), &[])));
+    }
+}
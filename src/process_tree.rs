@@ -0,0 +1,99 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::process_match::ProcessSnapshot;
+
+/// Returns `root` and all of its descendants in `processes`, root first.
+///
+/// Children are found through their parent PID. A process that started before its
+/// supposed parent cannot be its child; such entries are skipped, so a reused PID does
+/// not pull an unrelated process into the tree. Returns an empty list if `root` is not
+/// in the table.
+pub fn descendants(processes: &[ProcessSnapshot], root: u32) -> Vec<u32> {
+    let by_pid: HashMap<u32, &ProcessSnapshot> = processes.iter().map(|p| (p.pid, p)).collect();
+    if !by_pid.contains_key(&root) {
+        return Vec::new();
+    }
+
+    let mut children: HashMap<u32, Vec<&ProcessSnapshot>> = HashMap::new();
+    for process in processes {
+        if let Some(parent) = process.parent
+            && parent != process.pid
+        {
+            children.entry(parent).or_default().push(process);
+        }
+    }
+
+    let mut tree = Vec::new();
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::from([root]);
+    while let Some(pid) = queue.pop_front() {
+        if !seen.insert(pid) {
+            continue;
+        }
+        tree.push(pid);
+
+        let parent_start = by_pid[&pid].start_time;
+        let mut kids: Vec<&ProcessSnapshot> = children
+            .get(&pid)
+            .map(|kids| {
+                kids.iter()
+                    .copied()
+                    .filter(|child| child.start_time >= parent_start)
+                    .collect()
+            })
+            .unwrap_or_default();
+        kids.sort_by_key(|child| child.pid);
+        queue.extend(kids.iter().map(|child| child.pid));
+    }
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, parent: Option<u32>, start_time: u64) -> ProcessSnapshot {
+        ProcessSnapshot {
+            pid,
+            parent,
+            name: "syncthing".to_string(),
+            exe: None,
+            cmd: Vec::new(),
+            user_id: None,
+            start_time,
+        }
+    }
+
+    #[test]
+    fn test_walks_nested_children_only() {
+        let table = vec![
+            process(1, None, 0),
+            process(100, Some(1), 10), // our monitor process
+            process(101, Some(100), 11),
+            process(102, Some(101), 12),
+            process(200, Some(1), 5), // unrelated Syncthing instance
+            process(201, Some(200), 6),
+        ];
+
+        assert_eq!(descendants(&table, 100), vec![100, 101, 102]);
+    }
+
+    #[test]
+    fn test_missing_root_yields_empty_tree() {
+        let table = vec![process(1, None, 0), process(2, Some(1), 1)];
+        assert!(descendants(&table, 42).is_empty());
+    }
+
+    #[test]
+    fn test_reused_pid_is_not_a_child() {
+        // PID 300 claims 100 as parent but started before it, so 100 is a reused PID
+        let table = vec![process(100, Some(1), 50), process(300, Some(100), 20)];
+        assert_eq!(descendants(&table, 100), vec![100]);
+    }
+
+    #[test]
+    fn test_parent_cycle_terminates() {
+        let table = vec![process(100, Some(101), 10), process(101, Some(100), 10)];
+        assert_eq!(descendants(&table, 100), vec![100, 101]);
+    }
+}
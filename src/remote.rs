@@ -0,0 +1,226 @@
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::app_state::AppState;
+use crate::config::RemoteConfig;
+use crate::syncthing_api::SyncthingClient;
+use crate::utils;
+
+/// Health and sync status of a remote Syncthing, ordered from best to worst.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteHealth {
+    /// Reachable and all folders are up to date with the connected devices
+    InSync,
+    /// Reachable, but there is still data to sync
+    OutOfSync {
+        /// Aggregated completion over all folders and devices, in percent
+        completion: f64,
+    },
+    /// The REST API could not be reached or rejected the request
+    Down(String),
+}
+
+impl RemoteHealth {
+    /// Higher values are worse, used to find the worst status of several remotes.
+    fn severity(&self) -> u8 {
+        match self {
+            RemoteHealth::InSync => 0,
+            RemoteHealth::OutOfSync { .. } => 1,
+            RemoteHealth::Down(_) => 2,
+        }
+    }
+}
+
+impl fmt::Display for RemoteHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RemoteHealth::InSync => write!(f, "in sync"),
+            RemoteHealth::OutOfSync { completion } => {
+                write!(f, "syncing ({}%)", utils::completion_percent(*completion))
+            }
+            RemoteHealth::Down(_) => write!(f, "down"),
+        }
+    }
+}
+
+/// Result of polling one remote.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteStatus {
+    pub name: String,
+    pub health: RemoteHealth,
+    /// Version reported by the remote, if reachable
+    pub version: Option<String>,
+}
+
+/// Queries the health and sync status of a remote over its REST API.
+pub fn poll(remote: &RemoteConfig) -> RemoteStatus {
+    let client = SyncthingClient::new(&remote.url, &remote.api_key);
+    let (health, version) = match client.system_version() {
+        Ok(version) => {
+            let health = match client.db_completion(None, None) {
+                Ok(completion) if completion.need_bytes == 0 && completion.completion >= 100.0 => {
+                    RemoteHealth::InSync
+                }
+                Ok(completion) => RemoteHealth::OutOfSync {
+                    completion: completion.completion,
+                },
+                Err(e) => RemoteHealth::Down(e.to_string()),
+            };
+            (health, Some(version.version))
+        }
+        Err(e) => (RemoteHealth::Down(e.to_string()), None),
+    };
+
+    RemoteStatus {
+        name: remote.name.clone(),
+        health,
+        version,
+    }
+}
+
+/// Returns the worst health of the given remotes, or `None` if there are none.
+pub fn worst_health(statuses: &[RemoteStatus]) -> Option<&RemoteHealth> {
+    statuses
+        .iter()
+        .map(|status| &status.health)
+        .max_by_key(|health| health.severity())
+}
+
+/// Spawns a background thread that polls the configured remotes every
+/// `remote_poll_interval_secs`.
+///
+/// Health changes are logged, and `on_update` receives the statuses of all remotes
+/// after each round of polls.
+pub fn spawn<F>(app_state: Arc<Mutex<AppState>>, mut on_update: F)
+where
+    F: FnMut(Vec<RemoteStatus>) + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut last: Vec<RemoteStatus> = Vec::new();
+
+        loop {
+            // Settings are re-read on every round so configuration reloads apply
+            let Ok((remotes, interval)) = app_state.lock().map(|state| {
+                (
+                    state.config.remotes.clone(),
+                    state.config.remote_poll_interval_secs,
+                )
+            }) else {
+                log::error!("Failed to lock app state for remote monitoring");
+                return;
+            };
+
+            // The state lock is released before the requests
+            let statuses: Vec<RemoteStatus> = remotes.iter().map(poll).collect();
+            for status in &statuses {
+                let previous = last.iter().find(|p| p.name == status.name);
+                log_health_change(previous.map(|p| &p.health), status);
+            }
+
+            if statuses != last {
+                on_update(statuses.clone());
+                last = statuses;
+            }
+
+            thread::sleep(Duration::from_secs(interval.max(1)));
+        }
+    });
+}
+
+/// Logs a remote's health when it differs from the previous poll.
+fn log_health_change(previous: Option<&RemoteHealth>, status: &RemoteStatus) {
+    if previous.map(RemoteHealth::severity) == Some(status.health.severity()) {
+        return;
+    }
+    match &status.health {
+        RemoteHealth::Down(reason) => log::warn!("Remote {} is down: {}", status.name, reason),
+        health => log::info!(
+            "Remote {} ({}) is {}",
+            status.name,
+            status.version.as_deref().unwrap_or("unknown version"),
+            health
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syncthing_api::mock::MockServer;
+
+    const VERSION: &str = r#"{"version":"v1.27.0"}"#;
+
+    fn remote(server: &MockServer) -> RemoteConfig {
+        RemoteConfig {
+            name: "nas".to_string(),
+            url: server.base_url.clone(),
+            api_key: "key".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_poll_in_sync_and_out_of_sync() {
+        let server = MockServer::start(vec![
+            ("/rest/system/version", 200, VERSION),
+            (
+                "/rest/db/completion",
+                200,
+                r#"{"completion":100,"needBytes":0}"#,
+            ),
+        ]);
+        let status = poll(&remote(&server));
+        assert_eq!(status.health, RemoteHealth::InSync);
+        assert_eq!(status.version.as_deref(), Some("v1.27.0"));
+        assert_eq!(server.recorded()[0].api_key.as_deref(), Some("key"));
+
+        let server = MockServer::start(vec![
+            ("/rest/system/version", 200, VERSION),
+            (
+                "/rest/db/completion",
+                200,
+                r#"{"completion":87.5,"needBytes":1024}"#,
+            ),
+        ]);
+        let status = poll(&remote(&server));
+        assert_eq!(status.health, RemoteHealth::OutOfSync { completion: 87.5 });
+        assert_eq!(status.health.to_string(), "syncing (87%)");
+    }
+
+    #[test]
+    fn test_poll_down_when_api_rejects_key() {
+        let server = MockServer::start(vec![("/rest/system/version", 403, "Forbidden")]);
+        let status = poll(&remote(&server));
+        assert!(matches!(status.health, RemoteHealth::Down(_)));
+        assert_eq!(status.version, None);
+    }
+
+    #[test]
+    fn test_worst_health() {
+        let status = |name: &str, health| RemoteStatus {
+            name: name.to_string(),
+            health,
+            version: None,
+        };
+        assert_eq!(worst_health(&[]), None);
+
+        let statuses = vec![
+            status("nas", RemoteHealth::InSync),
+            status("server", RemoteHealth::OutOfSync { completion: 50.0 }),
+        ];
+        assert_eq!(
+            worst_health(&statuses),
+            Some(&RemoteHealth::OutOfSync { completion: 50.0 })
+        );
+
+        let statuses = vec![
+            status("nas", RemoteHealth::Down("timeout".to_string())),
+            status("server", RemoteHealth::OutOfSync { completion: 50.0 }),
+        ];
+        assert!(matches!(
+            worst_health(&statuses),
+            Some(RemoteHealth::Down(_))
+        ));
+    }
+}
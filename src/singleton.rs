@@ -13,7 +13,7 @@ pub mod platform {
     }
 
     impl SingletonGuard {
-        pub fn acquire() -> Option<Self> {
+        pub fn acquire(_app_dirs: &crate::app_dirs::AppDirs) -> Option<Self> {
             let mutex_name: Vec<u16> = "Global\\SyncthingersSingletonMutex"
                 .encode_utf16()
                 .chain(std::iter::once(0))
@@ -28,9 +28,6 @@ pub mod platform {
                 }
 
                 if handle.is_null() || GetLastError() == ERROR_ALREADY_EXISTS {
-                    let msg = "Another instance of Syncthingers is already running. Exiting.\0";
-                    let caption = "Syncthingers Singleton\0";
-                    crate::error_handling::show_native_error_dialog(msg, caption);
                     if !handle.is_null() {
                         CloseHandle(handle);
                     }
@@ -44,13 +41,70 @@ pub mod platform {
 
 #[cfg(not(target_os = "windows"))]
 pub mod platform {
+    use std::fs::{File, TryLockError};
+
+    use crate::app_dirs::AppDirs;
+
+    /// Holds an advisory lock on `syncthingers.lock` in the app directory.
+    ///
+    /// The lock is released by the OS when the process exits, so a crashed
+    /// instance never blocks the next launch.
     pub struct SingletonGuard {
-        handle: (),
+        _lock_file: File,
     }
+
     impl SingletonGuard {
-        pub fn acquire() -> Option<Self> {
-            // TODO: Implement file lock or other mechanism for non-Windows platforms
-            Some(SingletonGuard { handle: () })
+        pub fn acquire(app_dirs: &AppDirs) -> Option<Self> {
+            let lock_path = app_dirs.lock_file_path();
+            let lock_file = match File::create(&lock_path) {
+                Ok(file) => file,
+                Err(e) => {
+                    log::error!(
+                        "Failed to create lock file {} for singleton enforcement: {}",
+                        lock_path.display(),
+                        e
+                    );
+                    return None;
+                }
+            };
+
+            match lock_file.try_lock() {
+                Ok(()) => {
+                    log::debug!("Acquired singleton lock: {}", lock_path.display());
+                    Some(SingletonGuard {
+                        _lock_file: lock_file,
+                    })
+                }
+                Err(TryLockError::WouldBlock) => None,
+                Err(TryLockError::Error(e)) => {
+                    log::error!("Failed to lock {}: {}", lock_path.display(), e);
+                    None
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use tempfile::TempDir;
+
+        fn temp_app_dirs() -> (TempDir, AppDirs) {
+            let dir = TempDir::new().unwrap();
+            let app_dirs = AppDirs::new(Some(dir.path().to_path_buf())).unwrap();
+            (dir, app_dirs)
+        }
+
+        #[test]
+        fn test_second_acquire_fails_while_held() {
+            let (_dir, app_dirs) = temp_app_dirs();
+
+            let guard = SingletonGuard::acquire(&app_dirs);
+            assert!(guard.is_some());
+            assert!(SingletonGuard::acquire(&app_dirs).is_none());
+
+            drop(guard);
+            assert!(SingletonGuard::acquire(&app_dirs).is_some());
         }
     }
 }
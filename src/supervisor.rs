@@ -0,0 +1,318 @@
+use std::process::ExitStatus;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::config::{RestartPolicy, SupervisionConfig};
+
+/// Maximum number of restart records kept in memory.
+const MAX_HISTORY: usize = 50;
+
+/// How a managed Syncthing process ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExitInfo {
+    /// Exit code, if the process exited normally (not killed by a signal).
+    pub code: Option<i32>,
+    pub success: bool,
+}
+
+impl ExitInfo {
+    /// Exit information for a process whose status could not be determined.
+    pub fn unknown() -> Self {
+        Self {
+            code: None,
+            success: false,
+        }
+    }
+}
+
+impl From<ExitStatus> for ExitInfo {
+    fn from(status: ExitStatus) -> Self {
+        Self {
+            code: status.code(),
+            success: status.success(),
+        }
+    }
+}
+
+/// A single automatic restart performed by the supervisor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestartRecord {
+    pub at: SystemTime,
+    pub exit: ExitInfo,
+    pub attempt: u32,
+    pub delay: Duration,
+}
+
+/// What the supervisor decided to do about an exited process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SupervisorDecision {
+    /// The policy does not restart this kind of exit.
+    Ignore,
+    /// The process will be restarted after `delay`.
+    Restart { attempt: u32, delay: Duration },
+    /// Too many restarts within the reset window; automatic restarts stopped.
+    GiveUp { attempts: u32 },
+}
+
+/// Restart supervisor for the managed Syncthing process.
+///
+/// Implements the configured restart policy with exponential backoff. The attempt
+/// counter is reset once the process has stayed up for the reset window, so only
+/// rapid crash loops (such as a corrupted index database) exhaust the retries.
+pub struct Supervisor {
+    config: SupervisionConfig,
+    attempts: u32,
+    last_restart: Option<Instant>,
+    pending_restart: Option<Instant>,
+    gave_up: bool,
+    history: Vec<RestartRecord>,
+}
+
+impl Supervisor {
+    pub fn new(config: SupervisionConfig) -> Self {
+        Self {
+            config,
+            attempts: 0,
+            last_restart: None,
+            pending_restart: None,
+            gave_up: false,
+            history: Vec::new(),
+        }
+    }
+
+    /// Applies a reloaded supervision configuration.
+    pub fn set_config(&mut self, config: SupervisionConfig) {
+        self.config = config;
+    }
+
+    /// Clears the attempt counter and any pending restart.
+    ///
+    /// Called when the user starts or stops Syncthing manually.
+    pub fn reset(&mut self) {
+        self.attempts = 0;
+        self.last_restart = None;
+        self.pending_restart = None;
+        self.gave_up = false;
+    }
+
+    /// Decides what to do after the managed process exited.
+    pub fn on_exit(&mut self, exit: ExitInfo, now: Instant) -> SupervisorDecision {
+        let wants_restart = match self.config.policy {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure => !exit.success,
+            RestartPolicy::Always => true,
+        };
+        if !wants_restart || self.gave_up {
+            return SupervisorDecision::Ignore;
+        }
+
+        // A process that stayed up for the whole reset window starts a fresh series
+        if let Some(last) = self.last_restart
+            && now.duration_since(last) >= Duration::from_secs(self.config.reset_window_secs)
+        {
+            self.attempts = 0;
+        }
+
+        if self.attempts >= self.config.max_retries {
+            self.gave_up = true;
+            self.pending_restart = None;
+            return SupervisorDecision::GiveUp {
+                attempts: self.attempts,
+            };
+        }
+
+        self.attempts += 1;
+        let delay = self.backoff(self.attempts);
+        self.pending_restart = Some(now + delay);
+
+        if self.history.len() >= MAX_HISTORY {
+            self.history.remove(0);
+        }
+        self.history.push(RestartRecord {
+            at: SystemTime::now(),
+            exit,
+            attempt: self.attempts,
+            delay,
+        });
+
+        SupervisorDecision::Restart {
+            attempt: self.attempts,
+            delay,
+        }
+    }
+
+    /// Returns true (once) when a scheduled restart is due.
+    pub fn restart_due(&mut self, now: Instant) -> bool {
+        match self.pending_restart {
+            Some(at) if now >= at => {
+                self.pending_restart = None;
+                self.last_restart = Some(now);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Maximum number of restarts configured.
+    pub fn max_retries(&self) -> u32 {
+        self.config.max_retries
+    }
+
+    /// Restarts performed so far, oldest first.
+    pub fn history(&self) -> &[RestartRecord] {
+        &self.history
+    }
+
+    /// Backoff before the given (1-based) attempt: doubles each time, capped at the maximum.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let initial = self.config.initial_backoff_secs.max(1);
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        Duration::from_secs(
+            initial
+                .saturating_mul(factor)
+                .min(self.config.max_backoff_secs.max(initial)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(policy: RestartPolicy) -> SupervisionConfig {
+        SupervisionConfig {
+            policy,
+            max_retries: 3,
+            initial_backoff_secs: 2,
+            max_backoff_secs: 5,
+            reset_window_secs: 60,
+        }
+    }
+
+    fn crash() -> ExitInfo {
+        ExitInfo {
+            code: Some(1),
+            success: false,
+        }
+    }
+
+    fn clean_exit() -> ExitInfo {
+        ExitInfo {
+            code: Some(0),
+            success: true,
+        }
+    }
+
+    #[test]
+    fn test_never_policy_ignores_crash() {
+        let mut supervisor = Supervisor::new(config(RestartPolicy::Never));
+        assert_eq!(
+            supervisor.on_exit(crash(), Instant::now()),
+            SupervisorDecision::Ignore
+        );
+    }
+
+    #[test]
+    fn test_on_failure_ignores_clean_exit() {
+        let mut supervisor = Supervisor::new(config(RestartPolicy::OnFailure));
+        assert_eq!(
+            supervisor.on_exit(clean_exit(), Instant::now()),
+            SupervisorDecision::Ignore
+        );
+        assert!(matches!(
+            supervisor.on_exit(crash(), Instant::now()),
+            SupervisorDecision::Restart { attempt: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_always_policy_restarts_clean_exit() {
+        let mut supervisor = Supervisor::new(config(RestartPolicy::Always));
+        assert!(matches!(
+            supervisor.on_exit(clean_exit(), Instant::now()),
+            SupervisorDecision::Restart { .. }
+        ));
+    }
+
+    #[test]
+    fn test_exponential_backoff_is_capped() {
+        let mut supervisor = Supervisor::new(config(RestartPolicy::OnFailure));
+        let now = Instant::now();
+
+        let delays: Vec<Duration> = (0..3)
+            .map(|_| match supervisor.on_exit(crash(), now) {
+                SupervisorDecision::Restart { delay, .. } => {
+                    assert!(supervisor.restart_due(now + delay));
+                    delay
+                }
+                other => panic!("Expected restart, got {:?}", other),
+            })
+            .collect();
+
+        assert_eq!(
+            delays,
+            vec![
+                Duration::from_secs(2),
+                Duration::from_secs(4),
+                Duration::from_secs(5)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_crash_loop_gives_up() {
+        let mut supervisor = Supervisor::new(config(RestartPolicy::OnFailure));
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(matches!(
+                supervisor.on_exit(crash(), now),
+                SupervisorDecision::Restart { .. }
+            ));
+            assert!(supervisor.restart_due(now + Duration::from_secs(10)));
+        }
+        assert_eq!(
+            supervisor.on_exit(crash(), now + Duration::from_secs(11)),
+            SupervisorDecision::GiveUp { attempts: 3 }
+        );
+        // Further exits are ignored until the supervisor is reset
+        assert_eq!(
+            supervisor.on_exit(crash(), now + Duration::from_secs(12)),
+            SupervisorDecision::Ignore
+        );
+        assert_eq!(supervisor.history().len(), 3);
+
+        supervisor.reset();
+        assert!(matches!(
+            supervisor.on_exit(crash(), now + Duration::from_secs(13)),
+            SupervisorDecision::Restart { attempt: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_stable_run_resets_attempts() {
+        let mut supervisor = Supervisor::new(config(RestartPolicy::OnFailure));
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            supervisor.on_exit(crash(), now);
+            assert!(supervisor.restart_due(now + Duration::from_secs(10)));
+        }
+
+        // Crash after running longer than the reset window
+        assert!(matches!(
+            supervisor.on_exit(crash(), now + Duration::from_secs(100)),
+            SupervisorDecision::Restart { attempt: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_restart_not_due_before_delay() {
+        let mut supervisor = Supervisor::new(config(RestartPolicy::OnFailure));
+        let now = Instant::now();
+
+        supervisor.on_exit(crash(), now);
+        assert!(!supervisor.restart_due(now + Duration::from_secs(1)));
+        assert!(supervisor.restart_due(now + Duration::from_secs(2)));
+        assert!(!supervisor.restart_due(now + Duration::from_secs(3)));
+    }
+}
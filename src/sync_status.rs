@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use crate::syncthing_api::{ApiError, SyncthingClient};
+use crate::tray_ui::TrayState;
+use crate::utils;
+
+/// How long after launch an unreachable REST API is reported as `Starting`
+/// rather than `ApiUnreachable`.
+const STARTUP_GRACE_PERIOD: Duration = Duration::from_secs(60);
+
+/// The parts of Syncthing's REST data the tray state is derived from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncSnapshot {
+    /// Remote devices known to Syncthing
+    pub devices: usize,
+    pub connected_devices: usize,
+    pub paused_devices: usize,
+    /// Aggregated completion over all folders and devices, in percent
+    pub completion: f64,
+    pub need_bytes: u64,
+    /// Messages from `/rest/system/error`
+    pub errors: Vec<String>,
+}
+
+impl SyncSnapshot {
+    /// Whether all remote devices are paused, so nothing is synced.
+    pub fn all_paused(&self) -> bool {
+        self.devices > 0 && self.paused_devices == self.devices
+    }
+}
+
+/// Queries the REST data needed to derive the tray state.
+pub fn query(client: &SyncthingClient) -> Result<SyncSnapshot, ApiError> {
+    let connections = client.system_connections()?;
+    let completion = client.db_completion(None, None)?;
+    let errors = client.system_errors()?;
+
+    let devices = connections.connections.values();
+    Ok(SyncSnapshot {
+        devices: devices.len(),
+        connected_devices: devices.clone().filter(|device| device.connected).count(),
+        paused_devices: devices.filter(|device| device.paused).count(),
+        completion: completion.completion,
+        need_bytes: completion.need_bytes,
+        errors: errors.into_iter().map(|error| error.message).collect(),
+    })
+}
+
+/// Derives the state of a running Syncthing from its REST data.
+///
+/// `uptime` is the time since this app started the process, `None` for external
+/// processes, which are expected to answer right away.
+pub fn derive_state(api: &Result<SyncSnapshot, ApiError>, uptime: Option<Duration>) -> TrayState {
+    let snapshot = match api {
+        Ok(snapshot) => snapshot,
+        // The GUI answers, but without a valid API key there are no details
+        Err(ApiError::Status {
+            status: 401 | 403, ..
+        }) => return TrayState::Running,
+        Err(ApiError::Transport { .. })
+            if uptime.is_some_and(|uptime| uptime < STARTUP_GRACE_PERIOD) =>
+        {
+            return TrayState::Starting;
+        }
+        Err(_) => return TrayState::ApiUnreachable,
+    };
+
+    if !snapshot.errors.is_empty() {
+        TrayState::Error
+    } else if snapshot.all_paused() {
+        TrayState::Paused
+    } else if snapshot.devices > 0 && snapshot.connected_devices == 0 {
+        TrayState::NoPeersConnected
+    } else if snapshot.need_bytes > 0 || snapshot.completion < 100.0 {
+        TrayState::Syncing(utils::completion_percent(snapshot.completion).min(99))
+    } else {
+        TrayState::Idle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syncthing_api::mock::MockServer;
+
+    fn snapshot() -> SyncSnapshot {
+        SyncSnapshot {
+            devices: 2,
+            connected_devices: 2,
+            paused_devices: 0,
+            completion: 100.0,
+            need_bytes: 0,
+            errors: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_derive_state_from_snapshot() {
+        assert_eq!(derive_state(&Ok(snapshot()), None), TrayState::Idle);
+
+        let syncing = SyncSnapshot {
+            completion: 45.7,
+            need_bytes: 1024,
+            ..snapshot()
+        };
+        assert_eq!(derive_state(&Ok(syncing), None), TrayState::Syncing(45));
+
+        let disconnected = SyncSnapshot {
+            connected_devices: 0,
+            ..snapshot()
+        };
+        assert_eq!(
+            derive_state(&Ok(disconnected), None),
+            TrayState::NoPeersConnected
+        );
+
+        let paused = SyncSnapshot {
+            connected_devices: 0,
+            paused_devices: 2,
+            ..snapshot()
+        };
+        assert_eq!(derive_state(&Ok(paused), None), TrayState::Paused);
+
+        let failing = SyncSnapshot {
+            errors: vec!["disk full".to_string()],
+            ..snapshot()
+        };
+        assert_eq!(derive_state(&Ok(failing), None), TrayState::Error);
+    }
+
+    #[test]
+    fn test_derive_state_from_api_errors() {
+        let unreachable = || {
+            Err(ApiError::Transport {
+                url: "http://127.0.0.1:8384".to_string(),
+                message: "connection refused".to_string(),
+            })
+        };
+        assert_eq!(
+            derive_state(&unreachable(), Some(Duration::from_secs(5))),
+            TrayState::Starting
+        );
+        assert_eq!(
+            derive_state(&unreachable(), Some(Duration::from_secs(600))),
+            TrayState::ApiUnreachable
+        );
+        assert_eq!(
+            derive_state(&unreachable(), None),
+            TrayState::ApiUnreachable
+        );
+
+        let forbidden = Err(ApiError::Status {
+            status: 403,
+            url: "http://127.0.0.1:8384".to_string(),
+        });
+        assert_eq!(derive_state(&forbidden, None), TrayState::Running);
+    }
+
+    #[test]
+    fn test_query_combines_endpoints() {
+        let server = MockServer::start(vec![
+            (
+                "/rest/system/connections",
+                200,
+                r#"{
+                    "connections": {
+                        "DEV-1": {"connected": true},
+                        "DEV-2": {"connected": false, "paused": true}
+                    },
+                    "total": {}
+                }"#,
+            ),
+            (
+                "/rest/db/completion",
+                200,
+                r#"{"completion":80,"needBytes":200}"#,
+            ),
+            ("/rest/system/error", 200, r#"{"errors":null}"#),
+        ]);
+        let client = SyncthingClient::new(&server.base_url, "key");
+
+        let snapshot = query(&client).unwrap();
+        assert_eq!(snapshot.devices, 2);
+        assert_eq!(snapshot.connected_devices, 1);
+        assert_eq!(snapshot.paused_devices, 1);
+        assert_eq!(derive_state(&Ok(snapshot), None), TrayState::Syncing(80));
+    }
+}
@@ -0,0 +1,690 @@
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::time::Duration;
+use thiserror::Error;
+use url::Url;
+
+use crate::config::InstanceConfig;
+use crate::syncthing_config;
+
+/// Header used by Syncthing for API key authentication.
+const API_KEY_HEADER: &str = "X-API-Key";
+
+/// Timeout applied to every REST request so a hung daemon cannot block the caller.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Errors returned by the Syncthing REST API client.
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("Syncthing API returned HTTP {status} for {url}")]
+    Status { status: u16, url: String },
+    #[error("Syncthing API request to {url} failed: {message}")]
+    Transport { url: String, message: String },
+    #[error("Failed to decode Syncthing API response from {url}: {message}")]
+    Decode { url: String, message: String },
+}
+
+/// Response of `/rest/system/status`.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemStatus {
+    #[serde(rename = "myID")]
+    pub my_id: String,
+    pub uptime: u64,
+    #[serde(default)]
+    pub start_time: String,
+    #[serde(default)]
+    pub goroutines: u64,
+    #[serde(default)]
+    pub alloc: u64,
+    #[serde(default)]
+    pub gui_address_used: String,
+}
+
+/// Response of `/rest/system/version`.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemVersion {
+    pub version: String,
+    #[serde(default)]
+    pub long_version: String,
+    #[serde(default)]
+    pub os: String,
+    #[serde(default)]
+    pub arch: String,
+}
+
+/// Per-device (or total) connection statistics from `/rest/system/connections`.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionStats {
+    #[serde(default)]
+    pub connected: bool,
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default)]
+    pub address: String,
+    #[serde(default)]
+    pub client_version: String,
+    #[serde(default)]
+    pub in_bytes_total: u64,
+    #[serde(default)]
+    pub out_bytes_total: u64,
+}
+
+/// Response of `/rest/system/connections`.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct Connections {
+    /// Connection statistics keyed by device ID.
+    pub connections: HashMap<String, ConnectionStats>,
+    /// Aggregated statistics over all connections.
+    pub total: ConnectionStats,
+}
+
+/// Response of `/rest/db/completion`.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Completion {
+    pub completion: f64,
+    #[serde(default)]
+    pub global_bytes: u64,
+    #[serde(default)]
+    pub need_bytes: u64,
+    #[serde(default)]
+    pub need_items: u64,
+    #[serde(default)]
+    pub need_deletes: u64,
+}
+
+/// A folder from `/rest/config/folders`.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderConfig {
+    pub id: String,
+    /// Display name; empty if the folder has no label
+    #[serde(default)]
+    pub label: String,
+    pub path: String,
+    #[serde(default)]
+    pub paused: bool,
+    /// Devices the folder is shared with, including this device itself
+    #[serde(default)]
+    pub devices: Vec<FolderDevice>,
+}
+
+/// A device entry of a folder in `/rest/config/folders`.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct FolderDevice {
+    #[serde(rename = "deviceID")]
+    pub device_id: String,
+}
+
+impl FolderConfig {
+    /// The label, or the ID for folders without one.
+    pub fn display_name(&self) -> &str {
+        if self.label.is_empty() {
+            &self.id
+        } else {
+            &self.label
+        }
+    }
+}
+
+/// Response of `/rest/db/status`.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderStatus {
+    /// e.g. `idle`, `scanning`, `syncing` or `error`
+    pub state: String,
+    #[serde(default)]
+    pub global_bytes: u64,
+    #[serde(default)]
+    pub in_sync_bytes: u64,
+    #[serde(default)]
+    pub need_bytes: u64,
+}
+
+impl FolderStatus {
+    /// Share of the global data that is in sync locally, in percent.
+    pub fn completion(&self) -> f64 {
+        if self.global_bytes == 0 {
+            100.0
+        } else {
+            self.in_sync_bytes as f64 * 100.0 / self.global_bytes as f64
+        }
+    }
+}
+
+/// A device from `/rest/config/devices`, including this device itself.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceConfig {
+    #[serde(rename = "deviceID")]
+    pub device_id: String,
+    /// Display name; empty if the device has no name
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub paused: bool,
+}
+
+impl DeviceConfig {
+    /// The name, or the short device ID the web UI shows for unnamed devices.
+    pub fn display_name(&self) -> &str {
+        if self.name.is_empty() {
+            self.device_id.get(..7).unwrap_or(&self.device_id)
+        } else {
+            &self.name
+        }
+    }
+}
+
+/// An entry of `/rest/events` or `/rest/events/disk`, with its type-specific data
+/// left undecoded.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct RawEvent {
+    pub id: u64,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub time: String,
+    #[serde(default)]
+    pub data: serde_json::Value,
+}
+
+/// An entry of `/rest/system/error`.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct SystemError {
+    #[serde(default)]
+    pub when: String,
+    pub message: String,
+}
+
+/// Response of `/rest/system/error`.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+struct SystemErrors {
+    /// `null` when there are no errors
+    errors: Option<Vec<SystemError>>,
+}
+
+/// Blocking client for Syncthing's REST API.
+///
+/// The client is cheap to clone, so callers should clone it out of `AppState`
+/// before issuing requests instead of holding the state lock during network I/O.
+#[derive(Clone)]
+pub struct SyncthingClient {
+    base_url: String,
+    api_key: String,
+    agent: ureq::Agent,
+}
+
+impl SyncthingClient {
+    /// Creates a client for the given base URL (e.g. `http://localhost:8384`) and API key.
+    pub fn new(base_url: &str, api_key: &str) -> Self {
+        let agent = ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build();
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+            agent,
+        }
+    }
+
+    /// Creates a client using the web UI URL and API key of a configured instance,
+    /// falling back to the values in Syncthing's `config.xml` for unset ones.
+    pub fn from_instance(instance: &InstanceConfig) -> Self {
+        let gui = syncthing_config::resolve(instance);
+        Self::new(&gui.web_ui_url, &gui.api_key)
+    }
+
+    /// Returns the base URL the client talks to.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Fetches `/rest/system/status`.
+    pub fn system_status(&self) -> Result<SystemStatus, ApiError> {
+        self.get("/rest/system/status", &[])
+    }
+
+    /// Fetches `/rest/system/version`.
+    pub fn system_version(&self) -> Result<SystemVersion, ApiError> {
+        self.get("/rest/system/version", &[])
+    }
+
+    /// Fetches `/rest/system/connections`.
+    pub fn system_connections(&self) -> Result<Connections, ApiError> {
+        self.get("/rest/system/connections", &[])
+    }
+
+    /// Fetches the configured folders from `/rest/config/folders`.
+    pub fn config_folders(&self) -> Result<Vec<FolderConfig>, ApiError> {
+        self.get("/rest/config/folders", &[])
+    }
+
+    /// Fetches the configured devices from `/rest/config/devices`.
+    pub fn config_devices(&self) -> Result<Vec<DeviceConfig>, ApiError> {
+        self.get("/rest/config/devices", &[])
+    }
+
+    /// Pauses or resumes a device via `PATCH /rest/config/devices/{id}`.
+    pub fn set_device_paused(&self, device: &str, paused: bool) -> Result<(), ApiError> {
+        self.patch(
+            &["rest", "config", "devices", device],
+            serde_json::json!({ "paused": paused }),
+        )
+    }
+
+    /// Fetches `/rest/db/status` of a folder.
+    pub fn db_status(&self, folder: &str) -> Result<FolderStatus, ApiError> {
+        self.get("/rest/db/status", &[("folder", folder)])
+    }
+
+    /// Pauses or resumes a folder via `PATCH /rest/config/folders/{id}`.
+    pub fn set_folder_paused(&self, folder: &str, paused: bool) -> Result<(), ApiError> {
+        self.patch(
+            &["rest", "config", "folders", folder],
+            serde_json::json!({ "paused": paused }),
+        )
+    }
+
+    /// Asks Syncthing to rescan a folder via `POST /rest/db/scan`.
+    pub fn scan_folder(&self, folder: &str) -> Result<(), ApiError> {
+        self.post("/rest/db/scan", &[("folder", folder)])
+    }
+
+    /// Long-polls an event endpoint such as `/rest/events`.
+    ///
+    /// Syncthing answers as soon as events newer than the `since` query parameter
+    /// exist, or with an empty list after `timeout`.
+    pub fn events(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        timeout: Duration,
+    ) -> Result<Vec<RawEvent>, ApiError> {
+        let timeout_secs = timeout.as_secs().to_string();
+        let mut query = query.to_vec();
+        query.push(("timeout", &timeout_secs));
+        // Leave room for the response on top of the server-side wait
+        self.get_with_timeout(path, &query, timeout + REQUEST_TIMEOUT)
+    }
+
+    /// Fetches the errors listed in `/rest/system/error`, oldest first.
+    pub fn system_errors(&self) -> Result<Vec<SystemError>, ApiError> {
+        self.get::<SystemErrors>("/rest/system/error", &[])
+            .map(|response| response.errors.unwrap_or_default())
+    }
+
+    /// Fetches `/rest/db/completion`, optionally filtered by folder and/or device.
+    ///
+    /// Without filters Syncthing reports the aggregated completion over all
+    /// folders and remote devices.
+    pub fn db_completion(
+        &self,
+        folder: Option<&str>,
+        device: Option<&str>,
+    ) -> Result<Completion, ApiError> {
+        let mut query = Vec::new();
+        if let Some(folder) = folder {
+            query.push(("folder", folder));
+        }
+        if let Some(device) = device {
+            query.push(("device", device));
+        }
+        self.get("/rest/db/completion", &query)
+    }
+
+    /// Pauses all remote devices via `POST /rest/system/pause`.
+    ///
+    /// Syncthing keeps running for discovery and the web UI, but stops transfers.
+    pub fn pause_all(&self) -> Result<(), ApiError> {
+        self.post("/rest/system/pause", &[])
+    }
+
+    /// Resumes all remote devices via `POST /rest/system/resume`.
+    pub fn resume_all(&self) -> Result<(), ApiError> {
+        self.post("/rest/system/resume", &[])
+    }
+
+    /// Asks Syncthing to shut down via `POST /rest/system/shutdown`.
+    pub fn shutdown(&self) -> Result<(), ApiError> {
+        self.post("/rest/system/shutdown", &[])
+    }
+
+    /// Issues an authenticated POST request without a body.
+    fn post(&self, path: &str, query: &[(&str, &str)]) -> Result<(), ApiError> {
+        let url = format!("{}{}", self.base_url, path);
+        let mut request = self.agent.post(&url).set(API_KEY_HEADER, &self.api_key);
+        for (key, value) in query {
+            request = request.query(key, value);
+        }
+
+        request.call().map_err(|e| Self::map_error(&url, e))?;
+        Ok(())
+    }
+
+    /// Issues an authenticated PATCH request with a JSON body. Path segments are
+    /// percent-encoded, since they may contain user-chosen IDs.
+    fn patch(&self, segments: &[&str], body: serde_json::Value) -> Result<(), ApiError> {
+        let mut url = Url::parse(&self.base_url).map_err(|e| ApiError::Transport {
+            url: self.base_url.clone(),
+            message: e.to_string(),
+        })?;
+        if let Ok(mut path) = url.path_segments_mut() {
+            path.pop_if_empty().extend(segments);
+        }
+        let url = url.to_string();
+
+        self.agent
+            .request("PATCH", &url)
+            .set(API_KEY_HEADER, &self.api_key)
+            .send_json(body)
+            .map_err(|e| Self::map_error(&url, e))?;
+        Ok(())
+    }
+
+    /// Issues an authenticated GET request and decodes the JSON body.
+    fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, &str)]) -> Result<T, ApiError> {
+        self.get_with_timeout(path, query, REQUEST_TIMEOUT)
+    }
+
+    /// Like `get`, but with a custom timeout for the whole request.
+    fn get_with_timeout<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        timeout: Duration,
+    ) -> Result<T, ApiError> {
+        let url = format!("{}{}", self.base_url, path);
+        let mut request = self
+            .agent
+            .get(&url)
+            .set(API_KEY_HEADER, &self.api_key)
+            .timeout(timeout);
+        for (key, value) in query {
+            request = request.query(key, value);
+        }
+
+        let response = request.call().map_err(|e| Self::map_error(&url, e))?;
+        response.into_json().map_err(|e| ApiError::Decode {
+            url,
+            message: e.to_string(),
+        })
+    }
+
+    /// Converts a ureq error into an `ApiError`.
+    fn map_error(url: &str, error: ureq::Error) -> ApiError {
+        match error {
+            ureq::Error::Status(status, _) => ApiError::Status {
+                status,
+                url: url.to_string(),
+            },
+            ureq::Error::Transport(transport) => ApiError::Transport {
+                url: url.to_string(),
+                message: transport.to_string(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod mock {
+    //! Minimal local HTTP server for exercising the REST client in tests.
+
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    /// A request recorded by the mock server.
+    #[derive(Debug, Clone)]
+    pub struct RecordedRequest {
+        pub method: String,
+        pub url: String,
+        pub api_key: Option<String>,
+        pub body: String,
+    }
+
+    /// Serves canned JSON responses keyed by request path (without query string).
+    pub struct MockServer {
+        pub base_url: String,
+        pub requests: Arc<Mutex<Vec<RecordedRequest>>>,
+    }
+
+    impl MockServer {
+        /// Starts a server that answers each path in `routes` with `(status, body)`.
+        /// Unknown paths are answered with HTTP 404.
+        pub fn start(routes: Vec<(&str, u16, &str)>) -> Self {
+            let server = tiny_http::Server::http("127.0.0.1:0").expect("bind mock server");
+            let base_url = format!("http://{}", server.server_addr().to_ip().unwrap());
+            let routes: HashMap<String, (u16, String)> = routes
+                .into_iter()
+                .map(|(path, status, body)| (path.to_string(), (status, body.to_string())))
+                .collect();
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let recorded = requests.clone();
+
+            thread::spawn(move || {
+                for mut request in server.incoming_requests() {
+                    let api_key = request
+                        .headers()
+                        .iter()
+                        .find(|h| h.field.equiv("X-API-Key"))
+                        .map(|h| h.value.to_string());
+                    let url = request.url().to_string();
+                    let mut body = String::new();
+                    let _ = request.as_reader().read_to_string(&mut body);
+                    recorded.lock().unwrap().push(RecordedRequest {
+                        method: request.method().to_string(),
+                        url: url.clone(),
+                        api_key,
+                        body,
+                    });
+
+                    let path = url.split('?').next().unwrap_or_default();
+                    let (status, body) = routes
+                        .get(path)
+                        .cloned()
+                        .unwrap_or((404, "not found".to_string()));
+                    let response = tiny_http::Response::from_string(body)
+                        .with_status_code(status)
+                        .with_header(
+                            "Content-Type: application/json"
+                                .parse::<tiny_http::Header>()
+                                .unwrap(),
+                        );
+                    let _ = request.respond(response);
+                }
+            });
+
+            Self { base_url, requests }
+        }
+
+        /// Returns all requests received so far.
+        pub fn recorded(&self) -> Vec<RecordedRequest> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mock::MockServer;
+    use super::*;
+
+    #[test]
+    fn test_system_status_sends_api_key() {
+        let server = MockServer::start(vec![(
+            "/rest/system/status",
+            200,
+            r#"{"myID":"ABC-123","uptime":42,"startTime":"2025-01-01T00:00:00Z","goroutines":10}"#,
+        )]);
+        let client = SyncthingClient::new(&format!("{}/", server.base_url), "secret");
+
+        let status = client.system_status().unwrap();
+        assert_eq!(status.my_id, "ABC-123");
+        assert_eq!(status.uptime, 42);
+
+        let requests = server.recorded();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].api_key.as_deref(), Some("secret"));
+    }
+
+    #[test]
+    fn test_system_version() {
+        let server = MockServer::start(vec![(
+            "/rest/system/version",
+            200,
+            r#"{"version":"v1.27.0","longVersion":"syncthing v1.27.0","os":"linux","arch":"amd64"}"#,
+        )]);
+        let client = SyncthingClient::new(&server.base_url, "key");
+
+        let version = client.system_version().unwrap();
+        assert_eq!(version.version, "v1.27.0");
+        assert_eq!(version.os, "linux");
+    }
+
+    #[test]
+    fn test_system_connections() {
+        let server = MockServer::start(vec![(
+            "/rest/system/connections",
+            200,
+            r#"{
+                "connections": {
+                    "DEV-1": {"connected": true, "address": "10.0.0.2:22000", "inBytesTotal": 100, "outBytesTotal": 200}
+                },
+                "total": {"inBytesTotal": 100, "outBytesTotal": 200}
+            }"#,
+        )]);
+        let client = SyncthingClient::new(&server.base_url, "key");
+
+        let connections = client.system_connections().unwrap();
+        assert!(connections.connections["DEV-1"].connected);
+        assert_eq!(connections.total.out_bytes_total, 200);
+    }
+
+    #[test]
+    fn test_db_completion_passes_filters() {
+        let server = MockServer::start(vec![(
+            "/rest/db/completion",
+            200,
+            r#"{"completion":99.5,"globalBytes":1000,"needBytes":5,"needItems":1,"needDeletes":0}"#,
+        )]);
+        let client = SyncthingClient::new(&server.base_url, "key");
+
+        let completion = client.db_completion(Some("docs"), Some("DEV-1")).unwrap();
+        assert_eq!(completion.completion, 99.5);
+        assert_eq!(completion.need_bytes, 5);
+
+        let requests = server.recorded();
+        assert!(requests[0].url.contains("folder=docs"));
+        assert!(requests[0].url.contains("device=DEV-1"));
+    }
+
+    #[test]
+    fn test_system_errors() {
+        let server = MockServer::start(vec![(
+            "/rest/system/error",
+            200,
+            r#"{"errors":[{"when":"2025-01-01T00:00:00Z","message":"folder docs: disk full"}]}"#,
+        )]);
+        let client = SyncthingClient::new(&server.base_url, "key");
+        let errors = client.system_errors().unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "folder docs: disk full");
+
+        let server = MockServer::start(vec![("/rest/system/error", 200, r#"{"errors":null}"#)]);
+        let client = SyncthingClient::new(&server.base_url, "key");
+        assert!(client.system_errors().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_folder_config_and_status() {
+        let server = MockServer::start(vec![
+            (
+                "/rest/config/folders",
+                200,
+                r#"[{"id":"abcd-1234","label":"","path":"~/Sync","paused":false,"type":"sendreceive"}]"#,
+            ),
+            (
+                "/rest/db/status",
+                200,
+                r#"{"state":"syncing","globalBytes":1000,"inSyncBytes":250,"needBytes":750}"#,
+            ),
+        ]);
+        let client = SyncthingClient::new(&server.base_url, "key");
+
+        let folders = client.config_folders().unwrap();
+        assert_eq!(folders[0].display_name(), "abcd-1234");
+        let status = client.db_status("abcd-1234").unwrap();
+        assert_eq!(status.state, "syncing");
+        assert_eq!(status.completion(), 25.0);
+        assert!(server.recorded()[1].url.contains("folder=abcd-1234"));
+    }
+
+    #[test]
+    fn test_folder_actions() {
+        let server = MockServer::start(vec![
+            ("/rest/config/folders/my%20docs", 200, ""),
+            ("/rest/db/scan", 200, ""),
+        ]);
+        let client = SyncthingClient::new(&server.base_url, "key");
+
+        client.set_folder_paused("my docs", true).unwrap();
+        client.scan_folder("my docs").unwrap();
+
+        let requests = server.recorded();
+        assert_eq!(requests[0].method, "PATCH");
+        assert_eq!(requests[0].body, r#"{"paused":true}"#);
+        assert_eq!(requests[1].method, "POST");
+        assert_eq!(requests[1].url, "/rest/db/scan?folder=my+docs");
+    }
+
+    #[test]
+    fn test_shutdown_posts() {
+        let server = MockServer::start(vec![(
+            "/rest/system/shutdown",
+            200,
+            r#"{"ok":"shutting down"}"#,
+        )]);
+        let client = SyncthingClient::new(&server.base_url, "key");
+
+        client.shutdown().unwrap();
+        let requests = server.recorded();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].url, "/rest/system/shutdown");
+    }
+
+    #[test]
+    fn test_http_error_status() {
+        let server = MockServer::start(vec![("/rest/system/status", 403, "CSRF Error")]);
+        let client = SyncthingClient::new(&server.base_url, "wrong");
+
+        match client.system_status() {
+            Err(ApiError::Status { status, .. }) => assert_eq!(status, 403),
+            other => panic!("Expected status error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_decode_error() {
+        let server = MockServer::start(vec![("/rest/system/version", 200, "not json")]);
+        let client = SyncthingClient::new(&server.base_url, "key");
+
+        assert!(matches!(
+            client.system_version(),
+            Err(ApiError::Decode { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unreachable_server() {
+        // Port 9 (discard) on localhost is not expected to run an HTTP server
+        let client = SyncthingClient::new("http://127.0.0.1:9", "key");
+        assert!(matches!(
+            client.system_status(),
+            Err(ApiError::Transport { .. })
+        ));
+    }
+}
@@ -0,0 +1,378 @@
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use url::Url;
+
+use crate::config::{DEFAULT_WEB_UI_URL, InstanceConfig};
+use crate::syncthing_api::{FolderConfig, FolderDevice};
+use crate::utils::is_test_environment;
+
+/// GUI settings read from Syncthing's own `config.xml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuiConfig {
+    /// Listen address, e.g. `127.0.0.1:8384`, `0.0.0.0:8384` or `unix:///run/st.sock`
+    pub address: String,
+    /// Whether the GUI is served over HTTPS
+    pub tls: bool,
+    pub api_key: Option<String>,
+}
+
+impl GuiConfig {
+    /// URL to reach the GUI from this machine, or `None` for Unix socket addresses.
+    ///
+    /// Wildcard listen addresses are reached through the loopback address.
+    pub fn url(&self) -> Option<String> {
+        if self.address.starts_with("unix") {
+            return None;
+        }
+        let scheme = if self.tls { "https" } else { "http" };
+        let (host, port) = split_host_port(&self.address)?;
+        let host = match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+            Ok(ip) if ip.is_unspecified() => "127.0.0.1",
+            _ => host,
+        };
+        Some(format!("{scheme}://{host}:{port}"))
+    }
+}
+
+/// GUI URL and API key an instance is reached with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedGui {
+    pub web_ui_url: String,
+    pub api_key: String,
+}
+
+/// Parses the `<gui>` element of a Syncthing `config.xml`.
+pub fn parse(xml: &str) -> Result<GuiConfig, String> {
+    let document = roxmltree::Document::parse(xml).map_err(|e| e.to_string())?;
+    let gui = document
+        .root_element()
+        .children()
+        .find(|node| node.has_tag_name("gui"))
+        .ok_or("missing <gui> element")?;
+    let child_text = |name: &str| {
+        gui.children()
+            .find(|node| node.has_tag_name(name))
+            .and_then(|node| node.text())
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+            .map(str::to_string)
+    };
+
+    Ok(GuiConfig {
+        address: child_text("address").ok_or("missing <gui><address> element")?,
+        tls: gui.attribute("tls") == Some("true"),
+        api_key: child_text("apikey"),
+    })
+}
+
+/// Parses the `<folder>` elements of a Syncthing `config.xml`.
+pub fn parse_folders(xml: &str) -> Result<Vec<FolderConfig>, String> {
+    let document = roxmltree::Document::parse(xml).map_err(|e| e.to_string())?;
+    document
+        .root_element()
+        .children()
+        .filter(|node| node.has_tag_name("folder"))
+        .map(|folder| {
+            let attribute = |name: &str| folder.attribute(name).unwrap_or_default().to_string();
+            let path = attribute("path");
+            if path.is_empty() {
+                return Err(format!("folder '{}' has no path", attribute("id")));
+            }
+            Ok(FolderConfig {
+                id: attribute("id"),
+                label: attribute("label"),
+                path,
+                paused: folder
+                    .children()
+                    .find(|node| node.has_tag_name("paused"))
+                    .and_then(|node| node.text())
+                    .is_some_and(|text| text.trim() == "true"),
+                devices: folder
+                    .children()
+                    .filter(|node| node.has_tag_name("device"))
+                    .filter_map(|node| node.attribute("id"))
+                    .map(|id| FolderDevice {
+                        device_id: id.to_string(),
+                    })
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Location of the `config.xml` used by the instance.
+///
+/// Taken from `home` or the `--home`/`--config` startup argument, otherwise the
+/// platform default configuration directory. Default locations are not used for
+/// test executables, so tests do not depend on the developer's own Syncthing.
+pub fn config_xml_path(instance: &InstanceConfig) -> Option<PathBuf> {
+    if let Some(home) = instance.home_dir() {
+        return Some(Path::new(&home).join("config.xml"));
+    }
+    if is_test_environment(&instance.syncthing_path) {
+        return None;
+    }
+    default_config_dirs()
+        .into_iter()
+        .map(|dir| dir.join("config.xml"))
+        .find(|path| path.exists())
+}
+
+/// Candidate default configuration directories, in the order Syncthing uses them.
+fn default_config_dirs() -> Vec<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        dirs::data_local_dir()
+            .map(|dir| dir.join("Syncthing"))
+            .into_iter()
+            .collect()
+    }
+    #[cfg(target_os = "macos")]
+    {
+        dirs::home_dir()
+            .map(|dir| dir.join("Library/Application Support/Syncthing"))
+            .into_iter()
+            .collect()
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        // Syncthing 1.27 moved the default from ~/.config to ~/.local/state
+        [dirs::state_dir(), dirs::config_dir()]
+            .into_iter()
+            .flatten()
+            .map(|dir| dir.join("syncthing"))
+            .collect()
+    }
+}
+
+/// Modification time of the instance's `config.xml`, if it can be found.
+pub fn config_xml_modified(instance: &InstanceConfig) -> Option<SystemTime> {
+    let path = config_xml_path(instance)?;
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Reads the GUI settings of the instance's Syncthing, if its `config.xml` can be found.
+pub fn discover(instance: &InstanceConfig) -> Option<GuiConfig> {
+    let path = config_xml_path(instance)?;
+    let xml = fs::read_to_string(&path)
+        .map_err(|e| log::debug!("Cannot read {}: {}", path.display(), e))
+        .ok()?;
+    parse(&xml)
+        .map_err(|e| log::warn!("Cannot parse {}: {}", path.display(), e))
+        .ok()
+}
+
+/// Reads the folders shared by the instance's Syncthing, if its `config.xml` can be found.
+pub fn discover_folders(instance: &InstanceConfig) -> Option<Vec<FolderConfig>> {
+    let path = config_xml_path(instance)?;
+    let xml = fs::read_to_string(&path)
+        .map_err(|e| log::debug!("Cannot read {}: {}", path.display(), e))
+        .ok()?;
+    parse_folders(&xml)
+        .map_err(|e| log::warn!("Cannot parse folders in {}: {}", path.display(), e))
+        .ok()
+}
+
+/// Fills in an empty `web_ui_url` or `api_key` from Syncthing's `config.xml`.
+pub fn resolve(instance: &InstanceConfig) -> ResolvedGui {
+    let needs_discovery = instance.web_ui_url.is_empty() || instance.api_key.is_empty();
+    let discovered = needs_discovery.then(|| discover(instance)).flatten();
+
+    let web_ui_url = if instance.web_ui_url.is_empty() {
+        discovered
+            .as_ref()
+            .and_then(GuiConfig::url)
+            .unwrap_or_else(|| DEFAULT_WEB_UI_URL.to_string())
+    } else {
+        instance.web_ui_url.clone()
+    };
+    let api_key = if instance.api_key.is_empty() {
+        discovered.and_then(|gui| gui.api_key).unwrap_or_default()
+    } else {
+        instance.api_key.clone()
+    };
+
+    ResolvedGui {
+        web_ui_url,
+        api_key,
+    }
+}
+
+/// Returns the URL Syncthing listens on if it disagrees with the configured `web_ui_url`.
+pub fn address_mismatch(instance: &InstanceConfig) -> Option<String> {
+    if instance.web_ui_url.is_empty() {
+        return None;
+    }
+    let gui = discover(instance)?;
+    let actual = gui.url()?;
+    (!urls_match(&instance.web_ui_url, &gui)).then_some(actual)
+}
+
+/// Checks whether `configured` reaches a GUI listening on `gui.address`.
+///
+/// Loopback names are interchangeable, and a wildcard listen address accepts any host.
+fn urls_match(configured: &str, gui: &GuiConfig) -> bool {
+    let (Ok(configured), Some((host, port))) =
+        (Url::parse(configured), split_host_port(&gui.address))
+    else {
+        return true;
+    };
+    let scheme = if gui.tls { "https" } else { "http" };
+    if configured.scheme() != scheme || configured.port_or_known_default() != port.parse().ok() {
+        return false;
+    }
+
+    let is_loopback = |host: &str| {
+        host.eq_ignore_ascii_case("localhost")
+            || host
+                .trim_matches(['[', ']'])
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback())
+    };
+    let configured_host = configured.host_str().unwrap_or_default();
+    match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(ip) if ip.is_unspecified() => true,
+        _ if is_loopback(host) => is_loopback(configured_host),
+        _ => configured_host
+            .trim_matches(['[', ']'])
+            .eq_ignore_ascii_case(host.trim_matches(['[', ']'])),
+    }
+}
+
+/// Splits `host:port`, keeping brackets around IPv6 hosts.
+fn split_host_port(address: &str) -> Option<(&str, &str)> {
+    let (host, port) = address.rsplit_once(':')?;
+    Some((if host.is_empty() { "0.0.0.0" } else { host }, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const CONFIG_XML: &str = r#"<configuration version="37">
+    <folder id="default" label="Default Folder" path="/data/Sync">
+        <device id="AAAAAAA-BBBBBBB" introducedBy=""></device>
+        <paused>false</paused>
+    </folder>
+    <folder id="abcd-1234" label="" path="~/Photos">
+        <paused>true</paused>
+    </folder>
+    <gui enabled="true" tls="false" debugging="false">
+        <address>0.0.0.0:8385</address>
+        <apikey>abc123</apikey>
+        <theme>default</theme>
+    </gui>
+</configuration>"#;
+
+    fn gui(address: &str, tls: bool) -> GuiConfig {
+        GuiConfig {
+            address: address.to_string(),
+            tls,
+            api_key: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_gui_settings() {
+        let gui = parse(CONFIG_XML).unwrap();
+        assert_eq!(gui.address, "0.0.0.0:8385");
+        assert!(!gui.tls);
+        assert_eq!(gui.api_key.as_deref(), Some("abc123"));
+        assert_eq!(gui.url().as_deref(), Some("http://127.0.0.1:8385"));
+
+        assert!(parse("<configuration/>").is_err());
+        assert!(parse("not xml").is_err());
+    }
+
+    #[test]
+    fn test_parse_folders() {
+        let folders = parse_folders(CONFIG_XML).unwrap();
+        assert_eq!(folders.len(), 2);
+        assert_eq!(folders[0].display_name(), "Default Folder");
+        assert_eq!(folders[0].path, "/data/Sync");
+        assert!(!folders[0].paused);
+        assert_eq!(folders[0].devices[0].device_id, "AAAAAAA-BBBBBBB");
+        assert_eq!(folders[1].display_name(), "abcd-1234");
+        assert!(folders[1].paused);
+
+        assert!(parse_folders("<configuration><folder id=\"x\"/></configuration>").is_err());
+    }
+
+    #[test]
+    fn test_gui_url_forms() {
+        assert_eq!(
+            gui("[::1]:8384", true).url().as_deref(),
+            Some("https://[::1]:8384")
+        );
+        assert_eq!(
+            gui("[::]:8384", false).url().as_deref(),
+            Some("http://127.0.0.1:8384")
+        );
+        assert_eq!(
+            gui(":8384", false).url().as_deref(),
+            Some("http://127.0.0.1:8384")
+        );
+        assert_eq!(gui("unix:///run/syncthing.sock", false).url(), None);
+    }
+
+    #[test]
+    fn test_urls_match() {
+        assert!(urls_match(
+            "http://localhost:8384",
+            &gui("127.0.0.1:8384", false)
+        ));
+        assert!(urls_match(
+            "http://nas.local:8384/",
+            &gui("0.0.0.0:8384", false)
+        ));
+        assert!(!urls_match(
+            "http://localhost:8384",
+            &gui("127.0.0.1:8385", false)
+        ));
+        assert!(!urls_match(
+            "http://localhost:8384",
+            &gui("127.0.0.1:8384", true)
+        ));
+        assert!(!urls_match(
+            "http://nas.local:8384",
+            &gui("192.168.1.2:8384", false)
+        ));
+    }
+
+    #[test]
+    fn test_resolve_fills_in_empty_settings_from_home() {
+        let home = TempDir::new().unwrap();
+        fs::write(home.path().join("config.xml"), CONFIG_XML).unwrap();
+        let instance = InstanceConfig {
+            syncthing_path: "nonexistent_test_syncthing".to_string(),
+            home: Some(home.path().to_string_lossy().to_string()),
+            web_ui_url: String::new(),
+            ..InstanceConfig::default()
+        };
+
+        let resolved = resolve(&instance);
+        assert_eq!(resolved.web_ui_url, "http://127.0.0.1:8385");
+        assert_eq!(resolved.api_key, "abc123");
+        assert_eq!(address_mismatch(&instance), None);
+
+        // Configured values win, but a differing port is reported
+        let instance = InstanceConfig {
+            web_ui_url: "http://localhost:8384".to_string(),
+            api_key: "mine".to_string(),
+            ..instance
+        };
+        let resolved = resolve(&instance);
+        assert_eq!(resolved.web_ui_url, "http://localhost:8384");
+        assert_eq!(resolved.api_key, "mine");
+        assert_eq!(
+            address_mismatch(&instance).as_deref(),
+            Some("http://127.0.0.1:8385")
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::app_state::AppState;
+use crate::config::SpeedUnits;
+
+/// Upload and download rates in bytes per second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransferRate {
+    pub download: f64,
+    pub upload: f64,
+}
+
+impl TransferRate {
+    /// Formats both rates for display, e.g. `↓ 1.2 MiB/s  ↑ 56 KiB/s`.
+    pub fn display(&self, units: SpeedUnits) -> RateDisplay {
+        RateDisplay { rate: *self, units }
+    }
+}
+
+/// A `TransferRate` formatted in the configured units.
+pub struct RateDisplay {
+    rate: TransferRate,
+    units: SpeedUnits,
+}
+
+impl fmt::Display for RateDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "↓ {}  ↑ {}",
+            format_rate(self.rate.download, self.units),
+            format_rate(self.rate.upload, self.units)
+        )
+    }
+}
+
+/// Formats a rate given in bytes per second.
+pub fn format_rate(bytes_per_sec: f64, units: SpeedUnits) -> String {
+    let (value, base, prefixes): (f64, f64, [&str; 4]) = match units {
+        SpeedUnits::Bytes => (bytes_per_sec, 1024.0, ["B/s", "KiB/s", "MiB/s", "GiB/s"]),
+        SpeedUnits::Bits => (
+            bytes_per_sec * 8.0,
+            1000.0,
+            ["bit/s", "kbit/s", "Mbit/s", "Gbit/s"],
+        ),
+    };
+
+    let mut value = value.max(0.0);
+    let mut index = 0;
+    while value >= base && index < prefixes.len() - 1 {
+        value /= base;
+        index += 1;
+    }
+
+    if index == 0 || value >= 100.0 {
+        format!("{:.0} {}", value, prefixes[index])
+    } else {
+        format!("{:.1} {}", value, prefixes[index])
+    }
+}
+
+/// Computes transfer rates from Syncthing's cumulative byte counters.
+#[derive(Debug, Default)]
+pub struct SpeedMeter {
+    last: Option<(Instant, u64, u64)>,
+}
+
+impl SpeedMeter {
+    /// Records new counter totals and returns the rates since the previous sample.
+    ///
+    /// Returns `None` for the first sample and when the counters went backwards,
+    /// which happens when Syncthing restarts.
+    pub fn update(&mut self, at: Instant, in_total: u64, out_total: u64) -> Option<TransferRate> {
+        let previous = self.last.replace((at, in_total, out_total));
+        let (last_at, last_in, last_out) = previous?;
+
+        let elapsed = at.duration_since(last_at).as_secs_f64();
+        if elapsed <= 0.0 || in_total < last_in || out_total < last_out {
+            return None;
+        }
+
+        Some(TransferRate {
+            download: (in_total - last_in) as f64 / elapsed,
+            upload: (out_total - last_out) as f64 / elapsed,
+        })
+    }
+
+    /// Forgets the previous sample.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+/// Spawns a background thread that polls `/rest/system/connections` of every running
+/// instance while transfer speed monitoring is enabled.
+///
+/// `on_update` receives the formatted rates summed over all instances after each
+/// successful poll, and `None` once rates are no longer available.
+pub fn spawn<F>(app_state: Arc<Mutex<AppState>>, mut on_update: F)
+where
+    F: FnMut(Option<String>) + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut meters: HashMap<String, SpeedMeter> = HashMap::new();
+        let mut showing = false;
+
+        loop {
+            // Settings are re-read on every poll so configuration reloads apply
+            let Ok((settings, clients)) = app_state.lock().map(|state| {
+                let clients: Vec<_> = state
+                    .instances
+                    .iter()
+                    .filter(|instance| instance.syncthing_process.is_some())
+                    .map(|instance| (instance.name().to_string(), instance.api_client()))
+                    .collect();
+                (state.config.transfer_speed.clone(), clients)
+            }) else {
+                log::error!("Failed to lock app state for transfer speed monitoring");
+                return;
+            };
+            if !settings.enabled {
+                meters.clear();
+            }
+            meters.retain(|name, _| clients.iter().any(|(running, _)| running == name));
+
+            // `None` keeps the current display, e.g. for the first sample after a reset
+            let mut total: Option<TransferRate> = None;
+            let mut pending = false;
+            if settings.enabled {
+                // The state lock is released before the requests
+                for (name, client) in &clients {
+                    let meter = meters.entry(name.clone()).or_default();
+                    match client.system_connections() {
+                        Ok(connections) => match meter.update(
+                            Instant::now(),
+                            connections.total.in_bytes_total,
+                            connections.total.out_bytes_total,
+                        ) {
+                            Some(rate) => {
+                                let sum = total.get_or_insert(TransferRate {
+                                    download: 0.0,
+                                    upload: 0.0,
+                                });
+                                sum.download += rate.download;
+                                sum.upload += rate.upload;
+                            }
+                            None => pending = true,
+                        },
+                        Err(e) => {
+                            log::debug!("Failed to poll transfer speed of {}: {}", name, e);
+                            meter.reset();
+                        }
+                    }
+                }
+            }
+
+            match total {
+                Some(rate) => {
+                    let text = rate.display(settings.units).to_string();
+                    log::debug!("Transfer speed: {}", text);
+                    showing = true;
+                    on_update(Some(text));
+                }
+                None if showing && !pending => {
+                    showing = false;
+                    on_update(None);
+                }
+                None => {}
+            }
+
+            thread::sleep(Duration::from_secs(settings.poll_interval_secs.max(1)));
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_rate() {
+        assert_eq!(format_rate(0.0, SpeedUnits::Bytes), "0 B/s");
+        assert_eq!(format_rate(512.0, SpeedUnits::Bytes), "512 B/s");
+        assert_eq!(format_rate(1536.0, SpeedUnits::Bytes), "1.5 KiB/s");
+        assert_eq!(
+            format_rate(200.0 * 1024.0 * 1024.0, SpeedUnits::Bytes),
+            "200 MiB/s"
+        );
+        assert_eq!(format_rate(125_000.0, SpeedUnits::Bits), "1.0 Mbit/s");
+        assert_eq!(format_rate(100.0, SpeedUnits::Bits), "800 bit/s");
+    }
+
+    #[test]
+    fn test_speed_meter_computes_rates_from_deltas() {
+        let mut meter = SpeedMeter::default();
+        let start = Instant::now();
+
+        assert_eq!(meter.update(start, 1000, 500), None);
+        let rate = meter
+            .update(start + Duration::from_secs(2), 3000, 1500)
+            .unwrap();
+        assert_eq!(
+            rate,
+            TransferRate {
+                download: 1000.0,
+                upload: 500.0
+            }
+        );
+        assert_eq!(
+            rate.display(SpeedUnits::Bytes).to_string(),
+            "↓ 1000 B/s  ↑ 500 B/s"
+        );
+
+        // Counters restart from zero when Syncthing restarts
+        assert_eq!(meter.update(start + Duration::from_secs(4), 10, 10), None);
+        assert!(
+            meter
+                .update(start + Duration::from_secs(6), 20, 10)
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use std::sync::Arc;
+
+use crate::error_handling::AppError;
+use crate::tray_ui::TrayMenuAction;
+
+/// Title of the tray icon and heading of its tooltip.
+const TITLE: &str = "Syncthingers";
+
+/// Whether submenus are shown as such. `tray-item` has none on Windows, so there the
+/// entries of a submenu are listed below its title in the main menu.
+pub const HAS_SUBMENUS: bool = cfg!(not(target_os = "windows"));
+
+/// Called with the action of a clicked menu item.
+pub type ActionHandler = Arc<dyn Fn(TrayMenuAction) + Send + Sync>;
+
+/// One entry of the tray menu, independent of the platform backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuEntry {
+    /// Disabled status line
+    Label(String),
+    Item(String, TrayMenuAction),
+    Separator,
+    SubMenu(String, Vec<MenuEntry>),
+}
+
+/// System tray icon with its menu and tooltip.
+///
+/// On Linux the StatusNotifierItem is served through ksni, so the menu and tooltip
+/// are changed in place on the running service. On Windows `tray-item` is used; it
+/// cannot remove menu items, so a changed menu layout recreates the tray, while
+/// status lines are updated in place through their menu ids.
+#[cfg(not(target_os = "windows"))]
+pub struct Tray {
+    handle: ksni::Handle<KsniTray>,
+}
+
+#[cfg(not(target_os = "windows"))]
+struct KsniTray {
+    icon: &'static str,
+    tooltip: String,
+    menu: Vec<MenuEntry>,
+    on_action: ActionHandler,
+}
+
+#[cfg(not(target_os = "windows"))]
+impl ksni::Tray for KsniTray {
+    fn id(&self) -> String {
+        TITLE.to_lowercase()
+    }
+
+    fn title(&self) -> String {
+        TITLE.to_string()
+    }
+
+    fn icon_name(&self) -> String {
+        self.icon.to_string()
+    }
+
+    fn tool_tip(&self) -> ksni::ToolTip {
+        ksni::ToolTip {
+            title: TITLE.to_string(),
+            description: self.tooltip.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn menu(&self) -> Vec<ksni::MenuItem<Self>> {
+        ksni_items(&self.menu, &self.on_action)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn ksni_items(entries: &[MenuEntry], on_action: &ActionHandler) -> Vec<ksni::MenuItem<KsniTray>> {
+    use ksni::menu::{StandardItem, SubMenu};
+
+    entries
+        .iter()
+        .map(|entry| match entry {
+            MenuEntry::Label(label) => StandardItem {
+                label: label.clone(),
+                enabled: false,
+                ..Default::default()
+            }
+            .into(),
+            MenuEntry::Item(label, action) => {
+                let on_action = on_action.clone();
+                let action = action.clone();
+                StandardItem {
+                    label: label.clone(),
+                    // Actions can wait for Syncthing to stop; the tray service must keep
+                    // answering meanwhile
+                    activate: Box::new(move |_| {
+                        let on_action = on_action.clone();
+                        let action = action.clone();
+                        std::thread::spawn(move || on_action(action));
+                    }),
+                    ..Default::default()
+                }
+                .into()
+            }
+            MenuEntry::Separator => ksni::MenuItem::Separator,
+            MenuEntry::SubMenu(label, entries) => SubMenu {
+                label: label.clone(),
+                submenu: ksni_items(entries, on_action),
+                ..Default::default()
+            }
+            .into(),
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "windows"))]
+impl Tray {
+    /// Shows the tray icon with an empty menu.
+    pub fn new(icon: &'static str, on_action: ActionHandler) -> Result<Self, AppError> {
+        let service = ksni::TrayService::new(KsniTray {
+            icon,
+            tooltip: String::new(),
+            menu: Vec::new(),
+            on_action,
+        });
+        let handle = service.handle();
+        service.spawn();
+        Ok(Self { handle })
+    }
+
+    /// Replaces the icon and the whole menu.
+    pub fn set_menu(&mut self, icon: &'static str, menu: Vec<MenuEntry>) -> Result<(), AppError> {
+        self.handle.update(|tray| {
+            tray.icon = icon;
+            tray.menu = menu;
+        });
+        Ok(())
+    }
+
+    /// Updates the status lines of a menu with an unchanged layout.
+    pub fn update_labels(&mut self, menu: Vec<MenuEntry>) -> Result<(), AppError> {
+        self.handle.update(|tray| tray.menu = menu);
+        Ok(())
+    }
+
+    pub fn set_tooltip(&mut self, tooltip: &str) -> Result<(), AppError> {
+        self.handle
+            .update(|tray| tray.tooltip = tooltip.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub struct Tray {
+    tray: tray_item::TrayItem,
+    icon: &'static str,
+    /// Menu ids and texts of the status lines, in menu order
+    labels: Vec<(u32, String)>,
+    on_action: ActionHandler,
+}
+
+#[cfg(target_os = "windows")]
+impl Tray {
+    /// Shows the tray icon with an empty menu.
+    pub fn new(icon: &'static str, on_action: ActionHandler) -> Result<Self, AppError> {
+        Ok(Self {
+            tray: Self::create(icon)?,
+            icon,
+            labels: Vec::new(),
+            on_action,
+        })
+    }
+
+    fn create(icon: &'static str) -> Result<tray_item::TrayItem, AppError> {
+        tray_item::TrayItem::new(TITLE, tray_item::IconSource::Resource(icon))
+            .map_err(|e| AppError::TrayUi(format!("Failed to create tray: {e}")))
+    }
+
+    /// Replaces the icon and the whole menu by recreating the tray.
+    pub fn set_menu(&mut self, icon: &'static str, menu: Vec<MenuEntry>) -> Result<(), AppError> {
+        let mut tray = Self::create(icon)?;
+        let mut labels = Vec::new();
+        for (text, action) in flatten(&menu, "") {
+            let result = match action {
+                Flat::Label => tray.inner_mut().add_label_with_id(&text).map(|id| {
+                    labels.push((id, text.clone()));
+                }),
+                Flat::Item(action) => {
+                    let on_action = self.on_action.clone();
+                    tray.add_menu_item(&text, move || on_action(action.clone()))
+                }
+                Flat::Separator => tray.inner_mut().add_separator(),
+            };
+            result.map_err(|e| AppError::TrayUi(format!("Failed to add '{text}': {e}")))?;
+        }
+        self.tray = tray;
+        self.icon = icon;
+        self.labels = labels;
+        Ok(())
+    }
+
+    /// Updates the status lines of a menu with an unchanged layout.
+    pub fn update_labels(&mut self, menu: Vec<MenuEntry>) -> Result<(), AppError> {
+        let texts: Vec<String> = flatten(&menu, "")
+            .into_iter()
+            .filter(|(_, kind)| matches!(kind, Flat::Label))
+            .map(|(text, _)| text)
+            .collect();
+        if texts.len() != self.labels.len() {
+            // A status line appeared or disappeared, e.g. the transfer speed
+            return self.set_menu(self.icon, menu);
+        }
+        for ((id, current), text) in self.labels.iter_mut().zip(texts) {
+            if *current != text {
+                self.tray
+                    .inner_mut()
+                    .set_label(&text, *id)
+                    .map_err(|e| AppError::TrayUi(format!("Failed to update menu label: {e}")))?;
+                *current = text;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_tooltip(&mut self, tooltip: &str) -> Result<(), AppError> {
+        // Windows truncates tooltips after 127 characters
+        const MAX_TOOLTIP_CHARS: usize = 127;
+
+        let tooltip: String = tooltip.chars().take(MAX_TOOLTIP_CHARS).collect();
+        self.tray
+            .inner_mut()
+            .set_tooltip(&tooltip)
+            .map_err(|e| AppError::TrayUi(format!("Failed to set tooltip: {e}")))
+    }
+}
+
+#[cfg(target_os = "windows")]
+enum Flat {
+    Label,
+    Item(TrayMenuAction),
+    Separator,
+}
+
+/// Lays out the menu as the flat list of items added to `tray-item`: the entries of
+/// a submenu follow its title, indented one level further.
+#[cfg(target_os = "windows")]
+fn flatten(entries: &[MenuEntry], indent: &str) -> Vec<(String, Flat)> {
+    let mut flat = Vec::new();
+    for entry in entries {
+        match entry {
+            MenuEntry::Label(label) => flat.push((format!("{indent}{label}"), Flat::Label)),
+            MenuEntry::Item(label, action) => {
+                flat.push((format!("{indent}{label}"), Flat::Item(action.clone())))
+            }
+            MenuEntry::Separator => flat.push((String::new(), Flat::Separator)),
+            MenuEntry::SubMenu(label, entries) => {
+                let title_indent = &label[..label.len() - label.trim_start().len()];
+                flat.push((format!("{indent}{label}"), Flat::Label));
+                flat.extend(flatten(entries, &format!("{indent}{title_indent}    ")));
+            }
+        }
+    }
+    flat
+}
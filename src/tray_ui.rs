@@ -1,71 +1,347 @@
 use log::{debug, info, warn};
 use std::error::Error;
-use std::sync::{Arc, Mutex};
+use std::fmt;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::thread;
-use std::time::Duration;
-use tray_item::TrayItem;
+use std::time::{Duration, Instant};
 
 use crate::app_state::AppState;
 use crate::config::Config;
+use crate::conflicts::{self, ConflictCount};
+use crate::devices::{self, DeviceMenuState};
 use crate::error_handling::AppError;
+use crate::folders::{self, FolderMenuState};
+use crate::remote::{self, RemoteHealth, RemoteStatus};
+use crate::sync_status;
+use crate::syncthing_api::SyncthingClient;
+use crate::transfer_speed;
+use crate::tray_menu::{self, MenuEntry, Tray};
+use crate::utils;
 
-/// Represents the current state of the system tray UI.
+/// How often folders and devices are refreshed; `/rest/db/status` and
+/// `/rest/db/completion` are expensive for large folders, so they are polled less
+/// often than the instance state.
+const DETAILS_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// State of a Syncthing instance, derived from process liveness and REST API data.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum TrayState {
-    Running,
+    /// No Syncthing process is running
     Stopped,
+    /// Started by this app, and the REST API is not answering yet
+    Starting,
+    /// Running, but the REST API gives no details, e.g. without a valid API key
+    Running,
+    /// All folders are up to date
+    Idle,
+    /// Data is being synced; the aggregated completion in percent
+    Syncing(u8),
+    /// All remote devices are paused
+    Paused,
+    /// None of the remote devices is connected
+    NoPeersConnected,
+    /// Syncthing reports errors
+    Error,
+    /// The process is alive, but its REST API is not answering
+    ApiUnreachable,
+}
+
+impl TrayState {
+    /// Whether a Syncthing process is running in this state.
+    pub fn is_running(self) -> bool {
+        self != TrayState::Stopped
+    }
+
+    /// Higher values are worse; the icon shows the worst state of all instances and remotes.
+    fn severity(self) -> u8 {
+        match self {
+            TrayState::Idle => 0,
+            TrayState::Running => 1,
+            TrayState::Syncing(_) => 2,
+            TrayState::Starting => 3,
+            TrayState::Paused => 4,
+            TrayState::NoPeersConnected => 5,
+            TrayState::Stopped => 6,
+            TrayState::ApiUnreachable => 7,
+            TrayState::Error => 8,
+        }
+    }
+
+    /// Name of the icon resource shown for this state.
+    fn icon(self) -> &'static str {
+        match self {
+            TrayState::Idle | TrayState::Running => "syncthing_green",
+            TrayState::Starting => "syncthing_starting",
+            TrayState::Syncing(_) => "syncthing_syncing",
+            TrayState::Paused => "syncthing_paused",
+            TrayState::NoPeersConnected => "syncthing_no_peers",
+            TrayState::Error => "syncthing_error",
+            TrayState::ApiUnreachable => "syncthing_unreachable",
+            TrayState::Stopped => "syncthing_red",
+        }
+    }
+
+    /// Short description for menu labels, without the completion percentage so
+    /// progress alone does not rebuild the menu.
+    fn label(self) -> &'static str {
+        match self {
+            TrayState::Stopped => "stopped",
+            TrayState::Starting => "starting",
+            TrayState::Running => "running",
+            TrayState::Idle => "up to date",
+            TrayState::Syncing(_) => "syncing",
+            TrayState::Paused => "paused",
+            TrayState::NoPeersConnected => "no devices connected",
+            TrayState::Error => "error",
+            TrayState::ApiUnreachable => "not responding",
+        }
+    }
+
+    /// Whether both states are the same apart from the completion percentage.
+    fn same_kind(self, other: TrayState) -> bool {
+        std::mem::discriminant(&self) == std::mem::discriminant(&other)
+    }
+}
+
+impl fmt::Display for TrayState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrayState::Syncing(percent) => write!(f, "syncing ({percent}%)"),
+            state => write!(f, "{}", state.label()),
+        }
+    }
+}
+
+/// Returns the worst of the given states, or `Stopped` if there are none.
+fn worst_state(states: impl IntoIterator<Item = TrayState>) -> TrayState {
+    states
+        .into_iter()
+        .max_by_key(|state| state.severity())
+        .unwrap_or(TrayState::Stopped)
+}
+
+/// The state shown for a monitored remote.
+fn remote_state(health: &RemoteHealth) -> TrayState {
+    match health {
+        RemoteHealth::InSync => TrayState::Idle,
+        RemoteHealth::OutOfSync { completion } => {
+            TrayState::Syncing(utils::completion_percent(*completion).min(99))
+        }
+        RemoteHealth::Down(_) => TrayState::ApiUnreachable,
+    }
 }
 
 /// Actions that can be triggered from the system tray menu.
-#[derive(Debug, Copy, Clone)]
+///
+/// Syncthing actions carry the name of the instance they apply to, folder and
+/// device actions also the folder or device ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TrayMenuAction {
-    StartStop,
-    OpenWebUI,
+    StartStop(String),
+    Restart(String),
+    OpenWebUI(String),
+    /// Pauses syncing with all devices, keeping Syncthing running
+    PauseAll(String),
+    ResumeAll(String),
+    PauseFolder {
+        instance: String,
+        folder: String,
+    },
+    ResumeFolder {
+        instance: String,
+        folder: String,
+    },
+    RescanFolder {
+        instance: String,
+        folder: String,
+    },
+    /// Opens a folder path, as configured in Syncthing, in the file manager
+    OpenFolder(String),
+    PauseDevice {
+        instance: String,
+        device: String,
+    },
+    ResumeDevice {
+        instance: String,
+        device: String,
+    },
     OpenConfig,
     Exit,
 }
 
+impl TrayMenuAction {
+    /// The instance whose REST API carries out the action, for pausing, resuming and
+    /// rescanning.
+    fn rest_instance(&self) -> Option<&str> {
+        match self {
+            TrayMenuAction::PauseAll(name) | TrayMenuAction::ResumeAll(name) => Some(name),
+            TrayMenuAction::PauseFolder { instance, .. }
+            | TrayMenuAction::ResumeFolder { instance, .. }
+            | TrayMenuAction::RescanFolder { instance, .. }
+            | TrayMenuAction::PauseDevice { instance, .. }
+            | TrayMenuAction::ResumeDevice { instance, .. } => Some(instance),
+            _ => None,
+        }
+    }
+}
+
+/// State of one Syncthing instance as shown in the tray menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstanceMenuState {
+    pub name: String,
+    pub state: TrayState,
+    /// "started by app", "external" or "not running"
+    pub origin: &'static str,
+    /// Set when a configuration reload changed launch settings of a running managed process
+    pub restart_required: bool,
+    /// All remote devices are paused; also set when `state` shows something more severe
+    pub paused: bool,
+    /// Shared folders, refreshed every `DETAILS_POLL_INTERVAL` while running
+    pub folders: Vec<FolderMenuState>,
+    /// Remote devices, refreshed every `DETAILS_POLL_INTERVAL` while running
+    pub devices: Vec<DeviceMenuState>,
+}
+
+impl InstanceMenuState {
+    /// Whether the menu built for `other` also fits this state, i.e. they differ at
+    /// most in the syncing percentage and folder and device states.
+    fn same_menu(&self, other: &InstanceMenuState) -> bool {
+        self.name == other.name
+            && self.origin == other.origin
+            && self.restart_required == other.restart_required
+            && self.paused == other.paused
+            && self.state.same_kind(other.state)
+            && self.folders.len() == other.folders.len()
+            && self
+                .folders
+                .iter()
+                .zip(&other.folders)
+                .all(|(folder, other)| folder.same_menu(other))
+            && self.devices.len() == other.devices.len()
+            && self
+                .devices
+                .iter()
+                .zip(&other.devices)
+                .all(|(device, other)| device.same_menu(other))
+    }
+
+    /// The menu item pausing or resuming all syncing, `None` while stopped.
+    fn pause_item(&self) -> Option<(&'static str, TrayMenuAction)> {
+        if self.paused {
+            Some((
+                "Resume All Syncing",
+                TrayMenuAction::ResumeAll(self.name.clone()),
+            ))
+        } else if self.state.is_running() {
+            Some((
+                "Pause All Syncing",
+                TrayMenuAction::PauseAll(self.name.clone()),
+            ))
+        } else {
+            None
+        }
+    }
+}
+
 /// System tray UI component for Syncthingers application.
 pub struct TrayUi {
-    tray: TrayItem,
+    tray: Tray,
+    /// The worst state of the local instances
     state: TrayState,
-    app_state: Arc<Mutex<AppState>>,
+    instances: Vec<InstanceMenuState>,
+    /// Formatted upload/download rates, while transfer speed monitoring has data
+    transfer_speed: Option<String>,
+    /// Latest statuses of the monitored remote Syncthing instances
+    remotes: Vec<RemoteStatus>,
+    /// Number of sync conflict copies per instance, from the latest folder scan
+    conflicts: Vec<ConflictCount>,
 }
 
 impl TrayUi {
     /// Creates a new TrayUi instance with the given application state.
     pub fn new(app_state: Arc<Mutex<AppState>>) -> Result<Arc<Mutex<Self>>, Box<dyn Error>> {
         // Initialize tray with initial icon state
-        let tray = TrayItem::new(
-            "Syncthingers",
-            tray_item::IconSource::Resource("syncthing_red"),
+        let handler_state = app_state.clone();
+        let tray = Tray::new(
+            TrayState::Stopped.icon(),
+            Arc::new(move |action| {
+                _ = Self::handle_menu_action_static(handler_state.clone(), action);
+            }),
         )?;
 
-        // Determine initial state by detecting running Syncthing process
+        // Determine initial state by detecting running Syncthing processes
         let initial_state = Self::detect_initial_state(&app_state)?;
+        let instances = Self::get_instance_states(&app_state, false);
 
         let tray_ui = Self {
             tray,
             state: initial_state,
-            app_state: app_state.clone(),
+            instances,
+            transfer_speed: None,
+            remotes: Vec::new(),
+            conflicts: Vec::new(),
         };
 
         // Create thread-safe reference to tray UI
         let tray_ui_ptr = Arc::new(Mutex::new(tray_ui));
 
         // Start monitoring thread
-        Self::start_monitoring_thread(tray_ui_ptr.clone(), app_state)?;
+        Self::start_monitoring_thread(tray_ui_ptr.clone(), app_state.clone())?;
+
+        // Show transfer speeds when enabled in the configuration
+        let tray_ui_weak = Arc::downgrade(&tray_ui_ptr);
+        transfer_speed::spawn(app_state.clone(), move |speed| {
+            if let Some(tray_ui_arc) = tray_ui_weak.upgrade()
+                && let Ok(mut tray_ui) = tray_ui_arc.lock()
+                && let Err(e) = tray_ui.set_transfer_speed(speed)
+            {
+                warn!("Failed to update transfer speed: {}", e);
+            }
+        });
+
+        // Show the health of configured remote Syncthing instances
+        let tray_ui_weak = Arc::downgrade(&tray_ui_ptr);
+        remote::spawn(app_state.clone(), move |remotes| {
+            if let Some(tray_ui_arc) = tray_ui_weak.upgrade()
+                && let Ok(mut tray_ui) = tray_ui_arc.lock()
+                && let Err(e) = tray_ui.set_remotes(remotes)
+            {
+                warn!("Failed to update remote status: {}", e);
+            }
+        });
+
+        // Show how many sync conflicts wait to be resolved
+        let tray_ui_weak = Arc::downgrade(&tray_ui_ptr);
+        conflicts::spawn(app_state, move |conflicts| {
+            if let Some(tray_ui_arc) = tray_ui_weak.upgrade()
+                && let Ok(mut tray_ui) = tray_ui_arc.lock()
+                && let Err(e) = tray_ui.set_conflicts(conflicts)
+            {
+                warn!("Failed to update conflict count: {}", e);
+            }
+        });
 
         Ok(tray_ui_ptr)
     }
 
-    /// Detects the initial state of Syncthing (running or stopped).
+    /// Detects the initial state of Syncthing (running if any instance is running).
+    ///
+    /// Attaches to already running external processes; the detailed state is filled
+    /// in by the monitoring thread.
     fn detect_initial_state(app_state: &Arc<Mutex<AppState>>) -> Result<TrayState, Box<dyn Error>> {
         let mut state_guard = app_state.lock().map_err(|_| "Failed to lock app state")?;
 
-        if state_guard.syncthing_running()
-            || state_guard.detect_and_attach_external().unwrap_or(false)
-        {
+        let mut any_running = false;
+        for instance in &mut state_guard.instances {
+            if instance.syncthing_running()
+                || instance.detect_and_attach_external().unwrap_or(false)
+            {
+                any_running = true;
+            }
+        }
+
+        if any_running {
             Ok(TrayState::Running)
         } else {
             Ok(TrayState::Stopped)
@@ -83,37 +359,113 @@ impl TrayUi {
         // Spawn a polling thread
         thread::spawn(move || {
             // Get initial process state
-            let initial_state = Self::get_current_process_state(&app_state);
+            let initial_states = Self::get_instance_states(&app_state, true);
             if let Some(tray_ui_arc) = tray_ui_weak.upgrade()
                 && let Ok(mut tray_ui) = tray_ui_arc.lock()
             {
-                tray_ui.set_state(initial_state.0);
+                tray_ui.set_instances(initial_states.clone());
                 if let Err(e) = tray_ui.recreate_tray_menu() {
                     warn!("Failed to recreate tray menu: {}", e);
                 }
             }
 
-            // Initialize state tracking variable
-            let mut last_state = Some(initial_state.0);
+            // Initialize state tracking
+            let mut last_states = initial_states;
+            let mut last_details_poll = Instant::now();
+            let events = match app_state.lock() {
+                Ok(state) => state.events.subscribe(),
+                Err(_) => {
+                    warn!("Failed to lock app state for subscribing to events");
+                    return;
+                }
+            };
 
             // Simple polling loop
             loop {
-                // Poll every 2 seconds
-                thread::sleep(Duration::from_secs(2));
+                // Poll every 2 seconds, or right away when Syncthing reports a change
+                // of folder or device states
+                let status_event = match events.recv_timeout(Duration::from_secs(2)) {
+                    Ok(event) => {
+                        let mut affects_status = event.kind.affects_status();
+                        // Handle a burst of events in one go
+                        while let Ok(event) = events.try_recv() {
+                            affects_status |= event.kind.affects_status();
+                        }
+                        affects_status
+                    }
+                    Err(RecvTimeoutError::Timeout) => false,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        thread::sleep(Duration::from_secs(2));
+                        false
+                    }
+                };
 
-                // Check current process state
-                let new_state = Self::get_current_process_state(&app_state);
+                // Restart a crashed managed process if the supervision policy asks for it
+                if let Ok(mut state) = app_state.lock() {
+                    state.supervise();
+                }
+
+                // Check current process states; folders and devices keep their last
+                // known state between polls
+                let refresh_details =
+                    status_event || last_details_poll.elapsed() >= DETAILS_POLL_INTERVAL;
+                let mut new_states = Self::get_instance_states(&app_state, refresh_details);
+                if refresh_details {
+                    last_details_poll = Instant::now();
+                } else {
+                    for instance in new_states.iter_mut().filter(|i| i.state.is_running()) {
+                        if let Some(last) = last_states.iter().find(|l| l.name == instance.name) {
+                            instance.folders = last.folders.clone();
+                            instance.devices = last.devices.clone();
+                        }
+                    }
+                }
+
+                // Update UI if any instance changed, including restart offers after a
+                // configuration reload changed launch settings
+                if new_states != last_states {
+                    for instance in &new_states {
+                        let previous = last_states.iter().find(|p| p.name == instance.name);
+                        if previous.is_none_or(|p| !p.state.same_kind(instance.state)) {
+                            info!("{} is {}", instance.name, instance.state);
+                        }
+                        if previous.map(|p| p.origin) == Some(instance.origin) {
+                            continue;
+                        }
+                        Self::log_process_state(&instance.name, instance.origin);
+                        debug!(
+                            "State change of {} detected by polling: {:?}",
+                            instance.name, instance.state
+                        );
 
-                // Update UI if state changed
-                if last_state.as_ref() != Some(&new_state.0) {
-                    Self::log_process_state(&new_state.1);
-                    debug!("State change detected by polling: {:?}", new_state.0);
+                        if instance.state.is_running() {
+                            Self::log_api_health(&app_state, &instance.name);
+                        }
+                    }
+
+                    // Progress alone only updates the icon, tooltip and folder and device
+                    // lines, not the whole menu
+                    let menu_changed = new_states.len() != last_states.len()
+                        || new_states
+                            .iter()
+                            .zip(&last_states)
+                            .any(|(new, last)| !new.same_menu(last));
+                    let details_changed = new_states.iter().zip(&last_states).any(|(new, last)| {
+                        new.folders != last.folders || new.devices != last.devices
+                    });
 
                     if let Some(tray_ui_arc) = tray_ui_weak.upgrade() {
                         if let Ok(mut tray_ui) = tray_ui_arc.lock() {
-                            tray_ui.set_state(new_state.0);
-                            if let Err(e) = tray_ui.recreate_tray_menu() {
-                                warn!("Failed to recreate tray menu: {}", e);
+                            tray_ui.set_instances(new_states.clone());
+                            let result = if menu_changed {
+                                tray_ui.recreate_tray_menu()
+                            } else if details_changed {
+                                tray_ui.update_detail_labels()
+                            } else {
+                                tray_ui.update_tooltip()
+                            };
+                            if let Err(e) = result {
+                                warn!("Failed to update tray menu: {}", e);
                             }
                         }
                     } else {
@@ -122,7 +474,7 @@ impl TrayUi {
                         break;
                     }
 
-                    last_state = Some(new_state.0);
+                    last_states = new_states;
                 }
             }
         });
@@ -130,102 +482,433 @@ impl TrayUi {
         Ok(())
     }
 
-    /// Gets the current Syncthing process state and its origin.
-    fn get_current_process_state(app_state: &Arc<Mutex<AppState>>) -> (TrayState, String) {
-        match app_state.lock() {
-            Ok(mut state) => {
-                // Use syncthing_running() to get the actual state, which handles process cleanup
-                if state.syncthing_running() {
-                    if let Some(proc) = &state.syncthing_process {
-                        if proc.started_by_app {
-                            (TrayState::Running, "started by app".to_string())
-                        } else {
-                            (TrayState::Running, "external".to_string())
-                        }
-                    } else {
-                        // This shouldn't happen, but handle it gracefully
-                        (TrayState::Stopped, "not running".to_string())
+    /// Gets the current state of every Syncthing instance, including its folders and
+    /// devices if `with_details` is set.
+    ///
+    /// Process liveness is checked under the state lock; running instances are then
+    /// queried over the REST API without holding it.
+    fn get_instance_states(
+        app_state: &Arc<Mutex<AppState>>,
+        with_details: bool,
+    ) -> Vec<InstanceMenuState> {
+        let probes: Vec<_> = match app_state.lock() {
+            Ok(mut state) => state
+                .instances
+                .iter_mut()
+                .map(|instance| {
+                    // process_origin() checks the actual state, which handles process cleanup
+                    let origin = instance.process_origin();
+                    let api = (origin != "not running").then(|| {
+                        let uptime = instance
+                            .syncthing_process
+                            .as_ref()
+                            .and_then(|process| process.uptime());
+                        (instance.api_client(), uptime)
+                    });
+                    let menu_state = InstanceMenuState {
+                        name: instance.name().to_string(),
+                        state: TrayState::Stopped,
+                        origin,
+                        restart_required: instance.restart_required,
+                        paused: false,
+                        folders: Vec::new(),
+                        devices: Vec::new(),
+                    };
+                    (menu_state, api)
+                })
+                .collect(),
+            Err(_) => {
+                warn!("Failed to lock app state for checking process state");
+                return Vec::new();
+            }
+        };
+
+        probes
+            .into_iter()
+            .map(|(mut menu_state, api)| {
+                if let Some((client, uptime)) = api {
+                    let snapshot = sync_status::query(&client);
+                    menu_state.paused = snapshot.as_ref().is_ok_and(|s| s.all_paused());
+                    menu_state.state = sync_status::derive_state(&snapshot, uptime);
+                    if with_details {
+                        menu_state.folders = folders::query(&client).unwrap_or_else(|e| {
+                            debug!("Cannot list folders of {}: {}", menu_state.name, e);
+                            Vec::new()
+                        });
+                        menu_state.devices = devices::query(&client).unwrap_or_else(|e| {
+                            debug!("Cannot list devices of {}: {}", menu_state.name, e);
+                            Vec::new()
+                        });
                     }
-                } else {
-                    (TrayState::Stopped, "not running".to_string())
                 }
+                menu_state
+            })
+            .collect()
+    }
+
+    /// Logs the process state change of an instance.
+    fn log_process_state(name: &str, process_origin: &str) {
+        match process_origin {
+            "started by app" => info!("{} process state: running (started by this app)", name),
+            "external" => info!("{} process state: running (external)", name),
+            "not running" => info!("{} process state: not running", name),
+            _ => warn!("Unknown {} process state: {}", name, process_origin),
+        }
+    }
+
+    /// Queries the Syncthing REST API and logs whether the daemon is responding.
+    fn log_api_health(app_state: &Arc<Mutex<AppState>>, name: &str) {
+        // Clone the client so the state lock is not held during network I/O
+        let client = match app_state.lock() {
+            Ok(mut state) => match state.instance_mut(name) {
+                Ok(instance) => instance.api_client(),
+                Err(_) => return,
+            },
+            Err(_) => return,
+        };
+
+        match (client.system_version(), client.system_status()) {
+            (Ok(version), Ok(status)) => info!(
+                "Syncthing API at {} is responding: {} (device {}, uptime {}s)",
+                client.base_url(),
+                version.version,
+                status.my_id,
+                status.uptime
+            ),
+            (Err(e), _) | (_, Err(e)) => {
+                warn!(
+                    "Syncthing API at {} is not responding: {}",
+                    client.base_url(),
+                    e
+                )
             }
-            Err(_) => (TrayState::Stopped, "error checking state".to_string()),
         }
     }
 
-    /// Logs the process state change.
-    fn log_process_state(process_origin: &str) {
-        match process_origin {
-            "started by app" => info!("Syncthing process state: running (started by this app)"),
-            "external" => info!("Syncthing process state: running (external)"),
-            "not running" => info!("Syncthing process state: not running"),
-            _ => warn!("Unknown Syncthing process state: {}", process_origin),
+    /// Updates the instance states and the overall tray state.
+    pub fn set_instances(&mut self, instances: Vec<InstanceMenuState>) {
+        self.state = worst_state(instances.iter().map(|i| i.state));
+        self.instances = instances;
+    }
+
+    /// Updates the remote statuses and rebuilds the menu if they changed.
+    pub fn set_remotes(&mut self, remotes: Vec<RemoteStatus>) -> Result<(), AppError> {
+        if self.remotes == remotes {
+            return Ok(());
         }
+        self.remotes = remotes;
+        self.recreate_tray_menu()
     }
 
-    /// Updates the tray state.
-    pub fn set_state(&mut self, state: TrayState) {
-        self.state = state;
+    /// Updates the conflict counts and rebuilds the menu if they changed.
+    pub fn set_conflicts(&mut self, conflicts: Vec<ConflictCount>) -> Result<(), AppError> {
+        if self.conflicts == conflicts {
+            return Ok(());
+        }
+        self.conflicts = conflicts;
+        self.recreate_tray_menu()
+    }
+
+    /// Number of sync conflict copies in the folders of an instance.
+    fn conflict_count(&self, instance: &str) -> usize {
+        self.conflicts
+            .iter()
+            .find(|c| c.instance == instance)
+            .map_or(0, |c| c.count)
+    }
+
+    /// The state shown by the icon: the worst of the local instances and the remotes.
+    fn icon_state(&self) -> TrayState {
+        let remotes = remote::worst_health(&self.remotes).map(remote_state);
+        worst_state(std::iter::once(self.state).chain(remotes))
     }
 
     /// Recreates the tray menu with updated state.
     pub fn recreate_tray_menu(&mut self) -> Result<(), AppError> {
-        // Determine icon based on current state
-        let icon = match self.state {
-            TrayState::Running => tray_item::IconSource::Resource("syncthing_green"),
-            TrayState::Stopped => tray_item::IconSource::Resource("syncthing_red"),
-        };
+        let menu = self.menu();
+        self.tray.set_menu(self.icon_state().icon(), menu)?;
+        self.update_tooltip()
+    }
 
-        // Create new tray with updated icon
-        let mut new_tray = TrayItem::new("Syncthingers", icon)
-            .map_err(|e| AppError::TrayUi(format!("Failed to recreate tray: {e}")))?;
+    /// Updates the displayed transfer speed, or hides it when `None`.
+    pub fn set_transfer_speed(&mut self, speed: Option<String>) -> Result<(), AppError> {
+        if self.transfer_speed == speed {
+            return Ok(());
+        }
+        self.transfer_speed = speed;
 
-        // Add menu items with appropriate callbacks
-        self.add_menu_items(&mut new_tray)?;
+        // Update the existing menu line in place, so the menu is not rebuilt on every
+        // poll
+        let menu = self.menu();
+        self.tray.update_labels(menu)?;
+        self.update_tooltip()
+    }
 
-        // Replace the old tray with new one
-        self.tray = new_tray;
+    /// Updates the folder and device status lines after their states or completions
+    /// changed.
+    pub fn update_detail_labels(&mut self) -> Result<(), AppError> {
+        let menu = self.menu();
+        self.tray.update_labels(menu)?;
+        self.update_tooltip()
+    }
 
-        Ok(())
+    /// Shows the instance states and transfer speed in the tray tooltip.
+    fn update_tooltip(&mut self) -> Result<(), AppError> {
+        let tooltip = self.tooltip_text();
+        self.tray.set_tooltip(&tooltip)
     }
 
-    /// Adds all menu items to the tray.
-    fn add_menu_items(&self, tray: &mut TrayItem) -> Result<(), AppError> {
-        let start_stop_label = match self.state {
-            TrayState::Running => "Stop Syncthing",
-            TrayState::Stopped => "Start Syncthing",
-        };
+    /// Tooltip listing the state of each instance and remote that is not up to date.
+    fn tooltip_text(&self) -> String {
+        let mut lines = vec!["Syncthingers".to_string()];
+        for instance in &self.instances {
+            lines.push(format!("{}: {}", instance.name, instance.state));
+            let conflicts = self.conflict_count(&instance.name);
+            if conflicts > 0 {
+                lines.push(format!("{}: {} sync conflict(s)", instance.name, conflicts));
+            }
+        }
+        for remote in &self.remotes {
+            if remote.health != RemoteHealth::InSync {
+                lines.push(format!("{}: {}", remote.name, remote.health));
+            }
+        }
+        if let Some(speed) = &self.transfer_speed {
+            lines.push(speed.clone());
+        }
+        lines.join("\n")
+    }
 
-        // Start/Stop menu item
-        self.add_menu_item(tray, start_stop_label, TrayMenuAction::StartStop)?;
+    /// Builds the menu entries for the current state.
+    fn menu(&self) -> Vec<MenuEntry> {
+        let mut menu = Vec::new();
 
-        // Open Web UI menu item
-        self.add_menu_item(tray, "Open Syncthing Web UI", TrayMenuAction::OpenWebUI)?;
+        // Disabled status line with the current transfer speed
+        if let Some(speed) = &self.transfer_speed {
+            menu.push(MenuEntry::Label(speed.clone()));
+        }
 
-        // Open Configuration menu item
-        self.add_menu_item(tray, "Open Configuration", TrayMenuAction::OpenConfig)?;
+        if let [instance] = self.instances.as_slice() {
+            menu.push(MenuEntry::Label(format!(
+                "Syncthing: {}",
+                instance.state.label()
+            )));
+            self.add_instance_items(&mut menu, instance, "");
+            if !instance.folders.is_empty() || !instance.devices.is_empty() {
+                menu.push(MenuEntry::Separator);
+            }
+            if !instance.folders.is_empty() {
+                menu.push(MenuEntry::SubMenu(
+                    "Folders".to_string(),
+                    Self::folder_entries(instance, Self::detail_limit()),
+                ));
+            }
+            if !instance.devices.is_empty() {
+                menu.push(MenuEntry::SubMenu(
+                    "Devices".to_string(),
+                    Self::device_entries(instance, Self::detail_limit()),
+                ));
+            }
+        } else {
+            // Each instance gets a titled section
+            for instance in &self.instances {
+                menu.push(MenuEntry::Separator);
+                menu.push(MenuEntry::Label(format!(
+                    "{} ({})",
+                    instance.name,
+                    instance.state.label()
+                )));
+                self.add_instance_items(&mut menu, instance, "    ");
+                if !instance.folders.is_empty() {
+                    menu.push(MenuEntry::SubMenu(
+                        "    Folders".to_string(),
+                        Self::folder_entries(instance, Self::detail_limit()),
+                    ));
+                }
+                if !instance.devices.is_empty() {
+                    menu.push(MenuEntry::SubMenu(
+                        "    Devices".to_string(),
+                        Self::device_entries(instance, Self::detail_limit()),
+                    ));
+                }
+            }
+            menu.push(MenuEntry::Separator);
+        }
 
-        // Exit menu item
-        self.add_menu_item(tray, "Exit", TrayMenuAction::Exit)?;
+        // Disabled status lines of the monitored remotes
+        if !self.remotes.is_empty() {
+            if let [_] = self.instances.as_slice() {
+                menu.push(MenuEntry::Separator);
+            }
+            for remote in &self.remotes {
+                menu.push(MenuEntry::Label(format!(
+                    "{}: {}",
+                    remote.name, remote.health
+                )));
+            }
+            menu.push(MenuEntry::Separator);
+        }
 
-        Ok(())
+        menu.push(MenuEntry::Item(
+            "Open Configuration".to_string(),
+            TrayMenuAction::OpenConfig,
+        ));
+        menu.push(MenuEntry::Item("Exit".to_string(), TrayMenuAction::Exit));
+        menu
     }
 
-    /// Helper to add an individual menu item with appropriate callback.
-    fn add_menu_item(
+    /// Adds the menu items controlling one instance, prefixing labels with `indent`.
+    fn add_instance_items(
         &self,
-        tray: &mut TrayItem,
-        label: &str,
-        action: TrayMenuAction,
-    ) -> Result<(), AppError> {
-        let app_state = self.app_state.clone();
-        let action_clone = action;
+        menu: &mut Vec<MenuEntry>,
+        instance: &InstanceMenuState,
+        indent: &str,
+    ) {
+        // Conflicts are listed and resolved with the `conflicts` and `resolve` commands
+        let conflicts = self.conflict_count(&instance.name);
+        if conflicts > 0 {
+            menu.push(MenuEntry::Label(format!(
+                "{indent}Sync conflicts: {conflicts}"
+            )));
+        }
 
-        tray.add_menu_item(label, move || {
-            _ = Self::handle_menu_action_static(app_state.clone(), action_clone);
-        })
-        .map_err(|e| AppError::TrayUi(format!("Failed to add menu item '{}': {}", label, e)))
+        let start_stop_label = if instance.state.is_running() {
+            "Stop Syncthing"
+        } else {
+            "Start Syncthing"
+        };
+        menu.push(MenuEntry::Item(
+            format!("{indent}{start_stop_label}"),
+            TrayMenuAction::StartStop(instance.name.clone()),
+        ));
+
+        // Restart menu item, shown when configuration changes need a restart
+        if instance.restart_required && instance.state.is_running() {
+            menu.push(MenuEntry::Item(
+                format!("{indent}Restart Syncthing (configuration changed)"),
+                TrayMenuAction::Restart(instance.name.clone()),
+            ));
+        }
+
+        // Pause/Resume all syncing, which keeps Syncthing running unlike Stop
+        if let Some((label, action)) = instance.pause_item() {
+            menu.push(MenuEntry::Item(format!("{indent}{label}"), action));
+        }
+
+        menu.push(MenuEntry::Item(
+            format!("{indent}Open Syncthing Web UI"),
+            TrayMenuAction::OpenWebUI(instance.name.clone()),
+        ));
+    }
+
+    /// Entries of the Folders submenu: a submenu per folder, titled with its status and
+    /// holding its actions. At most `limit` folders are listed.
+    fn folder_entries(instance: &InstanceMenuState, limit: usize) -> Vec<MenuEntry> {
+        let mut entries: Vec<MenuEntry> = instance
+            .folders
+            .iter()
+            .take(limit)
+            .map(|folder| {
+                let name = instance.name.clone();
+                let folder_id = folder.id.clone();
+                let (pause_label, pause_action) = if folder.paused {
+                    (
+                        "Resume",
+                        TrayMenuAction::ResumeFolder {
+                            instance: name.clone(),
+                            folder: folder_id.clone(),
+                        },
+                    )
+                } else {
+                    (
+                        "Pause",
+                        TrayMenuAction::PauseFolder {
+                            instance: name.clone(),
+                            folder: folder_id.clone(),
+                        },
+                    )
+                };
+                MenuEntry::SubMenu(
+                    folder.label(),
+                    vec![
+                        MenuEntry::Item(pause_label.to_string(), pause_action),
+                        MenuEntry::Item(
+                            "Rescan".to_string(),
+                            TrayMenuAction::RescanFolder {
+                                instance: name,
+                                folder: folder_id,
+                            },
+                        ),
+                        MenuEntry::Item(
+                            "Open in File Manager".to_string(),
+                            TrayMenuAction::OpenFolder(folder.path.clone()),
+                        ),
+                    ],
+                )
+            })
+            .collect();
+        if instance.folders.len() > limit {
+            entries.push(MenuEntry::Label(format!(
+                "... and {} more in the web UI",
+                instance.folders.len() - limit
+            )));
+        }
+        entries
+    }
+
+    /// How many folders or devices are listed per instance. Without submenus each of
+    /// them adds several lines to the main menu, so long lists are cut short.
+    fn detail_limit() -> usize {
+        // Enough for a typical setup while keeping the menu on screen
+        const MAX_FLAT_DETAILS: usize = 10;
+
+        if tray_menu::HAS_SUBMENUS {
+            usize::MAX
+        } else {
+            MAX_FLAT_DETAILS
+        }
+    }
+
+    /// Entries of the Devices submenu: a submenu per device, titled with its status and
+    /// holding its Pause or Resume action. At most `limit` devices are listed.
+    fn device_entries(instance: &InstanceMenuState, limit: usize) -> Vec<MenuEntry> {
+        let mut entries: Vec<MenuEntry> = instance
+            .devices
+            .iter()
+            .take(limit)
+            .map(|device| {
+                let name = instance.name.clone();
+                let device_id = device.id.clone();
+                let (label, action) = if device.paused {
+                    (
+                        "Resume",
+                        TrayMenuAction::ResumeDevice {
+                            instance: name,
+                            device: device_id,
+                        },
+                    )
+                } else {
+                    (
+                        "Pause",
+                        TrayMenuAction::PauseDevice {
+                            instance: name,
+                            device: device_id,
+                        },
+                    )
+                };
+                MenuEntry::SubMenu(
+                    device.label(),
+                    vec![MenuEntry::Item(label.to_string(), action)],
+                )
+            })
+            .collect();
+        if instance.devices.len() > limit {
+            entries.push(MenuEntry::Label(format!(
+                "... and {} more in the web UI",
+                instance.devices.len() - limit
+            )));
+        }
+        entries
     }
 
     /// Sets up the initial tray menu.
@@ -240,26 +923,114 @@ impl TrayUi {
     ) -> Result<(), AppError> {
         info!("Tray menu action: {:?}", action);
 
-        match app_state.lock() {
-            Ok(mut state) => Self::process_menu_action(&mut state, action),
-            Err(_) => Err(AppError::TrayUi("Failed to lock app state".to_string())),
+        // REST requests can take up to their timeout, so the lock is only held while
+        // taking the client
+        if let Some(instance) = action.rest_instance() {
+            let client = Self::lock(&app_state)?.instance_mut(instance)?.api_client();
+            return Self::send_rest_action(&client, action);
+        }
+
+        // Stopping waits for Syncthing to exit, which must not block the other threads
+        match action {
+            TrayMenuAction::StartStop(name) => {
+                if !AppState::stop_instance(&app_state, &name)? {
+                    Self::lock(&app_state)?
+                        .instance_mut(&name)?
+                        .start_syncthing()?;
+                }
+                Ok(())
+            }
+            TrayMenuAction::Restart(name) => AppState::restart_instance(&app_state, &name),
+            action => Self::process_menu_action(&mut *Self::lock(&app_state)?, action),
         }
     }
 
+    /// Carries out a pause, resume or rescan action through the REST API.
+    fn send_rest_action(client: &SyncthingClient, action: TrayMenuAction) -> Result<(), AppError> {
+        match action {
+            TrayMenuAction::PauseAll(name) => {
+                client.pause_all()?;
+                info!("Paused syncing of {}", name);
+            }
+            TrayMenuAction::ResumeAll(name) => {
+                client.resume_all()?;
+                info!("Resumed syncing of {}", name);
+            }
+            TrayMenuAction::PauseFolder { instance, folder } => {
+                client.set_folder_paused(&folder, true)?;
+                info!("Paused folder {} of {}", folder, instance);
+            }
+            TrayMenuAction::ResumeFolder { instance, folder } => {
+                client.set_folder_paused(&folder, false)?;
+                info!("Resumed folder {} of {}", folder, instance);
+            }
+            TrayMenuAction::RescanFolder { instance, folder } => {
+                client.scan_folder(&folder)?;
+                info!("Requested rescan of folder {} of {}", folder, instance);
+            }
+            TrayMenuAction::PauseDevice { instance, device } => {
+                client.set_device_paused(&device, true)?;
+                info!("Paused device {} of {}", device, instance);
+            }
+            TrayMenuAction::ResumeDevice { instance, device } => {
+                client.set_device_paused(&device, false)?;
+                info!("Resumed device {} of {}", device, instance);
+            }
+            action => {
+                return Err(AppError::TrayUi(format!(
+                    "{:?} is not a REST API action",
+                    action
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn lock(app_state: &Mutex<AppState>) -> Result<MutexGuard<'_, AppState>, AppError> {
+        app_state
+            .lock()
+            .map_err(|_| AppError::TrayUi("Failed to lock app state".to_string()))
+    }
+
     /// Processes a menu action with the given application state.
-    fn process_menu_action(state: &mut AppState, action: TrayMenuAction) -> Result<(), AppError> {
+    pub fn process_menu_action(
+        state: &mut AppState,
+        action: TrayMenuAction,
+    ) -> Result<(), AppError> {
+        if let Some(instance) = action.rest_instance() {
+            let client = state.instance_mut(instance)?.api_client();
+            return Self::send_rest_action(&client, action);
+        }
+
         match action {
-            TrayMenuAction::StartStop => {
-                if state.syncthing_running() {
-                    state.stop_syncthing()?;
+            TrayMenuAction::StartStop(name) => {
+                let instance = state.instance_mut(&name)?;
+                if instance.syncthing_running() {
+                    instance.stop_syncthing()?;
                 } else {
-                    state.start_syncthing()?;
+                    instance.start_syncthing()?;
                 }
             }
-            TrayMenuAction::OpenWebUI => {
-                opener::open(&state.config.web_ui_url)
+            TrayMenuAction::Restart(name) => {
+                state.instance_mut(&name)?.restart_syncthing()?;
+            }
+            TrayMenuAction::OpenWebUI(name) => {
+                let gui = state.instance_mut(&name)?.gui();
+                opener::open(&gui.web_ui_url)
                     .map_err(|e| AppError::TrayUi(format!("Failed to open web UI: {}", e)))?;
             }
+            TrayMenuAction::OpenFolder(path) => {
+                opener::open(folders::local_path(&path))
+                    .map_err(|e| AppError::TrayUi(format!("Failed to open folder: {}", e)))?;
+            }
+            // Sent through the REST API above
+            TrayMenuAction::PauseAll(_)
+            | TrayMenuAction::ResumeAll(_)
+            | TrayMenuAction::PauseFolder { .. }
+            | TrayMenuAction::ResumeFolder { .. }
+            | TrayMenuAction::RescanFolder { .. }
+            | TrayMenuAction::PauseDevice { .. }
+            | TrayMenuAction::ResumeDevice { .. } => {}
             TrayMenuAction::OpenConfig => {
                 // Use the stateful AppDirs instance
                 let config_file_path = state.app_dirs.config_file_path();
@@ -297,6 +1068,7 @@ impl TrayUi {
 mod tests {
     use super::*;
     use crate::app_dirs::AppDirs;
+    use crate::syncthing_api::mock::MockServer;
 
     // Helper for creating test config
     fn create_test_config() -> Config {
@@ -338,16 +1110,241 @@ mod tests {
     }
 
     #[test]
-    fn test_get_current_process_state() {
+    fn test_get_instance_states() {
         let config = create_test_config();
         let app_dirs = dummy_app_dirs();
         let app_state = Arc::new(Mutex::new(AppState::new(config, app_dirs)));
 
         // Test with no process running
-        let (state, origin) = TrayUi::get_current_process_state(&app_state);
-        assert_eq!(state, TrayState::Stopped);
-        assert_eq!(origin, "not running");
+        let states = TrayUi::get_instance_states(&app_state, true);
+        assert_eq!(states.len(), 1);
+        assert_eq!(states[0].state, TrayState::Stopped);
+        assert_eq!(states[0].origin, "not running");
+        assert!(states[0].folders.is_empty());
+        assert!(states[0].devices.is_empty());
 
         // In a real test with mocks, we could test the other states as well
     }
+
+    #[test]
+    fn test_folder_and_device_actions_call_rest_api() {
+        let server = MockServer::start(vec![
+            ("/rest/config/folders/docs", 200, ""),
+            ("/rest/db/scan", 200, ""),
+            ("/rest/config/devices/BUILDSV-BBBBBBB", 200, ""),
+        ]);
+        let config = Config {
+            web_ui_url: server.base_url.clone(),
+            api_key: "key".to_string(),
+            ..create_test_config()
+        };
+        let mut state = AppState::new(config, dummy_app_dirs());
+        let instance = state.instances[0].name().to_string();
+        TrayUi::process_menu_action(
+            &mut state,
+            TrayMenuAction::ResumeFolder {
+                instance: instance.clone(),
+                folder: "docs".to_string(),
+            },
+        )
+        .unwrap();
+        TrayUi::process_menu_action(
+            &mut state,
+            TrayMenuAction::RescanFolder {
+                instance: instance.clone(),
+                folder: "docs".to_string(),
+            },
+        )
+        .unwrap();
+        TrayUi::process_menu_action(
+            &mut state,
+            TrayMenuAction::PauseDevice {
+                instance,
+                device: "BUILDSV-BBBBBBB".to_string(),
+            },
+        )
+        .unwrap();
+
+        let requests = server.recorded();
+        assert_eq!(requests[0].method, "PATCH");
+        assert_eq!(requests[0].body, r#"{"paused":false}"#);
+        assert_eq!(requests[1].url, "/rest/db/scan?folder=docs");
+        assert_eq!(requests[2].method, "PATCH");
+        assert_eq!(requests[2].url, "/rest/config/devices/BUILDSV-BBBBBBB");
+        assert_eq!(requests[2].body, r#"{"paused":true}"#);
+    }
+
+    #[test]
+    fn test_menu_rest_action_is_sent_from_shared_state() {
+        let server = MockServer::start(vec![("/rest/system/pause", 200, "")]);
+        let config = Config {
+            web_ui_url: server.base_url.clone(),
+            api_key: "key".to_string(),
+            ..create_test_config()
+        };
+        let app_state = Arc::new(Mutex::new(AppState::new(config, dummy_app_dirs())));
+        let instance = app_state.lock().unwrap().instances[0].name().to_string();
+
+        TrayUi::handle_menu_action_static(app_state.clone(), TrayMenuAction::PauseAll(instance))
+            .unwrap();
+
+        assert_eq!(server.recorded()[0].url, "/rest/system/pause");
+        assert!(app_state.try_lock().is_ok());
+    }
+
+    #[test]
+    fn test_devices_get_a_submenu_each() {
+        let device = |id: &str, paused: bool| DeviceMenuState {
+            id: id.to_string(),
+            name: id.to_string(),
+            paused,
+            connected: false,
+            address: String::new(),
+            completion: 100,
+        };
+        let instance = InstanceMenuState {
+            name: "Syncthing".to_string(),
+            state: TrayState::Idle,
+            origin: "external",
+            restart_required: false,
+            paused: false,
+            folders: Vec::new(),
+            devices: vec![device("laptop", true), device("nas", false)],
+        };
+
+        let entries = TrayUi::device_entries(&instance, usize::MAX);
+        assert_eq!(
+            entries[0],
+            MenuEntry::SubMenu(
+                "laptop: paused (100%)".to_string(),
+                vec![MenuEntry::Item(
+                    "Resume".to_string(),
+                    TrayMenuAction::ResumeDevice {
+                        instance: "Syncthing".to_string(),
+                        device: "laptop".to_string(),
+                    }
+                )]
+            )
+        );
+        assert_eq!(entries.len(), 2);
+
+        let entries = TrayUi::device_entries(&instance, 1);
+        assert_eq!(
+            entries.last(),
+            Some(&MenuEntry::Label(
+                "... and 1 more in the web UI".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_folders_get_a_submenu_each() {
+        let folder = |id: &str, paused: bool| FolderMenuState {
+            id: id.to_string(),
+            name: id.to_string(),
+            path: format!("/data/{id}"),
+            paused,
+            state: "idle".to_string(),
+            completion: 100,
+        };
+        let instance = InstanceMenuState {
+            name: "Syncthing".to_string(),
+            state: TrayState::Idle,
+            origin: "external",
+            restart_required: false,
+            paused: false,
+            folders: vec![folder("docs", false), folder("photos", true)],
+            devices: Vec::new(),
+        };
+
+        let entries = TrayUi::folder_entries(&instance, usize::MAX);
+        assert_eq!(entries.len(), 2);
+        let MenuEntry::SubMenu(title, items) = &entries[1] else {
+            panic!("expected a submenu, got {:?}", entries[1]);
+        };
+        assert_eq!(title, "photos: paused (100%)");
+        assert_eq!(
+            items[0],
+            MenuEntry::Item(
+                "Resume".to_string(),
+                TrayMenuAction::ResumeFolder {
+                    instance: "Syncthing".to_string(),
+                    folder: "photos".to_string(),
+                }
+            )
+        );
+        assert_eq!(
+            items[2],
+            MenuEntry::Item(
+                "Open in File Manager".to_string(),
+                TrayMenuAction::OpenFolder("/data/photos".to_string())
+            )
+        );
+
+        // Without submenus long lists are cut short
+        let entries = TrayUi::folder_entries(&instance, 1);
+        assert_eq!(
+            entries.last(),
+            Some(&MenuEntry::Label(
+                "... and 1 more in the web UI".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_pause_item_follows_paused_flag() {
+        let instance = InstanceMenuState {
+            name: "Syncthing".to_string(),
+            state: TrayState::Idle,
+            origin: "external",
+            restart_required: false,
+            paused: false,
+            folders: Vec::new(),
+            devices: Vec::new(),
+        };
+        let label = |instance: &InstanceMenuState| instance.pause_item().map(|(label, _)| label);
+        assert_eq!(label(&instance), Some("Pause All Syncing"));
+
+        // An error hides the paused state in the icon, but resuming must stay possible
+        let paused = InstanceMenuState {
+            state: TrayState::Error,
+            paused: true,
+            ..instance.clone()
+        };
+        assert_eq!(label(&paused), Some("Resume All Syncing"));
+        assert!(!paused.same_menu(&InstanceMenuState {
+            paused: false,
+            ..paused.clone()
+        }));
+
+        let stopped = InstanceMenuState {
+            state: TrayState::Stopped,
+            origin: "not running",
+            ..instance
+        };
+        assert_eq!(label(&stopped), None);
+    }
+
+    #[test]
+    fn test_worst_state_picks_most_severe() {
+        assert_eq!(worst_state([]), TrayState::Stopped);
+        assert_eq!(
+            worst_state([TrayState::Idle, TrayState::Syncing(40), TrayState::Running]),
+            TrayState::Syncing(40)
+        );
+        assert_eq!(
+            worst_state([
+                TrayState::Paused,
+                TrayState::ApiUnreachable,
+                TrayState::Stopped
+            ]),
+            TrayState::ApiUnreachable
+        );
+        assert_eq!(
+            remote_state(&RemoteHealth::Down("timeout".to_string())),
+            TrayState::ApiUnreachable
+        );
+        assert!(TrayState::Syncing(10).same_kind(TrayState::Syncing(90)));
+        assert_eq!(TrayState::Syncing(42).to_string(), "syncing (42%)");
+    }
 }
@@ -44,7 +44,25 @@ pub fn is_test_environment(path: &str) -> bool {
     path.contains("test") || path.contains("mock") || path.contains("nonexistent")
 }
 
+/// Converts a completion reported by Syncthing into a whole percentage for display.
+///
+/// Rounded down, so a folder or device that still misses its last bytes is never
+/// shown as 100%; values outside 0..=100 are clamped.
+pub fn completion_percent(completion: f64) -> u8 {
+    completion.floor().clamp(0.0, 100.0) as u8
+}
+
 mod tests {
+    #[test]
+    fn test_completion_percent_rounds_down() {
+        use super::completion_percent;
+        assert_eq!(completion_percent(99.96), 99);
+        assert_eq!(completion_percent(45.7), 45);
+        assert_eq!(completion_percent(100.0), 100);
+        assert_eq!(completion_percent(-1.0), 0);
+        assert_eq!(completion_percent(f64::NAN), 0);
+    }
+
     #[test]
     fn test_normalize_exe_name_with_exe_extension() {
         use super::normalize_exe_name;
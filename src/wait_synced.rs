@@ -0,0 +1,383 @@
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::error_handling::AppError;
+use crate::syncthing_api::SyncthingClient;
+use crate::utils;
+
+/// Exit code of `wait-synced` when the timeout expires; clap already uses 2 for
+/// usage errors.
+pub const EXIT_TIMED_OUT: i32 = 3;
+
+/// How often the folder states and completions are checked while waiting.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Folders and devices to wait for; empty lists select all that are not paused.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WaitFilter {
+    /// Folder IDs or labels
+    pub folders: Vec<String>,
+    /// Device IDs or names
+    pub devices: Vec<String>,
+}
+
+/// Result of waiting for the selected folders to be in sync.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WaitOutcome {
+    Synced,
+    /// The timeout expired; lists what was still out of sync
+    TimedOut(Vec<String>),
+}
+
+/// A folder to wait for and the remote devices it has to reach.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Target {
+    folder_id: String,
+    folder_name: String,
+    /// IDs and display names of the remote devices
+    devices: Vec<(String, String)>,
+}
+
+/// Resolves the filter against the folders and devices configured in Syncthing.
+///
+/// Unknown names and explicitly selected but paused folders or devices are errors,
+/// since waiting for them could never succeed.
+fn targets(client: &SyncthingClient, filter: &WaitFilter) -> Result<Vec<Target>, AppError> {
+    let my_id = client.system_status()?.my_id;
+    let folders = client.config_folders()?;
+    let devices: Vec<_> = client
+        .config_devices()?
+        .into_iter()
+        .filter(|device| device.device_id != my_id)
+        .collect();
+
+    for wanted in &filter.folders {
+        if !folders
+            .iter()
+            .any(|f| f.id == *wanted || f.label == *wanted)
+        {
+            let names: Vec<&str> = folders.iter().map(|f| f.display_name()).collect();
+            return Err(AppError::Command(format!(
+                "Unknown folder '{}'. Configured folders: {}",
+                wanted,
+                names.join(", ")
+            )));
+        }
+    }
+    for wanted in &filter.devices {
+        if !devices
+            .iter()
+            .any(|d| d.device_id == *wanted || d.name == *wanted)
+        {
+            let names: Vec<&str> = devices.iter().map(|d| d.display_name()).collect();
+            return Err(AppError::Command(format!(
+                "Unknown device '{}'. Configured devices: {}",
+                wanted,
+                names.join(", ")
+            )));
+        }
+    }
+
+    let mut targets = Vec::new();
+    for folder in &folders {
+        let selected = filter.folders.is_empty()
+            || filter
+                .folders
+                .iter()
+                .any(|wanted| *wanted == folder.id || *wanted == folder.label);
+        if !selected {
+            continue;
+        }
+        if folder.paused {
+            if filter.folders.is_empty() {
+                continue;
+            }
+            return Err(AppError::Command(format!(
+                "Folder '{}' is paused.",
+                folder.display_name()
+            )));
+        }
+
+        let mut folder_devices = Vec::new();
+        for device in devices
+            .iter()
+            .filter(|d| folder.devices.iter().any(|f| f.device_id == d.device_id))
+        {
+            let selected = filter.devices.is_empty()
+                || filter
+                    .devices
+                    .iter()
+                    .any(|wanted| *wanted == device.device_id || *wanted == device.name);
+            if !selected {
+                continue;
+            }
+            if device.paused {
+                if filter.devices.is_empty() {
+                    continue;
+                }
+                return Err(AppError::Command(format!(
+                    "Device '{}' is paused.",
+                    device.display_name()
+                )));
+            }
+            folder_devices.push((device.device_id.clone(), device.display_name().to_string()));
+        }
+
+        // With a device filter only folders shared with those devices matter
+        if !filter.devices.is_empty() && folder_devices.is_empty() {
+            continue;
+        }
+        targets.push(Target {
+            folder_id: folder.id.clone(),
+            folder_name: folder.display_name().to_string(),
+            devices: folder_devices,
+        });
+    }
+
+    if targets.is_empty() {
+        return Err(AppError::Command(
+            "No folder matches the selected folders and devices.".to_string(),
+        ));
+    }
+    Ok(targets)
+}
+
+/// Describes each target that is not in sync yet, e.g. `Documents: syncing` or
+/// `Documents on laptop: 87%`. Request failures count as not in sync, so a
+/// restarting Syncthing does not end the wait.
+fn pending(client: &SyncthingClient, targets: &[Target]) -> Vec<String> {
+    let mut pending = Vec::new();
+    for target in targets {
+        match client.db_status(&target.folder_id) {
+            Ok(status) if status.state == "idle" && status.need_bytes == 0 => {}
+            Ok(status) => pending.push(format!("{}: {}", target.folder_name, status.state)),
+            Err(e) => pending.push(format!("{}: {}", target.folder_name, e)),
+        }
+        for (id, name) in &target.devices {
+            match client.db_completion(Some(&target.folder_id), Some(id)) {
+                Ok(completion) if completion.completion >= 100.0 => {}
+                Ok(completion) => pending.push(format!(
+                    "{} on {}: {}%",
+                    target.folder_name,
+                    name,
+                    utils::completion_percent(completion.completion)
+                )),
+                Err(e) => pending.push(format!("{} on {}: {}", target.folder_name, name, e)),
+            }
+        }
+    }
+    pending
+}
+
+/// Waits until the selected folders are idle and complete on the selected devices of
+/// every instance, or until `timeout` expires.
+///
+/// The folders are rescanned first, so files written just before the call are not
+/// missed while Syncthing's file watcher is still waiting. `on_progress` receives
+/// the list of pending folders and devices whenever it changes.
+pub fn wait(
+    instances: &[(String, SyncthingClient)],
+    filter: &WaitFilter,
+    timeout: Option<Duration>,
+    poll_interval: Duration,
+    mut on_progress: impl FnMut(&[String]),
+) -> Result<WaitOutcome, AppError> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let prefix = instances.len() > 1;
+
+    let mut plans = Vec::new();
+    for (name, client) in instances {
+        let targets = targets(client, filter).map_err(|e| {
+            if prefix {
+                AppError::Command(format!("{name}: {e}"))
+            } else {
+                e
+            }
+        })?;
+        for target in &targets {
+            // The scan request only waits a few seconds for large folders; the state
+            // check below keeps waiting for a scan that is still running
+            let _ = client.scan_folder(&target.folder_id);
+        }
+        plans.push((name, client, targets));
+    }
+
+    let mut last = Vec::new();
+    loop {
+        let mut lines = Vec::new();
+        for (name, client, targets) in &plans {
+            for line in pending(client, targets) {
+                lines.push(if prefix {
+                    format!("{name}: {line}")
+                } else {
+                    line
+                });
+            }
+        }
+        if lines.is_empty() {
+            return Ok(WaitOutcome::Synced);
+        }
+        if lines != last {
+            on_progress(&lines);
+        }
+
+        let remaining = match deadline {
+            Some(deadline) => deadline.saturating_duration_since(Instant::now()),
+            None => poll_interval,
+        };
+        if remaining.is_zero() {
+            return Ok(WaitOutcome::TimedOut(lines));
+        }
+        thread::sleep(poll_interval.min(remaining));
+        last = lines;
+    }
+}
+
+/// Runs the `wait-synced` command for the instance named `instance`, or all
+/// configured instances, reporting progress on stderr.
+pub fn run_command(
+    config_path: &Path,
+    instance: Option<&str>,
+    filter: &WaitFilter,
+    timeout: Option<Duration>,
+) -> Result<WaitOutcome, AppError> {
+    let (config, _) = Config::load_validated(config_path)?;
+    let configs = config.instance_configs();
+    let names: Vec<String> = configs.iter().map(|c| c.name.clone()).collect();
+    let instances: Vec<(String, SyncthingClient)> = configs
+        .iter()
+        .filter(|c| instance.is_none_or(|name| c.name == name))
+        .map(|c| (c.name.clone(), SyncthingClient::from_instance(c)))
+        .collect();
+    if instances.is_empty() {
+        return Err(AppError::Command(format!(
+            "Unknown instance '{}'. Configured instances: {}",
+            instance.unwrap_or_default(),
+            names.join(", ")
+        )));
+    }
+
+    wait(&instances, filter, timeout, POLL_INTERVAL, |pending| {
+        eprintln!("Waiting for: {}", pending.join(", "));
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syncthing_api::mock::MockServer;
+
+    const FOLDERS: &str = r#"[
+        {"id":"docs","label":"Documents","path":"/data/docs","devices":[{"deviceID":"ME"},{"deviceID":"LAPTOP"},{"deviceID":"NAS"}]},
+        {"id":"old","label":"","path":"/data/old","paused":true,"devices":[{"deviceID":"ME"}]}
+    ]"#;
+    const DEVICES: &str = r#"[
+        {"deviceID":"ME","name":"this"},
+        {"deviceID":"LAPTOP","name":"laptop"},
+        {"deviceID":"NAS","name":"nas","paused":true}
+    ]"#;
+
+    fn server(state: &str, completion: f64) -> MockServer {
+        let status =
+            format!(r#"{{"state":"{state}","globalBytes":10,"inSyncBytes":10,"needBytes":0}}"#);
+        let completion = format!(r#"{{"completion":{completion}}}"#);
+        MockServer::start(vec![
+            ("/rest/system/status", 200, r#"{"myID":"ME","uptime":1}"#),
+            ("/rest/config/folders", 200, FOLDERS),
+            ("/rest/config/devices", 200, DEVICES),
+            ("/rest/db/scan", 200, ""),
+            ("/rest/db/status", 200, &status),
+            ("/rest/db/completion", 200, &completion),
+        ])
+    }
+
+    fn instances(server: &MockServer) -> Vec<(String, SyncthingClient)> {
+        vec![(
+            "Syncthing".to_string(),
+            SyncthingClient::new(&server.base_url, "key"),
+        )]
+    }
+
+    #[test]
+    fn test_targets_apply_filters() {
+        let server = server("idle", 100.0);
+        let client = SyncthingClient::new(&server.base_url, "key");
+
+        // Paused folders are skipped and this device is never waited for
+        let all = targets(&client, &WaitFilter::default()).unwrap();
+        assert_eq!(
+            all,
+            vec![Target {
+                folder_id: "docs".to_string(),
+                folder_name: "Documents".to_string(),
+                devices: vec![("LAPTOP".to_string(), "laptop".to_string())],
+            }]
+        );
+
+        let by_name = WaitFilter {
+            folders: vec!["Documents".to_string()],
+            devices: vec!["laptop".to_string()],
+        };
+        assert_eq!(targets(&client, &by_name).unwrap(), all);
+
+        let errors = [
+            (vec!["music"], vec![], "Unknown folder 'music'"),
+            (vec!["old"], vec![], "Folder 'old' is paused."),
+            (vec![], vec!["phone"], "Unknown device 'phone'"),
+            (vec![], vec!["this"], "Unknown device 'this'"),
+            (vec![], vec!["nas"], "Device 'nas' is paused."),
+        ];
+        for (folders, devices, message) in errors {
+            let filter = WaitFilter {
+                folders: folders.into_iter().map(String::from).collect(),
+                devices: devices.into_iter().map(String::from).collect(),
+            };
+            let error = targets(&client, &filter).unwrap_err().to_string();
+            assert!(error.contains(message), "{error}");
+        }
+    }
+
+    #[test]
+    fn test_wait_returns_when_synced_after_rescan() {
+        let server = server("idle", 100.0);
+        let outcome = wait(
+            &instances(&server),
+            &WaitFilter::default(),
+            Some(Duration::from_secs(5)),
+            Duration::from_millis(10),
+            |_| panic!("nothing should be pending"),
+        );
+        assert_eq!(outcome.unwrap(), WaitOutcome::Synced);
+
+        let scans: Vec<_> = server
+            .recorded()
+            .into_iter()
+            .filter(|r| r.method == "POST")
+            .collect();
+        assert_eq!(scans.len(), 1);
+        assert!(scans[0].url.contains("/rest/db/scan?folder=docs"));
+    }
+
+    #[test]
+    fn test_wait_times_out_with_pending_folders() {
+        let server = server("syncing", 87.5);
+        let mut progress = Vec::new();
+        let outcome = wait(
+            &instances(&server),
+            &WaitFilter::default(),
+            Some(Duration::from_millis(50)),
+            Duration::from_millis(10),
+            |pending| progress.push(pending.to_vec()),
+        );
+
+        let expected = vec![
+            "Documents: syncing".to_string(),
+            "Documents on laptop: 87%".to_string(),
+        ];
+        assert_eq!(outcome.unwrap(), WaitOutcome::TimedOut(expected.clone()));
+        // Progress is only reported when it changes
+        assert_eq!(progress, vec![expected]);
+    }
+}